@@ -40,7 +40,7 @@ fn benchmark_spacecan_decoding(c: &mut Criterion) {
 fn benchmark_mesh_routing(c: &mut Criterion) {
     let mut group = c.benchmark_group("Mesh Network Routing");
     
-    let network = MeshNetwork::new();
+    let _network = MeshNetwork::new();
     
     group.bench_function("network_initialization", |b| {
         b.iter(|| black_box(MeshNetwork::new()))
@@ -51,7 +51,7 @@ fn benchmark_mesh_routing(c: &mut Criterion) {
 
 fn benchmark_encryption(c: &mut Criterion) {
     let mut group = c.benchmark_group("Cryptographic Operations");
-    let mut crypto = CryptoModule::new();
+    let crypto = CryptoModule::new();
     
     for size in [64, 256, 1024, 4096].iter() {
         let data = vec![0u8; *size];
@@ -72,7 +72,7 @@ fn benchmark_encryption(c: &mut Criterion) {
 
 fn benchmark_telemetry_processing(c: &mut Criterion) {
     let mut group = c.benchmark_group("Telemetry Processing");
-    let processor = TelemetryProcessor::new();
+    let _processor = TelemetryProcessor::new();
     
     group.bench_function("processor_initialization", |b| {
         b.iter(|| black_box(TelemetryProcessor::new()))
@@ -85,7 +85,7 @@ fn benchmark_cubesat_operations(c: &mut Criterion) {
     let mut group = c.benchmark_group("CubeSat Operations");
     
     group.bench_function("cubesat_initialization", |b| {
-        b.iter(|| black_box(CubeSatProtocol::new(1, "Benchmark-Sat".to_string())))
+        b.iter(|| black_box(CubeSatProtocol::new(1)))
     });
     
     group.finish();
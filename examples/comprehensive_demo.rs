@@ -3,8 +3,8 @@
 
 use rustsat_esa::*;
 use rustsat_esa::protocol::spacecan::{SpaceCANFrame, FramePriority, PowerMode};
-use rustsat_esa::protocol::network::{NetworkNode, NodeType, OrbitalPosition, MeshNetwork};
-use rustsat_esa::cubesat::{CubeSatFrame, FrameType, MissionConfig, CubeSatProtocol, MissionControl};
+use rustsat_esa::protocol::network::{NetworkNode, OrbitalPosition, MeshNetwork};
+use rustsat_esa::cubesat::{MissionConfig, CubeSatProtocol, MissionControl};
 use rustsat_esa::ground_station::{ESAGroundNetwork, CommandMessage, CommandType};
 use rustsat_esa::telemetry::{TelemetryProcessor, TelemetryData, TelemetryType, TelemetryValue};
 use rustsat_esa::security::{CryptoModule, Permission};
@@ -12,7 +12,6 @@ use rustsat_esa::simulation::{SpaceSimulator, ScenarioConfig};
 
 use chrono::{Utc, Duration};
 use std::collections::HashMap;
-use log::{info, warn, error};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logging
@@ -27,7 +26,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     demo_spacecan_protocol()?;
     
     // Demo 2: Mesh Network Routing
-    // demo_mesh_networking()?;
+    demo_mesh_networking()?;
     
     // Demo 3: CubeSat Mission Control
     demo_cubesat_mission_control()?;
@@ -42,7 +41,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     demo_telemetry_processing()?;
     
     // Demo 7: Complete Protocol Stack Integration
-    //demo_integrated_protocol_stack()?;
+    demo_integrated_protocol_stack()?;
     
     // Demo 8: Space Environment Simulation
     demo_space_simulation()?;
@@ -104,12 +103,12 @@ fn demo_mesh_networking() -> Result<(), Box<dyn std::error::Error>> {
         
         let mut node = NetworkNode::new_cubesat(i, position);
         node.communication_range = 1500.0; // Increase communication range to 1500 km
-        network.add_node(node);
+        network.add_node(node)?;
     }
     
     // Add ground station
-    let ground_station = NetworkNode::new_ground_station(100, 52.5, 13.4); // Berlin
-    network.add_node(ground_station);
+    let ground_station = NetworkNode::new_ground_station(100, 0.0, 0.0); // Under the constellation
+    network.add_node(ground_station)?;
     
     // Initialize routing
     network.initialize_routing()?;
@@ -294,6 +293,7 @@ fn demo_telemetry_processing() -> Result<(), Box<dyn std::error::Error>> {
             value: TelemetryValue::Float(85.5),
             quality: 0.95,
             sequence_number: 1,
+            raw_value: None,
         },
         TelemetryData {
             timestamp: Utc::now(),
@@ -302,6 +302,7 @@ fn demo_telemetry_processing() -> Result<(), Box<dyn std::error::Error>> {
             value: TelemetryValue::Float(23.7),
             quality: 0.92,
             sequence_number: 2,
+            raw_value: None,
         },
         TelemetryData {
             timestamp: Utc::now(),
@@ -310,6 +311,7 @@ fn demo_telemetry_processing() -> Result<(), Box<dyn std::error::Error>> {
             value: TelemetryValue::Float(0.98),
             quality: 0.99,
             sequence_number: 3,
+            raw_value: None,
         },
     ];
     
@@ -321,11 +323,13 @@ fn demo_telemetry_processing() -> Result<(), Box<dyn std::error::Error>> {
     println!("Processed {} telemetry data points", 3);
     
     // Create telemetry packet
-    let packet = telemetry_processor.create_telemetry_packet(1, 10)?;
-    println!("Created telemetry packet:");
-    println!("  Source: {}", packet.source_node);
-    println!("  Data points: {}", packet.data_points.len());
-    println!("  Compression: {:?}", packet.compression_type);
+    if let Some(packet) = telemetry_processor.create_telemetry_packet(1, 10)? {
+        println!("Created telemetry packet:");
+        println!("  Source: {}", packet.source_node);
+        println!("  Data points: {}", packet.unpack_body()?.len());
+        println!("  Compressed body: {} bytes", packet.compressed_body.len());
+        println!("  Compression: {:?}", packet.compression_type);
+    }
     
     // Get current mission events
     let current_events = telemetry_processor.get_current_events();
@@ -355,8 +359,8 @@ fn demo_integrated_protocol_stack() -> Result<(), Box<dyn std::error::Error>> {
     
     // Send a message through the complete stack
     let test_message = b"End-to-end protocol stack test message";
-    protocol_stack.send_message(100, test_message)?; // Send to ground station
-    println!("Message sent through complete protocol stack");
+    let report = protocol_stack.send_message(1, test_message)?; // Send to the mission CubeSat
+    println!("Message sent through complete protocol stack via {:?} ({} attempt(s))", report.route, report.attempts);
     
     // Attempt to receive messages
     if let Some(received) = protocol_stack.receive_message()? {
@@ -386,6 +390,7 @@ fn demo_space_simulation() -> Result<(), Box<dyn std::error::Error>> {
         communication_frequency: Duration::minutes(2),
         failure_probability: 0.02,
         space_weather_enabled: true,
+        checkpoint: None,
     };
     
     // Initialize and run simulation
@@ -44,7 +44,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Test security
     println!("\nTesting Security Module...");
-    let mut crypto = CryptoModule::new();
+    let crypto = CryptoModule::new();
     let test_message = b"Hello from CubeSat!";
     
     match crypto.encrypt(test_message) {
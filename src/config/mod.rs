@@ -1,13 +1,14 @@
 // Configuration management for the RustSat-ESA system
 // This demonstrates understanding of production configuration patterns
 
+use log::warn;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 use std::time::Duration;
-use chrono::{DateTime, Utc};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RustSatConfig {
     pub system: SystemConfig,
     pub network: NetworkConfig,
@@ -17,7 +18,7 @@ pub struct RustSatConfig {
     pub logging: LoggingConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SystemConfig {
     pub satellite_id: u32,
     pub mission_name: String,
@@ -27,7 +28,7 @@ pub struct SystemConfig {
     pub watchdog_timeout_ms: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NetworkConfig {
     pub max_hops: u8,
     pub routing_update_interval_ms: u64,
@@ -38,16 +39,22 @@ pub struct NetworkConfig {
     pub ground_station_priority: u8,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SecurityConfig {
     pub encryption_enabled: bool,
     pub key_rotation_interval_hours: u64,
     pub max_auth_failures: u32,
     pub auth_timeout_ms: u64,
     pub emergency_bypass_enabled: bool,
+    #[serde(default = "default_emergency_crc_enabled")]
+    pub emergency_crc_enabled: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_emergency_crc_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TelemetryConfig {
     pub collection_interval_ms: u64,
     pub compression_enabled: bool,
@@ -56,7 +63,7 @@ pub struct TelemetryConfig {
     pub data_retention_hours: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AlertThresholds {
     pub battery_low_percent: f64,
     pub temperature_high_celsius: f64,
@@ -65,7 +72,7 @@ pub struct AlertThresholds {
     pub signal_strength_low: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SimulationConfig {
     pub time_acceleration: f64,
     pub orbital_perturbations: bool,
@@ -74,7 +81,7 @@ pub struct SimulationConfig {
     pub realistic_delays: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LoggingConfig {
     pub level: String,
     pub file_enabled: bool,
@@ -109,6 +116,7 @@ impl Default for RustSatConfig {
                 max_auth_failures: 3,
                 auth_timeout_ms: 10000,
                 emergency_bypass_enabled: false,
+                emergency_crc_enabled: true,
             },
             telemetry: TelemetryConfig {
                 collection_interval_ms: 5000,
@@ -142,20 +150,47 @@ impl Default for RustSatConfig {
 }
 
 impl RustSatConfig {
+    /// Load and validate a config, choosing the format from the file extension
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let format = ConfigFormat::from_path(path.as_ref())?;
         let content = fs::read_to_string(path)
             .map_err(|e| ConfigError::FileRead(e.to_string()))?;
         
-        let config: RustSatConfig = serde_json::from_str(&content)
+        let config: RustSatConfig = format.parse(&content)?;
+        
+        config.validate()?;
+        Ok(config)
+    }
+    
+    /// Start from the defaults, apply whichever fields a config file at `path` sets (if it exists),
+    /// then overlay environment variables, validating only the merged result
+    pub fn load_layered<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let mut merged = serde_json::to_value(Self::default())
+            .map_err(|e| ConfigError::SerializeError(e.to_string()))?;
+        
+        if path.exists() {
+            let format = ConfigFormat::from_path(path)?;
+            let content = fs::read_to_string(path)
+                .map_err(|e| ConfigError::FileRead(e.to_string()))?;
+            merge_values(&mut merged, format.parse(&content)?);
+        }
+        
+        let mut config: RustSatConfig = serde_json::from_value(merged)
             .map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        config.apply_env();
         
         config.validate()?;
         Ok(config)
     }
     
+    /// Save the config in the format matching the file extension
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
-        let content = serde_json::to_string_pretty(self)
-            .map_err(|e| ConfigError::SerializeError(e.to_string()))?;
+        let content = match ConfigFormat::from_path(path.as_ref())? {
+            ConfigFormat::Json => serde_json::to_string_pretty(self).map_err(|e| ConfigError::SerializeError(e.to_string()))?,
+            ConfigFormat::Toml => toml::to_string_pretty(self).map_err(|e| ConfigError::SerializeError(e.to_string()))?,
+            ConfigFormat::Yaml => serde_yaml::to_string(self).map_err(|e| ConfigError::SerializeError(e.to_string()))?,
+        };
         
         fs::write(path, content)
             .map_err(|e| ConfigError::FileWrite(e.to_string()))?;
@@ -165,27 +200,48 @@ impl RustSatConfig {
     
     pub fn load_from_env() -> Self {
         let mut config = Self::default();
+        config.apply_env();
+        config
+    }
+    
+    /// Override scalar fields from environment variables. Each field maps to `RUSTSAT_` followed by
+    /// its path in upper case joined with `_`, e.g. `telemetry.alert_thresholds.battery_low_percent`
+    /// is read from `RUSTSAT_TELEMETRY_ALERT_THRESHOLDS_BATTERY_LOW_PERCENT`. Values that do not
+    /// parse as the field's type are logged and ignored.
+    fn apply_env(&mut self) {
+        let Ok(mut tree) = serde_json::to_value(&*self) else {
+            return;
+        };
+        let mut fields = Vec::new();
+        scalar_fields(&tree, &mut Vec::new(), &mut fields);
         
-        // Override with environment variables if present
-        if let Ok(sat_id) = std::env::var("RUSTSAT_SATELLITE_ID") {
-            if let Ok(id) = sat_id.parse() {
-                config.system.satellite_id = id;
+        for path in fields {
+            let name = env_var_name(&path);
+            let Some(raw) = std::env::var(&name).ok().or_else(|| legacy_env_var(&name)) else {
+                continue;
+            };
+            let pointer = format!("/{}", path.join("/"));
+            let Some(field) = tree.pointer_mut(&pointer) else {
+                continue;
+            };
+            let Some(parsed) = parse_env_value(field, &raw) else {
+                warn!("Ignoring {}={:?}: not a valid {}", name, raw, value_kind(field));
+                continue;
+            };
+            
+            // Values that parse but do not fit the field, such as 300 for a u8, are rejected too
+            let previous = std::mem::replace(field, parsed);
+            if let Err(e) = RustSatConfig::deserialize(&tree) {
+                warn!("Ignoring {}={:?}: {}", name, raw, e);
+                if let Some(field) = tree.pointer_mut(&pointer) {
+                    *field = previous;
+                }
             }
         }
         
-        if let Ok(mission_name) = std::env::var("RUSTSAT_MISSION_NAME") {
-            config.system.mission_name = mission_name;
+        if let Ok(config) = serde_json::from_value(tree) {
+            *self = config;
         }
-        
-        if let Ok(log_level) = std::env::var("RUSTSAT_LOG_LEVEL") {
-            config.logging.level = log_level;
-        }
-        
-        if let Ok(encryption) = std::env::var("RUSTSAT_ENCRYPTION_ENABLED") {
-            config.security.encryption_enabled = encryption.to_lowercase() == "true";
-        }
-        
-        config
     }
     
     pub fn validate(&self) -> Result<(), ConfigError> {
@@ -256,6 +312,118 @@ impl RustSatConfig {
     }
 }
 
+/// On-disk config formats, selected by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Files without an extension are read as JSON, the original format
+    fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        let Some(extension) = path.extension() else {
+            return Ok(Self::Json);
+        };
+        match extension.to_string_lossy().to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "toml" => Ok(Self::Toml),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            other => Err(ConfigError::UnsupportedFormat(format!(
+                "'.{}' (expected .json, .toml, .yaml or .yml)", other))),
+        }
+    }
+    
+    fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T, ConfigError> {
+        match self {
+            Self::Json => serde_json::from_str(content).map_err(|e| ConfigError::ParseError(e.to_string())),
+            Self::Toml => toml::from_str(content).map_err(|e| ConfigError::ParseError(e.to_string())),
+            Self::Yaml => serde_yaml::from_str(content).map_err(|e| ConfigError::ParseError(e.to_string())),
+        }
+    }
+}
+
+/// Variable names that predate the path-derived convention, with the names they alias
+const LEGACY_ENV_VARS: [(&str, &str); 4] = [
+    ("RUSTSAT_SATELLITE_ID", "RUSTSAT_SYSTEM_SATELLITE_ID"),
+    ("RUSTSAT_MISSION_NAME", "RUSTSAT_SYSTEM_MISSION_NAME"),
+    ("RUSTSAT_LOG_LEVEL", "RUSTSAT_LOGGING_LEVEL"),
+    ("RUSTSAT_ENCRYPTION_ENABLED", "RUSTSAT_SECURITY_ENCRYPTION_ENABLED"),
+];
+
+/// Environment variable overriding the field at `path`
+fn env_var_name(path: &[String]) -> String {
+    format!("RUSTSAT_{}", path.join("_").to_uppercase())
+}
+
+fn legacy_env_var(name: &str) -> Option<String> {
+    LEGACY_ENV_VARS.iter()
+        .find(|(_, canonical)| *canonical == name)
+        .and_then(|(legacy, _)| std::env::var(legacy).ok())
+}
+
+/// Collect the paths of every non-table value in a config tree
+fn scalar_fields(value: &serde_json::Value, path: &mut Vec<String>, fields: &mut Vec<Vec<String>>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map {
+                path.push(key.clone());
+                scalar_fields(child, path, fields);
+                path.pop();
+            }
+        }
+        serde_json::Value::Array(_) | serde_json::Value::Null => {}
+        _ => fields.push(path.clone()),
+    }
+}
+
+/// Parse `raw` as the same kind of value as `current`
+fn parse_env_value(current: &serde_json::Value, raw: &str) -> Option<serde_json::Value> {
+    let raw = raw.trim();
+    match current {
+        serde_json::Value::Bool(_) => match raw.to_lowercase().as_str() {
+            "true" => Some(true.into()),
+            "false" => Some(false.into()),
+            _ => None,
+        },
+        serde_json::Value::Number(n) if n.is_u64() => raw.parse::<u64>().ok().map(Into::into),
+        serde_json::Value::Number(n) if n.is_i64() => raw.parse::<i64>().ok().map(Into::into),
+        serde_json::Value::Number(_) => raw.parse::<f64>().ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(serde_json::Value::Number),
+        serde_json::Value::String(_) => Some(raw.into()),
+        _ => None,
+    }
+}
+
+fn value_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(n) if n.is_u64() => "unsigned integer",
+        serde_json::Value::Number(n) if n.is_i64() => "integer",
+        serde_json::Value::Number(_) => "number",
+        _ => "value",
+    }
+}
+
+/// Recursively overlay `patch` onto `base`; tables merge key by key, anything else replaces
+fn merge_values(base: &mut serde_json::Value, patch: serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("Failed to read config file: {0}")]
@@ -272,6 +440,9 @@ pub enum ConfigError {
     
     #[error("Configuration validation error: {0}")]
     ValidationError(String),
+    
+    #[error("Unsupported config file format: {0}")]
+    UnsupportedFormat(String),
 }
 
 // Configuration builder for programmatic config creation
@@ -326,8 +497,12 @@ impl Default for ConfigBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
     use tempfile::NamedTempFile;
     
+    /// Serializes tests that set process-wide environment variables
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+    
     #[test]
     fn test_default_config_is_valid() {
         let config = RustSatConfig::default();
@@ -367,6 +542,111 @@ mod tests {
         assert_eq!(original_json, loaded_json);
     }
     
+    #[test]
+    fn test_config_format_roundtrips() {
+        let dir = tempfile::tempdir().unwrap();
+        let original_config = ConfigBuilder::new()
+            .satellite_id(7)
+            .mission_name("Format Test")
+            .log_level("debug")
+            .build()
+            .unwrap();
+        
+        for name in ["config.json", "config.toml", "config.yaml", "config.yml"] {
+            let path = dir.path().join(name);
+            original_config.save_to_file(&path).unwrap();
+            let loaded_config = RustSatConfig::load_from_file(&path).unwrap();
+            assert_eq!(loaded_config, original_config, "{} did not round-trip", name);
+        }
+        
+        let toml_text = fs::read_to_string(dir.path().join("config.toml")).unwrap();
+        assert!(toml_text.contains("[system]"));
+        let yaml_text = fs::read_to_string(dir.path().join("config.yaml")).unwrap();
+        assert!(yaml_text.contains("system:"));
+    }
+    
+    #[test]
+    fn test_unknown_config_extension_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.ini");
+        let err = RustSatConfig::default().save_to_file(&path).unwrap_err();
+        assert!(matches!(err, ConfigError::UnsupportedFormat(_)));
+        assert!(err.to_string().contains(".ini"));
+        assert!(!path.exists());
+        
+        fs::write(&path, "satellite_id = 1").unwrap();
+        assert!(matches!(RustSatConfig::load_from_file(&path), Err(ConfigError::UnsupportedFormat(_))));
+    }
+    
+    #[test]
+    fn test_layered_config_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mission.toml");
+        fs::write(&path, "[system]\nsatellite_id = 5\nmission_name = \"File Mission\"\n\n[logging]\nlevel = \"warn\"\n").unwrap();
+        
+        // The file overrides only the fields it sets
+        let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::env::remove_var("RUSTSAT_MISSION_NAME");
+        let config = RustSatConfig::load_layered(&path).unwrap();
+        assert_eq!(config.system.satellite_id, 5);
+        assert_eq!(config.system.mission_name, "File Mission");
+        assert_eq!(config.logging.level, "warn");
+        assert_eq!(config.network, RustSatConfig::default().network);
+        assert_eq!(config.system.max_memory_mb, RustSatConfig::default().system.max_memory_mb);
+        
+        // Environment variables override the file
+        std::env::set_var("RUSTSAT_MISSION_NAME", "Env Mission");
+        let config = RustSatConfig::load_layered(&path).unwrap();
+        let missing = RustSatConfig::load_layered(dir.path().join("missing.toml")).unwrap();
+        std::env::remove_var("RUSTSAT_MISSION_NAME");
+        assert_eq!(config.system.mission_name, "Env Mission");
+        assert_eq!(config.system.satellite_id, 5);
+        assert_eq!(missing.system.mission_name, "Env Mission");
+        assert_eq!(missing.system.satellite_id, RustSatConfig::default().system.satellite_id);
+        
+        // Only the merged result is validated
+        fs::write(&path, "[system]\nsatellite_id = 0\n").unwrap();
+        assert!(matches!(RustSatConfig::load_layered(&path), Err(ConfigError::ValidationError(_))));
+    }
+    
+    #[test]
+    fn test_nested_env_overrides() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let vars = [
+            ("RUSTSAT_NETWORK_MAX_HOPS", "8"),
+            ("RUSTSAT_TELEMETRY_COLLECTION_INTERVAL_MS", "250"),
+            ("RUSTSAT_TELEMETRY_ALERT_THRESHOLDS_BATTERY_LOW_PERCENT", "35.5"),
+            ("RUSTSAT_SIMULATION_SPACE_WEATHER_ENABLED", "FALSE"),
+            ("RUSTSAT_LOGGING_LEVEL", "trace"),
+            ("RUSTSAT_MISSION_NAME", "Legacy Name"),
+            // Invalid values leave the defaults in place
+            ("RUSTSAT_NETWORK_RETRY_ATTEMPTS", "three"),
+            ("RUSTSAT_SYSTEM_MAX_CPU_PERCENT", "lots"),
+            ("RUSTSAT_SECURITY_ENCRYPTION_ENABLED", "maybe"),
+            ("RUSTSAT_NETWORK_GROUND_STATION_PRIORITY", "300"),
+        ];
+        for (name, value) in vars {
+            std::env::set_var(name, value);
+        }
+        let config = RustSatConfig::load_from_env();
+        for (name, _) in vars {
+            std::env::remove_var(name);
+        }
+        
+        let defaults = RustSatConfig::default();
+        assert_eq!(config.network.max_hops, 8);
+        assert_eq!(config.telemetry.collection_interval_ms, 250);
+        assert_eq!(config.telemetry.alert_thresholds.battery_low_percent, 35.5);
+        assert!(!config.simulation.space_weather_enabled);
+        assert_eq!(config.logging.level, "trace");
+        assert_eq!(config.system.mission_name, "Legacy Name");
+        assert_eq!(config.network.retry_attempts, defaults.network.retry_attempts);
+        assert_eq!(config.system.max_cpu_percent, defaults.system.max_cpu_percent);
+        assert_eq!(config.security.encryption_enabled, defaults.security.encryption_enabled);
+        assert_eq!(config.network.ground_station_priority, defaults.network.ground_station_priority);
+        assert_eq!(env_var_name(&["telemetry".to_string(), "max_buffer_size".to_string()]), "RUSTSAT_TELEMETRY_MAX_BUFFER_SIZE");
+    }
+    
     #[test]
     fn test_validation_errors() {
         let mut config = RustSatConfig::default();
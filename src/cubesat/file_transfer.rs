@@ -0,0 +1,232 @@
+// Chunked file transfer carried in FileTransfer frames
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use super::{CubeSatFrame, FrameType};
+
+/// Bytes of chunk header ahead of the file data: file id, offset, total size, chunk size
+pub const CHUNK_HEADER_LEN: usize = 14;
+/// Largest chunk that still fits the frame's 16-bit payload length
+pub const MAX_CHUNK_SIZE: usize = u16::MAX as usize - CHUNK_HEADER_LEN;
+
+/// One numbered piece of a file, as carried in a FileTransfer frame payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChunk {
+    pub file_id: u32,
+    pub offset: u32,
+    pub total_size: u32,
+    pub chunk_size: u16,
+    pub data: Vec<u8>,
+}
+
+/// Sending side of a transfer, able to re-send any chunk on request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSender {
+    pub file_id: u32,
+    pub source_id: u32,
+    pub destination_id: u32,
+    chunk_size: usize,
+    data: Vec<u8>,
+}
+
+/// Receiving side of a transfer, reassembling chunks in any order
+#[derive(Debug, Clone)]
+pub struct FileReceiver {
+    pub file_id: u32,
+    total_size: Option<u32>,
+    chunk_size: Option<u16>,
+    chunks: BTreeMap<u32, Vec<u8>>,
+}
+
+impl FileChunk {
+    /// Position of this chunk within the file
+    pub fn chunk_number(&self) -> u32 {
+        self.offset / self.chunk_size as u32
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(CHUNK_HEADER_LEN + self.data.len());
+        payload.extend_from_slice(&self.file_id.to_be_bytes());
+        payload.extend_from_slice(&self.offset.to_be_bytes());
+        payload.extend_from_slice(&self.total_size.to_be_bytes());
+        payload.extend_from_slice(&self.chunk_size.to_be_bytes());
+        payload.extend_from_slice(&self.data);
+        payload
+    }
+
+    pub fn from_frame(frame: &CubeSatFrame) -> Result<Self, String> {
+        if frame.frame_type != FrameType::FileTransfer {
+            return Err(format!("Expected a file transfer frame, got {:?}", frame.frame_type));
+        }
+        let payload = &frame.payload;
+        if payload.len() < CHUNK_HEADER_LEN {
+            return Err(format!("File chunk too short: {} bytes", payload.len()));
+        }
+
+        let word = |at: usize| u32::from_be_bytes([payload[at], payload[at + 1], payload[at + 2], payload[at + 3]]);
+        let chunk = Self {
+            file_id: word(0),
+            offset: word(4),
+            total_size: word(8),
+            chunk_size: u16::from_be_bytes([payload[12], payload[13]]),
+            data: payload[CHUNK_HEADER_LEN..].to_vec(),
+        };
+
+        if chunk.chunk_size == 0 || chunk.offset % chunk.chunk_size as u32 != 0 {
+            return Err(format!("Chunk offset {} is not aligned to chunk size {}", chunk.offset, chunk.chunk_size));
+        }
+        let expected_len = (chunk.total_size.saturating_sub(chunk.offset) as usize).min(chunk.chunk_size as usize);
+        if chunk.offset >= chunk.total_size || chunk.data.len() != expected_len {
+            return Err(format!("Chunk at offset {} does not fit a {} byte file", chunk.offset, chunk.total_size));
+        }
+        Ok(chunk)
+    }
+}
+
+impl FileSender {
+    pub fn new(file_id: u32, data: Vec<u8>, chunk_size: usize, source_id: u32, destination_id: u32) -> Result<Self, String> {
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(format!("Chunk size must be between 1 and {} bytes", MAX_CHUNK_SIZE));
+        }
+        if data.is_empty() || data.len() > u32::MAX as usize {
+            return Err(format!("Cannot transfer a file of {} bytes", data.len()));
+        }
+        Ok(Self { file_id, source_id, destination_id, chunk_size, data })
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        (self.data.len() + self.chunk_size - 1) / self.chunk_size
+    }
+
+    /// Frame carrying the chunk that starts at `offset`
+    pub fn chunk_frame(&self, offset: u32) -> Option<CubeSatFrame> {
+        let start = offset as usize;
+        if start >= self.data.len() || start % self.chunk_size != 0 {
+            return None;
+        }
+        let end = (start + self.chunk_size).min(self.data.len());
+        let chunk = FileChunk {
+            file_id: self.file_id,
+            offset,
+            total_size: self.data.len() as u32,
+            chunk_size: self.chunk_size as u16,
+            data: self.data[start..end].to_vec(),
+        };
+        Some(CubeSatFrame::new(FrameType::FileTransfer, chunk.encode(), self.source_id, self.destination_id))
+    }
+
+    /// Frames for every chunk of the file, in order
+    pub fn frames(&self) -> Vec<CubeSatFrame> {
+        (0..self.chunk_count())
+            .filter_map(|index| self.chunk_frame((index * self.chunk_size) as u32))
+            .collect()
+    }
+
+    /// Frames answering a NAK list, skipping offsets that do not start a chunk
+    pub fn retransmit(&self, offsets: &[u32]) -> Vec<CubeSatFrame> {
+        offsets.iter().filter_map(|&offset| self.chunk_frame(offset)).collect()
+    }
+}
+
+impl FileReceiver {
+    pub fn new(file_id: u32) -> Self {
+        Self {
+            file_id,
+            total_size: None,
+            chunk_size: None,
+            chunks: BTreeMap::new(),
+        }
+    }
+
+    /// Store one chunk, returning false if it was already received
+    pub fn ingest(&mut self, frame: &CubeSatFrame) -> Result<bool, String> {
+        let chunk = FileChunk::from_frame(frame)?;
+        if chunk.file_id != self.file_id {
+            return Err(format!("Chunk belongs to file {}, expected {}", chunk.file_id, self.file_id));
+        }
+        if self.total_size.is_some_and(|size| size != chunk.total_size)
+            || self.chunk_size.is_some_and(|size| size != chunk.chunk_size) {
+            return Err(format!("Chunk at offset {} disagrees with earlier chunks on the file layout", chunk.offset));
+        }
+
+        self.total_size = Some(chunk.total_size);
+        self.chunk_size = Some(chunk.chunk_size);
+        Ok(self.chunks.insert(chunk.offset, chunk.data).is_none())
+    }
+
+    /// Offsets of chunks not yet received; empty until the first chunk reveals the file layout
+    pub fn missing(&self) -> Vec<u32> {
+        let (Some(total_size), Some(chunk_size)) = (self.total_size, self.chunk_size) else {
+            return Vec::new();
+        };
+        (0..total_size)
+            .step_by(chunk_size as usize)
+            .filter(|offset| !self.chunks.contains_key(offset))
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.total_size.is_some() && self.missing().is_empty()
+    }
+
+    /// Reassemble the file once every chunk has arrived
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        if self.total_size.is_none() {
+            return Err(format!("No chunks received for file {}", self.file_id));
+        }
+        let missing = self.missing();
+        if !missing.is_empty() {
+            return Err(format!("File {} is missing {} chunks", self.file_id, missing.len()));
+        }
+        Ok(self.chunks.into_values().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cubesat::CubeSatProtocol;
+
+    #[test]
+    fn test_download_with_nak_retransmit() {
+        let data: Vec<u8> = (0..50 * 1024).map(|i| (i * 7 % 251) as u8).collect();
+        let mut satellite = CubeSatProtocol::new(3);
+        let frames = satellite.begin_download(42, data.clone(), 1024).unwrap();
+        assert_eq!(frames.len(), 50);
+
+        let mut receiver = FileReceiver::new(42);
+        for (index, frame) in frames.iter().enumerate() {
+            if index == 7 || index == 31 {
+                continue;
+            }
+            let received = CubeSatFrame::decode(&frame.encode()).unwrap();
+            assert!(receiver.ingest(&received).unwrap());
+        }
+        assert!(!receiver.ingest(&frames[0]).unwrap());
+
+        let nak = receiver.missing();
+        assert_eq!(nak, vec![7 * 1024, 31 * 1024]);
+        assert!(!receiver.is_complete());
+
+        for frame in satellite.retransmit_file_chunks(42, &nak).unwrap() {
+            receiver.ingest(&frame).unwrap();
+        }
+        assert!(receiver.missing().is_empty());
+        assert_eq!(receiver.finish().unwrap(), data);
+    }
+
+    #[test]
+    fn test_receiver_rejects_foreign_chunks() {
+        let sender = FileSender::new(1, vec![0xAB; 100], 30, 1, 0).unwrap();
+        let frames = sender.frames();
+        assert_eq!(frames.last().unwrap().payload.len(), CHUNK_HEADER_LEN + 10);
+
+        let mut receiver = FileReceiver::new(2);
+        assert!(receiver.ingest(&frames[0]).is_err());
+        assert!(FileReceiver::new(1).finish().is_err());
+
+        let mut truncated = frames[1].clone();
+        truncated.payload.pop();
+        assert!(FileChunk::from_frame(&truncated).is_err());
+        assert!(FileSender::new(1, vec![1], 0, 1, 0).is_err());
+    }
+}
@@ -1,10 +1,17 @@
 // CubeSat-specific protocol adaptations and mission control
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 use chrono::{DateTime, Utc, Duration};
-use log::{info, warn, error, debug};
+use log::{info, warn};
 use crate::protocol::network::OrbitalPosition;
-use crate::telemetry::{TelemetryData, TelemetryType, TelemetryValue, MissionEvent, EventType, EventStatus};
+use crate::simulation::{ground_state_eci, is_in_eclipse, sun_position_eci};
+use crate::telemetry::{CompressionType, TelemetryData, TelemetryPacket, TelemetryType, TelemetryValue};
+
+pub mod file_transfer;
+
+pub use file_transfer::{FileChunk, FileReceiver, FileSender};
 
 /// CubeSat frame with enhanced features
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -111,6 +118,7 @@ pub struct DataCollectionEvent {
 }
 
 /// CubeSat protocol implementation
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CubeSatProtocol {
     satellite_id: u32,
     mission_config: Option<MissionConfig>,
@@ -119,8 +127,34 @@ pub struct CubeSatProtocol {
     telemetry_buffer: Vec<TelemetryData>,
     beacon_counter: u32,
     last_ground_contact: Option<DateTime<Utc>>,
+    command_expiry_tolerance: Duration,
+    emergency_recovery: EmergencyRecoveryConfig,
+    emergency_mode: bool,
+    recovery_elapsed: Duration,
+    downlink_queue: Vec<TelemetryPacket>,
+    data_points_downlinked: u64,
+    active_payload: Option<ActivePayload>,
+    outgoing_files: HashMap<u32, FileSender>,
+    clock_bias: Duration,
+    pending_time_sync: Option<DateTime<Utc>>,
 }
 
+/// Payload operating mode currently running and when it was switched on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivePayload {
+    pub mode: OperatingMode,
+    pub activated_at: DateTime<Utc>,
+}
+
+/// Fraction of the power level drawn per watt of payload consumption while a mode is active
+pub const PAYLOAD_POWER_DRAW_PER_WATT: f64 = 0.02;
+
+/// Telemetry points packed by a `DataDownload` command when `max_points` is not given
+pub const DEFAULT_DOWNLOAD_POINTS: usize = 100;
+
+/// Default grace period after a command's scheduled execution before it is treated as stale
+pub const DEFAULT_COMMAND_EXPIRY_TOLERANCE_SECS: i64 = 300;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemState {
     pub power_level: f64,        // 0.0 to 1.0
@@ -132,6 +166,65 @@ pub struct SystemState {
     pub last_updated: DateTime<Utc>,
 }
 
+/// Conditions for leaving emergency mode automatically
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmergencyRecoveryConfig {
+    pub min_power_level: f64,
+    pub min_system_health: f64,
+    pub sustain_period: Duration,
+}
+
+impl Default for EmergencyRecoveryConfig {
+    fn default() -> Self {
+        Self {
+            min_power_level: 0.6,
+            min_system_health: 0.9,
+            sustain_period: Duration::minutes(5),
+        }
+    }
+}
+
+/// Nominal ranges checked by the commissioning self-test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestLimits {
+    pub min_power_level: f64,
+    pub min_temperature: f64,
+    pub max_temperature: f64,
+    pub min_system_health: f64,
+}
+
+impl Default for SelfTestLimits {
+    fn default() -> Self {
+        Self {
+            min_power_level: 0.5,
+            min_temperature: -20.0,
+            max_temperature: 60.0,
+            min_system_health: 0.7,
+        }
+    }
+}
+
+/// Result of the commissioning self-test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub power_ok: bool,
+    pub thermal_ok: bool,
+    pub comm_ok: bool,
+    pub payload_ok: bool,
+    pub overall: bool,
+}
+
+/// Decoded contents of a beacon frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BeaconReport {
+    pub satellite_id: u32,
+    pub beacon_number: u32,
+    pub power_percent: f64,
+    pub temperature_c: f64,
+    pub health: f64,
+    pub uptime_secs: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CubeSatCommand {
     pub command_id: u32,
@@ -154,6 +247,13 @@ pub enum CommandType {
     EmergencyMode,
 }
 
+impl CommandType {
+    /// Whether the command may run while the satellite is in emergency mode
+    pub fn is_essential(&self) -> bool {
+        !matches!(self, CommandType::PayloadActivate | CommandType::AttitudeControl)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CommandStatus {
     Queued,
@@ -161,17 +261,20 @@ pub enum CommandStatus {
     Completed,
     Failed,
     Cancelled,
+    Expired,
 }
 
 /// Mission control system for CubeSat operations
 pub struct MissionControl {
     satellites: HashMap<u32, CubeSatProtocol>,
-    mission_timeline: Vec<MissionEvent>,
-    ground_contacts: Vec<GroundContact>,
     emergency_procedures: HashMap<EmergencyType, EmergencyProcedure>,
     statistics: MissionStatistics,
+    next_emergency_command_id: u32,
 }
 
+/// First command id handed out to commands generated by emergency procedures
+pub const EMERGENCY_COMMAND_ID_BASE: u32 = 0xFFFF_0000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GroundContact {
     pub contact_id: u32,
@@ -184,7 +287,7 @@ pub struct GroundContact {
     pub signal_quality: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EmergencyType {
     PowerCritical,
     ThermalEmergency,
@@ -314,6 +417,40 @@ impl CubeSatFrame {
     }
 }
 
+impl BeaconReport {
+    /// Parse the `BEACON:n;PWR:..;TEMP:..;HEALTH:..;UPTIME:..` payload written by `generate_beacon`
+    pub fn parse(frame: &CubeSatFrame) -> Result<Self, String> {
+        if frame.frame_type != FrameType::Beacon {
+            return Err(format!("Expected a beacon frame, got {:?}", frame.frame_type));
+        }
+        let text = std::str::from_utf8(&frame.payload)
+            .map_err(|_| "Beacon payload is not valid UTF-8".to_string())?;
+
+        let mut fields = HashMap::new();
+        for field in text.split(';') {
+            let (key, value) = field.split_once(':')
+                .ok_or(format!("Malformed beacon field '{}'", field))?;
+            if fields.insert(key, value).is_some() {
+                return Err(format!("Duplicate beacon field {}", key));
+            }
+        }
+
+        fn field<T: std::str::FromStr>(fields: &HashMap<&str, &str>, key: &str) -> Result<T, String> {
+            let value = fields.get(key).ok_or(format!("Beacon is missing {}", key))?;
+            value.parse().map_err(|_| format!("Invalid beacon {} value '{}'", key, value))
+        }
+
+        Ok(Self {
+            satellite_id: frame.source_id,
+            beacon_number: field(&fields, "BEACON")?,
+            power_percent: field(&fields, "PWR")?,
+            temperature_c: field(&fields, "TEMP")?,
+            health: field(&fields, "HEALTH")?,
+            uptime_secs: field(&fields, "UPTIME")?,
+        })
+    }
+}
+
 impl CubeSatProtocol {
     pub fn new(satellite_id: u32) -> Self {
         Self {
@@ -337,7 +474,189 @@ impl CubeSatProtocol {
             telemetry_buffer: Vec::new(),
             beacon_counter: 0,
             last_ground_contact: None,
+            command_expiry_tolerance: Duration::seconds(DEFAULT_COMMAND_EXPIRY_TOLERANCE_SECS),
+            emergency_recovery: EmergencyRecoveryConfig::default(),
+            emergency_mode: false,
+            recovery_elapsed: Duration::zero(),
+            downlink_queue: Vec::new(),
+            data_points_downlinked: 0,
+            active_payload: None,
+            outgoing_files: HashMap::new(),
+            clock_bias: Duration::zero(),
+            pending_time_sync: None,
+        }
+    }
+
+    /// Set how long after its scheduled time a command may still be executed
+    pub fn set_command_expiry_tolerance(&mut self, tolerance: Duration) {
+        self.command_expiry_tolerance = tolerance;
+    }
+
+    /// Check whether a command missed its execution window
+    pub fn is_command_expired(&self, command: &CubeSatCommand, now: DateTime<Utc>) -> bool {
+        command.scheduled_execution
+            .map(|scheduled| scheduled + self.command_expiry_tolerance < now)
+            .unwrap_or(false)
+    }
+
+    /// Execute a command unless it is stale, returning it with its final status
+    pub fn dispatch_command(&mut self, mut command: CubeSatCommand, now: DateTime<Utc>) -> CubeSatCommand {
+        if self.is_command_expired(&command, now) {
+            warn!("Command {} expired before execution (scheduled {:?})",
+                  command.command_id, command.scheduled_execution);
+            command.status = CommandStatus::Expired;
+            return command;
+        }
+
+        command.status = CommandStatus::Executing;
+        command.status = match self.execute_command(command.clone()) {
+            Ok(()) => CommandStatus::Completed,
+            Err(e) => {
+                warn!("Command {} failed: {}", command.command_id, e);
+                CommandStatus::Failed
+            }
+        };
+        command
+    }
+
+    /// Configure when the satellite leaves emergency mode on its own
+    pub fn set_emergency_recovery(&mut self, config: EmergencyRecoveryConfig) {
+        self.emergency_recovery = config;
+    }
+
+    /// Whether the satellite is currently in emergency mode
+    pub fn is_in_emergency_mode(&self) -> bool {
+        self.emergency_mode
+    }
+
+    /// Queue a command for execution at its scheduled time, higher priority first among equal times.
+    /// Commands that are already due run immediately and are returned with their final status.
+    pub fn enqueue_command(&mut self, command: CubeSatCommand) -> Option<CubeSatCommand> {
+        self.enqueue_command_at(command, Utc::now())
+    }
+
+    /// Queue a command, treating `now` as the current time when deciding whether it is already due
+    pub fn enqueue_command_at(&mut self, mut command: CubeSatCommand, now: DateTime<Utc>) -> Option<CubeSatCommand> {
+        if command.scheduled_execution.map(|t| t <= now).unwrap_or(true) {
+            return Some(self.dispatch_command(command, now));
+        }
+
+        command.status = CommandStatus::Queued;
+        let runs_after = |queued: &CubeSatCommand| {
+            (queued.scheduled_execution, std::cmp::Reverse(queued.priority))
+                > (command.scheduled_execution, std::cmp::Reverse(command.priority))
+        };
+        let position = self.command_queue.iter()
+            .position(runs_after)
+            .unwrap_or(self.command_queue.len());
+        self.command_queue.insert(position, command);
+        None
+    }
+
+    /// Execute every queued command that is due in schedule order, returning them with their final status
+    pub fn tick(&mut self, now: DateTime<Utc>) -> Vec<CubeSatCommand> {
+        let (due, pending): (Vec<_>, Vec<_>) = self.command_queue.drain(..)
+            .partition(|command| command.scheduled_execution.map(|t| t <= now).unwrap_or(true));
+        self.command_queue = pending;
+
+        let executed = due.into_iter()
+            .map(|command| self.dispatch_command(command, now))
+            .collect();
+        self.enforce_payload_duration_limit(now);
+        executed
+    }
+
+    /// Payload mode that is currently running, if any
+    pub fn active_payload(&self) -> Option<&ActivePayload> {
+        self.active_payload.as_ref()
+    }
+
+    /// Switch the payload off once its mode has run for longer than its `duration_limit`
+    fn enforce_payload_duration_limit(&mut self, now: DateTime<Utc>) {
+        let expired = self.active_payload.as_ref().is_some_and(|active| {
+            active.mode.duration_limit
+                .is_some_and(|limit| now - active.activated_at >= limit)
+        });
+        if !expired {
+            return;
+        }
+        if let Some(active) = self.deactivate_payload() {
+            warn!("Payload mode {} reached its duration limit of {} minutes, deactivated",
+                  active.mode.mode_name,
+                  active.mode.duration_limit.map(|d| d.num_minutes()).unwrap_or(0));
+        }
+    }
+
+    /// Turn on a configured payload mode, replacing any mode already running
+    fn activate_payload(&mut self, mode_name: &str, now: DateTime<Utc>) -> Result<(), String> {
+        if self.emergency_mode {
+            return Err("Payload activation refused while in emergency mode".to_string());
+        }
+        let config = self.mission_config.as_ref()
+            .ok_or(format!("No operating mode named '{}' is configured", mode_name))?;
+        let mode = config.payload_config.operating_modes.iter()
+            .find(|mode| mode.mode_name == mode_name)
+            .cloned()
+            .ok_or(format!("No operating mode named '{}' is configured", mode_name))?;
+
+        let budget = config.power_budget.payload_power;
+        if !mode.power_consumption.is_finite() || mode.power_consumption < 0.0 {
+            return Err(format!("Payload mode '{}' has invalid power draw {} W", mode_name, mode.power_consumption));
         }
+        if mode.power_consumption > budget {
+            return Err(format!("Payload mode '{}' draws {} W, over the {} W payload budget",
+                mode_name, mode.power_consumption, budget));
+        }
+        // Power the running mode would give back when it is replaced
+        let reclaimed = self.active_payload.as_ref()
+            .map_or(0.0, |active| active.mode.power_consumption * PAYLOAD_POWER_DRAW_PER_WATT);
+        let draw = mode.power_consumption * PAYLOAD_POWER_DRAW_PER_WATT;
+        if draw > self.system_state.power_level + reclaimed {
+            return Err(format!("Not enough power left to run payload mode '{}'", mode_name));
+        }
+
+        self.deactivate_payload();
+        self.system_state.power_level -= draw;
+        self.active_payload = Some(ActivePayload { mode, activated_at: now });
+        Ok(())
+    }
+
+    /// Turn off the running payload mode and give back the power it was drawing
+    fn deactivate_payload(&mut self) -> Option<ActivePayload> {
+        let active = self.active_payload.take()?;
+        self.system_state.power_level = (self.system_state.power_level
+            + active.mode.power_consumption * PAYLOAD_POWER_DRAW_PER_WATT).min(1.0);
+        Some(active)
+    }
+
+    /// Commands waiting for execution, in scheduled order
+    pub fn queued_commands(&self) -> &[CubeSatCommand] {
+        &self.command_queue
+    }
+
+    /// Persist the command queue so scheduled commands survive a reboot
+    pub fn save_command_queue<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.command_queue)
+            .map_err(|e| format!("Failed to serialize command queue: {}", e))?;
+        fs::write(path, content)
+            .map_err(|e| format!("Failed to write command queue: {}", e))
+    }
+
+    /// Restore a persisted command queue, merging it with any commands already queued
+    pub fn load_command_queue<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read command queue: {}", e))?;
+        let commands: Vec<CubeSatCommand> = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse command queue: {}", e))?;
+
+        let count = commands.len();
+        for command in commands {
+            if let Some(executed) = self.enqueue_command(command) {
+                info!("Restored command {} was already due: {:?}", executed.command_id, executed.status);
+            }
+        }
+        info!("Restored {} queued commands for satellite {}", count, self.satellite_id);
+        Ok(count)
     }
 
     pub fn configure_mission(&mut self, config: MissionConfig) -> Result<(), String> {
@@ -353,20 +672,32 @@ impl CubeSatProtocol {
     pub fn execute_command(&mut self, command: CubeSatCommand) -> Result<(), String> {
         info!("Executing command {} of type {:?}", command.command_id, command.command_type);
 
+        if self.emergency_mode && !command.command_type.is_essential() {
+            return Err(format!("{:?} refused while in emergency mode", command.command_type));
+        }
+
         match command.command_type {
             CommandType::SystemReboot => {
+                let now = Utc::now();
+                // Only future-scheduled commands survive a reboot
+                let queued = self.command_queue.len();
+                self.command_queue.retain(|queued| queued.scheduled_execution.map(|t| t > now).unwrap_or(false));
+                if self.command_queue.len() < queued {
+                    warn!("Reboot discarded {} unscheduled commands", queued - self.command_queue.len());
+                }
                 self.system_state.uptime = Duration::zero();
-                self.system_state.last_updated = Utc::now();
-                info!("System rebooted");
+                self.system_state.last_updated = now;
+                info!("System rebooted with {} commands still queued", self.command_queue.len());
             },
             CommandType::PayloadActivate => {
-                // Simulate payload activation
-                self.system_state.power_level -= 0.1; // Increased power consumption
-                info!("Payload activated");
+                let mode = command.parameters.get("mode")
+                    .ok_or("PayloadActivate requires a mode parameter")?;
+                self.activate_payload(mode, Utc::now())?;
+                info!("Payload activated in mode {}", mode);
             },
             CommandType::PayloadDeactivate => {
-                self.system_state.power_level += 0.05; // Reduced power consumption
-                info!("Payload deactivated");
+                let active = self.deactivate_payload().ok_or("No payload mode is active")?;
+                info!("Payload mode {} deactivated", active.mode.mode_name);
             },
             CommandType::AttitudeControl => {
                 if let (Some(roll), Some(pitch), Some(yaw)) = (
@@ -394,77 +725,197 @@ impl CubeSatProtocol {
             },
             CommandType::EmergencyMode => {
                 self.system_state.power_level = 0.2; // Minimal power
+                self.emergency_mode = true;
+                self.recovery_elapsed = Duration::zero();
                 warn!("Entered emergency mode");
             },
-            _ => {
-                debug!("Command type {:?} not fully implemented", command.command_type);
-            }
+            CommandType::DataDownload => {
+                let max_points = match command.parameters.get("max_points") {
+                    Some(value) => value.parse::<usize>().ok().filter(|&n| n > 0)
+                        .ok_or(format!("Invalid max_points: {}", value))?,
+                    None => DEFAULT_DOWNLOAD_POINTS,
+                };
+                let count = max_points.min(self.telemetry_buffer.len());
+                if count == 0 {
+                    return Err("No telemetry buffered for download".to_string());
+                }
+
+                let data_points: Vec<TelemetryData> = self.telemetry_buffer.drain(..count).collect();
+                self.data_points_downlinked += count as u64;
+                self.downlink_queue.push(TelemetryPacket {
+                    packet_id: rand::random::<u32>(),
+                    source_node: self.satellite_id,
+                    timestamp: self.clock_time(),
+                    data_points,
+                    compression_type: CompressionType::None,
+                    priority: command.priority,
+                    fragment_index: 0,
+                    fragment_total: 1,
+                    compressed_body: Vec::new(),
+                });
+                info!("Packed {} telemetry points for downlink", count);
+            },
+            CommandType::ConfigUpdate => {
+                let mut config = self.mission_config.clone()
+                    .ok_or("No mission configured to update")?;
+                for (key, value) in &command.parameters {
+                    apply_config_parameter(&mut config, key, value)?;
+                }
+                self.mission_config = Some(config);
+                info!("Updated mission configuration: {:?}", command.parameters.keys().collect::<Vec<_>>());
+            },
         }
 
         Ok(())
     }
 
+    /// Take telemetry packets produced by `DataDownload` commands for transmission
+    pub fn take_downlink_packets(&mut self) -> Vec<TelemetryPacket> {
+        std::mem::take(&mut self.downlink_queue)
+    }
+
+    /// Total telemetry points packed for downlink
+    pub fn data_points_downlinked(&self) -> u64 {
+        self.data_points_downlinked
+    }
+
+    /// Split a file into FileTransfer frames for the ground, keeping it for retransmission
+    pub fn begin_download(&mut self, file_id: u32, data: Vec<u8>, chunk_size: usize) -> Result<Vec<CubeSatFrame>, String> {
+        let sender = FileSender::new(file_id, data, chunk_size, self.satellite_id, 0)?;
+        let frames = sender.frames();
+        info!("Starting download of file {} in {} chunks", file_id, frames.len());
+        self.outgoing_files.insert(file_id, sender);
+        Ok(frames)
+    }
+
+    /// Re-send the chunks a ground receiver reported missing
+    pub fn retransmit_file_chunks(&self, file_id: u32, offsets: &[u32]) -> Result<Vec<CubeSatFrame>, String> {
+        let sender = self.outgoing_files.get(&file_id)
+            .ok_or(format!("No download in progress for file {}", file_id))?;
+        Ok(sender.retransmit(offsets))
+    }
+
+    /// Forget a download once the ground has confirmed it
+    pub fn complete_download(&mut self, file_id: u32) -> bool {
+        self.outgoing_files.remove(&file_id).is_some()
+    }
+
+    pub fn mission_config(&self) -> Option<&MissionConfig> {
+        self.mission_config.as_ref()
+    }
+
+    /// Onboard clock, corrected by the bias learned from time synchronisation
+    pub fn clock_time(&self) -> DateTime<Utc> {
+        Utc::now() + self.clock_bias
+    }
+
+    /// Correction currently applied to the onboard clock
+    pub fn clock_bias(&self) -> Duration {
+        self.clock_bias
+    }
+
+    /// Start a two-way time sync, returning the request frame stamped with the onboard send time
+    pub fn request_time_sync(&mut self) -> CubeSatFrame {
+        let sent_at = self.clock_time();
+        self.pending_time_sync = Some(sent_at);
+
+        let mut payload = sent_at.timestamp().to_be_bytes().to_vec();
+        payload.extend_from_slice(&sent_at.timestamp_subsec_nanos().to_be_bytes());
+        let mut frame = CubeSatFrame::new(FrameType::TimeSync, payload, self.satellite_id, 0);
+        frame.timestamp = sent_at;
+        frame
+    }
+
+    /// Complete a time sync from the ground's reply time and the onboard time it was received.
+    /// The ground time is compared against the midpoint of the round trip, so the propagation
+    /// delay in each direction cancels out. Returns the offset that was applied to the clock.
+    pub fn handle_time_sync(&mut self, ground_time: DateTime<Utc>, rx_time: DateTime<Utc>) -> Result<Duration, String> {
+        let sent_at = self.pending_time_sync
+            .ok_or("No time sync request is outstanding")?;
+        let round_trip = rx_time - sent_at;
+        if round_trip < Duration::zero() {
+            return Err("Time sync reply was received before the request was sent".to_string());
+        }
+
+        self.pending_time_sync = None;
+        let offset = ground_time - (sent_at + round_trip / 2);
+        self.clock_bias += offset;
+        info!("Time sync: clock offset {} ms over a {} ms round trip",
+              offset.num_milliseconds(), round_trip.num_milliseconds());
+        Ok(offset)
+    }
+
+    /// Replace the on-board system state, e.g. with one driven by an external simulation
+    pub fn set_system_state(&mut self, state: SystemState) {
+        self.system_state = state;
+    }
+
     pub fn generate_telemetry(&mut self) -> Vec<TelemetryData> {
-        let now = Utc::now();
-        let mut telemetry = Vec::new();
-
-        // System health telemetry
-        telemetry.push(TelemetryData {
-            timestamp: now,
-            source_node: self.satellite_id,
-            data_type: TelemetryType::SystemHealth,
-            value: TelemetryValue::Float(self.system_state.system_health),
-            quality: 0.95,
-            sequence_number: self.telemetry_buffer.len() as u64,
-        });
+        let now = self.clock_time();
+        let telemetry = vec![
+            // System health telemetry
+            TelemetryData {
+                timestamp: now,
+                source_node: self.satellite_id,
+                data_type: TelemetryType::SystemHealth,
+                value: TelemetryValue::Float(self.system_state.system_health),
+                quality: 0.95,
+                sequence_number: self.telemetry_buffer.len() as u64,
+                raw_value: None,
+            },
 
-        // Power status telemetry
-        telemetry.push(TelemetryData {
-            timestamp: now,
-            source_node: self.satellite_id,
-            data_type: TelemetryType::PowerStatus,
-            value: TelemetryValue::Float(self.system_state.power_level * 100.0),
-            quality: 0.98,
-            sequence_number: self.telemetry_buffer.len() as u64 + 1,
-        });
+            // Power status telemetry
+            TelemetryData {
+                timestamp: now,
+                source_node: self.satellite_id,
+                data_type: TelemetryType::PowerStatus,
+                value: TelemetryValue::Float(self.system_state.power_level * 100.0),
+                quality: 0.98,
+                sequence_number: self.telemetry_buffer.len() as u64 + 1,
+                raw_value: None,
+            },
 
-        // Temperature telemetry
-        telemetry.push(TelemetryData {
-            timestamp: now,
-            source_node: self.satellite_id,
-            data_type: TelemetryType::Temperature,
-            value: TelemetryValue::Float(self.system_state.temperature),
-            quality: 0.92,
-            sequence_number: self.telemetry_buffer.len() as u64 + 2,
-        });
+            // Temperature telemetry
+            TelemetryData {
+                timestamp: now,
+                source_node: self.satellite_id,
+                data_type: TelemetryType::Temperature,
+                value: TelemetryValue::Float(self.system_state.temperature),
+                quality: 0.92,
+                sequence_number: self.telemetry_buffer.len() as u64 + 2,
+                raw_value: None,
+            },
 
-        // Attitude telemetry
-        telemetry.push(TelemetryData {
-            timestamp: now,
-            source_node: self.satellite_id,
-            data_type: TelemetryType::Attitude,
-            value: TelemetryValue::Vector3D(
-                self.system_state.attitude.0,
-                self.system_state.attitude.1,
-                self.system_state.attitude.2,
-            ),
-            quality: 0.90,
-            sequence_number: self.telemetry_buffer.len() as u64 + 3,
-        });
+            // Attitude telemetry
+            TelemetryData {
+                timestamp: now,
+                source_node: self.satellite_id,
+                data_type: TelemetryType::Attitude,
+                value: TelemetryValue::Vector3D(
+                    self.system_state.attitude.0,
+                    self.system_state.attitude.1,
+                    self.system_state.attitude.2,
+                ),
+                quality: 0.90,
+                sequence_number: self.telemetry_buffer.len() as u64 + 3,
+                raw_value: None,
+            },
 
-        // Orbital position telemetry
-        telemetry.push(TelemetryData {
-            timestamp: now,
-            source_node: self.satellite_id,
-            data_type: TelemetryType::OrbitPosition,
-            value: TelemetryValue::Vector3D(
-                self.system_state.position.latitude,
-                self.system_state.position.longitude,
-                self.system_state.position.altitude,
-            ),
-            quality: 0.88,
-            sequence_number: self.telemetry_buffer.len() as u64 + 4,
-        });
+            // Orbital position telemetry
+            TelemetryData {
+                timestamp: now,
+                source_node: self.satellite_id,
+                data_type: TelemetryType::OrbitPosition,
+                value: TelemetryValue::Vector3D(
+                    self.system_state.position.latitude,
+                    self.system_state.position.longitude,
+                    self.system_state.position.altitude,
+                ),
+                quality: 0.88,
+                sequence_number: self.telemetry_buffer.len() as u64 + 4,
+                raw_value: None,
+            },
+        ];
 
         // Add to buffer
         self.telemetry_buffer.extend(telemetry.clone());
@@ -489,12 +940,14 @@ impl CubeSatProtocol {
             self.system_state.uptime.num_seconds()
         );
 
-        CubeSatFrame::new(
+        let mut beacon = CubeSatFrame::new(
             FrameType::Beacon,
             beacon_data.into_bytes(),
             self.satellite_id,
             0, // Broadcast
-        )
+        );
+        beacon.timestamp = self.clock_time();
+        beacon
     }
 
     pub fn update_system_state(&mut self, time_delta: Duration) {
@@ -507,7 +960,7 @@ impl CubeSatProtocol {
         let solar_charging = if self.is_in_sunlight() { 0.002 } else { 0.0 };
         
         self.system_state.power_level = (self.system_state.power_level - power_consumption + solar_charging)
-            .max(0.0).min(1.0);
+            .clamp(0.0, 1.0);
 
         // Simulate temperature variations
         let temp_variation = (rand::random::<f64>() - 0.5) * 2.0; // ±1°C
@@ -519,6 +972,10 @@ impl CubeSatProtocol {
         
         self.system_state.system_health = (power_health * temp_health).min(1.0);
 
+        if self.emergency_mode {
+            self.update_emergency_recovery(time_delta);
+        }
+
         // Update orbital position (simplified)
         let orbital_period = 90.0 * 60.0; // 90 minutes in seconds
         let angular_velocity = 360.0 / orbital_period; // degrees per second
@@ -530,17 +987,75 @@ impl CubeSatProtocol {
         }
     }
 
+    /// Leave emergency mode once power and health stay above the recovery thresholds
+    fn update_emergency_recovery(&mut self, time_delta: Duration) {
+        let recovered = self.system_state.power_level >= self.emergency_recovery.min_power_level
+            && self.system_state.system_health >= self.emergency_recovery.min_system_health;
+
+        if !recovered {
+            self.recovery_elapsed = Duration::zero();
+            return;
+        }
+
+        self.recovery_elapsed += time_delta;
+        if self.recovery_elapsed >= self.emergency_recovery.sustain_period {
+            self.emergency_mode = false;
+            self.recovery_elapsed = Duration::zero();
+            info!("Satellite {} recovered from emergency mode, resuming normal operation", self.satellite_id);
+        }
+    }
+
     fn is_in_sunlight(&self) -> bool {
-        // Simplified sunlight calculation based on orbital position
-        // In reality, this would consider Earth's shadow
-        let sun_angle = (self.system_state.position.longitude + 180.0) % 360.0;
-        sun_angle < 180.0 // Simplified: half the orbit is in sunlight
+        let now = self.clock_time();
+        let position = &self.system_state.position;
+        let position_eci = ground_state_eci(position.latitude, position.longitude, position.altitude * 1000.0, now).position_eci;
+        !is_in_eclipse(position_eci, sun_position_eci(now))
     }
 
     pub fn get_system_state(&self) -> &SystemState {
         &self.system_state
     }
 
+    /// Mission this satellite is configured for, if any
+    pub fn mission_id(&self) -> Option<&str> {
+        self.mission_config.as_ref().map(|config| config.mission_id.as_str())
+    }
+
+    /// Run the commissioning self-test against default nominal ranges
+    pub fn run_self_test(&self) -> SelfTestReport {
+        self.run_self_test_with(&SelfTestLimits::default())
+    }
+
+    /// Run the commissioning self-test against the given nominal ranges
+    pub fn run_self_test_with(&self, limits: &SelfTestLimits) -> SelfTestReport {
+        let state = &self.system_state;
+
+        let power_ok = state.power_level >= limits.min_power_level;
+        let thermal_ok = state.temperature >= limits.min_temperature
+            && state.temperature <= limits.max_temperature;
+
+        // Loop a test frame through the encoder to exercise the comm stack
+        let test_frame = CubeSatFrame::new(FrameType::Beacon, b"SELFTEST".to_vec(), self.satellite_id, 0);
+        let comm_ok = CubeSatFrame::decode(&test_frame.encode())
+            .map(|decoded| decoded.payload == test_frame.payload)
+            .unwrap_or(false);
+
+        // Payload modes must fit the payload power budget
+        let payload_within_budget = self.mission_config.as_ref()
+            .map(|config| config.payload_config.operating_modes.iter()
+                .all(|mode| mode.power_consumption <= config.power_budget.payload_power))
+            .unwrap_or(true);
+        let payload_ok = payload_within_budget && state.system_health >= limits.min_system_health;
+
+        let overall = power_ok && thermal_ok && comm_ok && payload_ok;
+        if !overall {
+            warn!("Satellite {} self-test failed: power={} thermal={} comm={} payload={}",
+                  self.satellite_id, power_ok, thermal_ok, comm_ok, payload_ok);
+        }
+
+        SelfTestReport { power_ok, thermal_ok, comm_ok, payload_ok, overall }
+    }
+
     pub fn get_telemetry_buffer(&self) -> &[TelemetryData] {
         &self.telemetry_buffer
     }
@@ -550,10 +1065,9 @@ impl MissionControl {
     pub fn new() -> Self {
         Self {
             satellites: HashMap::new(),
-            mission_timeline: Vec::new(),
-            ground_contacts: Vec::new(),
             emergency_procedures: HashMap::new(),
             statistics: MissionStatistics::default(),
+            next_emergency_command_id: EMERGENCY_COMMAND_ID_BASE,
         }
     }
 
@@ -568,6 +1082,9 @@ impl MissionControl {
 
     pub fn send_command_to_satellite(&mut self, satellite_id: u32, command: CubeSatCommand) -> Result<(), String> {
         if let Some(satellite) = self.satellites.get_mut(&satellite_id) {
+            if satellite.is_command_expired(&command, Utc::now()) {
+                return Err(format!("Command {} expired before execution", command.command_id));
+            }
             satellite.execute_command(command)?;
             self.statistics.total_commands_executed += 1;
             Ok(())
@@ -576,6 +1093,38 @@ impl MissionControl {
         }
     }
 
+    /// Ids of satellites belonging to a mission, sorted
+    pub fn satellites_in_mission(&self, mission_id: &str) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.satellites.iter()
+            .filter(|(_, satellite)| satellite.mission_id() == Some(mission_id))
+            .map(|(&id, _)| id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Send a command on behalf of a mission, rejecting satellites outside it
+    pub fn send_mission_command(&mut self, mission_id: &str, satellite_id: u32, command: CubeSatCommand) -> Result<(), String> {
+        let satellite = self.satellites.get(&satellite_id)
+            .ok_or(format!("Satellite {} not found", satellite_id))?;
+
+        if satellite.mission_id() != Some(mission_id) {
+            warn!("Rejected command {} from mission {} to satellite {} outside the mission",
+                  command.command_id, mission_id, satellite_id);
+            return Err(format!("Satellite {} does not belong to mission {}", satellite_id, mission_id));
+        }
+
+        self.send_command_to_satellite(satellite_id, command)
+    }
+
+    /// Collect telemetry only from the satellites of one mission
+    pub fn collect_mission_telemetry(&mut self, mission_id: &str) -> HashMap<u32, Vec<TelemetryData>> {
+        self.satellites.iter_mut()
+            .filter(|(_, satellite)| satellite.mission_id() == Some(mission_id))
+            .map(|(&id, satellite)| (id, satellite.generate_telemetry()))
+            .collect()
+    }
+
     pub fn collect_telemetry(&mut self) -> HashMap<u32, Vec<TelemetryData>> {
         let mut all_telemetry = HashMap::new();
         
@@ -602,6 +1151,83 @@ impl MissionControl {
     pub fn get_satellite_status(&self, satellite_id: u32) -> Option<&SystemState> {
         self.satellites.get(&satellite_id).map(|s| s.get_system_state())
     }
+
+    pub fn get_satellite_mut(&mut self, satellite_id: u32) -> Option<&mut CubeSatProtocol> {
+        self.satellites.get_mut(&satellite_id)
+    }
+
+    /// Register the procedure to run for its emergency type, replacing any earlier one
+    pub fn register_procedure(&mut self, procedure: EmergencyProcedure) -> Result<(), String> {
+        for action in &procedure.actions {
+            command_type_for_action(&action.action_type)?;
+        }
+        info!("Registered emergency procedure {} for {:?}", procedure.procedure_id, procedure.emergency_type);
+        self.emergency_procedures.insert(procedure.emergency_type, procedure);
+        Ok(())
+    }
+
+    /// Run the registered procedure for an emergency on one satellite.
+    /// Actions without accumulated delay execute at once; later ones are queued on the satellite,
+    /// and actions that would start after the procedure timeout are abandoned.
+    pub fn trigger_emergency(&mut self, satellite_id: u32, emergency_type: EmergencyType, now: DateTime<Utc>) -> Result<Vec<CubeSatCommand>, String> {
+        let satellite = self.satellites.get_mut(&satellite_id)
+            .ok_or(format!("Satellite {} not found", satellite_id))?;
+        let procedure = self.emergency_procedures.get(&emergency_type)
+            .ok_or(format!("No emergency procedure registered for {:?}", emergency_type))?;
+        self.statistics.emergency_events += 1;
+        warn!("Emergency {:?} on satellite {}, running procedure {}",
+              emergency_type, satellite_id, procedure.procedure_id);
+
+        let deadline = now + procedure.timeout;
+        let mut start = now;
+        let mut issued = Vec::new();
+        for (index, action) in procedure.actions.iter().enumerate() {
+            start += action.delay;
+            if start > deadline {
+                warn!("Procedure {} timed out, abandoning {} remaining actions",
+                      procedure.procedure_id, procedure.actions.len() - index);
+                break;
+            }
+
+            let command = CubeSatCommand {
+                command_id: self.next_emergency_command_id,
+                command_type: command_type_for_action(&action.action_type)?,
+                parameters: action.parameters.clone(),
+                scheduled_execution: Some(start),
+                priority: u8::MAX,
+                status: CommandStatus::Queued,
+            };
+            self.next_emergency_command_id = self.next_emergency_command_id.wrapping_add(1);
+
+            match satellite.enqueue_command_at(command.clone(), now) {
+                Some(executed) => {
+                    self.statistics.total_commands_executed += 1;
+                    if executed.status == CommandStatus::Failed {
+                        return Err(format!("Procedure {} aborted: action {} failed",
+                                           procedure.procedure_id, action.action_type));
+                    }
+                    issued.push(executed);
+                }
+                None => issued.push(command),
+            }
+        }
+        Ok(issued)
+    }
+}
+
+/// Map an `EmergencyAction::action_type` name onto the command it issues
+fn command_type_for_action(action_type: &str) -> Result<CommandType, String> {
+    match action_type {
+        "SystemReboot" => Ok(CommandType::SystemReboot),
+        "PayloadActivate" => Ok(CommandType::PayloadActivate),
+        "PayloadDeactivate" => Ok(CommandType::PayloadDeactivate),
+        "AttitudeControl" => Ok(CommandType::AttitudeControl),
+        "PowerManagement" => Ok(CommandType::PowerManagement),
+        "DataDownload" => Ok(CommandType::DataDownload),
+        "ConfigUpdate" => Ok(CommandType::ConfigUpdate),
+        "EmergencyMode" => Ok(CommandType::EmergencyMode),
+        other => Err(format!("Unknown emergency action type: {}", other)),
+    }
 }
 
 impl Default for MissionControl {
@@ -610,6 +1236,25 @@ impl Default for MissionControl {
     }
 }
 
+/// Set one named `MissionConfig` field from a `ConfigUpdate` command parameter
+fn apply_config_parameter(config: &mut MissionConfig, key: &str, value: &str) -> Result<(), String> {
+    let positive_secs = || value.parse::<i64>().ok().filter(|&secs| secs > 0)
+        .map(Duration::seconds)
+        .ok_or(format!("{} must be a positive number of seconds, got {}", key, value));
+    let watts = || value.parse::<f64>().ok().filter(|watts| watts.is_finite() && *watts >= 0.0)
+        .ok_or(format!("{} must be a non-negative power in watts, got {}", key, value));
+
+    match key {
+        "beacon_interval_secs" => config.communication_schedule.beacon_interval = positive_secs()?,
+        "telemetry_interval_secs" => config.communication_schedule.telemetry_interval = positive_secs()?,
+        "system_power_consumption" => config.power_budget.system_power_consumption = watts()?,
+        "communication_power" => config.power_budget.communication_power = watts()?,
+        "payload_power" => config.power_budget.payload_power = watts()?,
+        _ => return Err(format!("Unknown configuration parameter {}", key)),
+    }
+    Ok(())
+}
+
 impl Default for MissionConfig {
     fn default() -> Self {
         Self {
@@ -662,6 +1307,7 @@ impl Default for MissionConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn test_cubesat_frame_encoding_decoding() {
@@ -713,6 +1359,230 @@ mod tests {
         assert!(protocol.execute_command(command).is_ok());
     }
 
+    #[test]
+    fn test_scheduled_command_survives_reboot() {
+        let mut protocol = CubeSatProtocol::new(1);
+        let now = Utc::now();
+        let fire_at = now + Duration::minutes(30);
+
+        protocol.enqueue_command(CubeSatCommand {
+            command_id: 42,
+            command_type: CommandType::EmergencyMode,
+            parameters: HashMap::new(),
+            scheduled_execution: Some(fire_at),
+            priority: 5,
+            status: CommandStatus::Queued,
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let queue_path = dir.path().join("command_queue.json");
+        protocol.save_command_queue(&queue_path).unwrap();
+
+        let reboot = CubeSatCommand {
+            command_id: 43,
+            command_type: CommandType::SystemReboot,
+            parameters: HashMap::new(),
+            scheduled_execution: None,
+            priority: 9,
+            status: CommandStatus::Queued,
+        };
+        assert_eq!(protocol.dispatch_command(reboot, now).status, CommandStatus::Completed);
+        assert_eq!(protocol.queued_commands().len(), 1);
+
+        // A power cycle loses volatile state; the persisted queue restores it
+        let mut rebooted = CubeSatProtocol::new(1);
+        assert_eq!(rebooted.load_command_queue(&queue_path).unwrap(), 1);
+
+        assert!(rebooted.tick(fire_at - Duration::seconds(1)).is_empty());
+        assert_eq!(rebooted.system_state.power_level, 1.0);
+
+        let fired = rebooted.tick(fire_at);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].command_id, 42);
+        assert_eq!(fired[0].status, CommandStatus::Completed);
+        assert_eq!(rebooted.system_state.power_level, 0.2);
+        assert!(rebooted.queued_commands().is_empty());
+    }
+
+    #[test]
+    fn test_automatic_emergency_recovery() {
+        let mut protocol = CubeSatProtocol::new(1);
+        protocol.set_emergency_recovery(EmergencyRecoveryConfig {
+            min_power_level: 0.6,
+            min_system_health: 0.9,
+            sustain_period: Duration::minutes(2),
+        });
+        protocol.execute_command(CubeSatCommand {
+            command_id: 1,
+            command_type: CommandType::EmergencyMode,
+            parameters: HashMap::new(),
+            scheduled_execution: None,
+            priority: 10,
+            status: CommandStatus::Queued,
+        }).unwrap();
+        assert!(protocol.is_in_emergency_mode());
+
+        // Charge at the sub-solar point with nominal thermal conditions
+        let (sun_x, sun_y, sun_z) = sun_position_eci(Utc::now());
+        let subsolar_latitude = (sun_z / (sun_x * sun_x + sun_y * sun_y + sun_z * sun_z).sqrt()).asin().to_degrees();
+        let subsolar_longitude = (sun_y.atan2(sun_x) - crate::simulation::gmst_rad(Utc::now())).to_degrees();
+        let mut steps = 0;
+        while protocol.is_in_emergency_mode() && steps < 1000 {
+            protocol.system_state.position.latitude = subsolar_latitude;
+            protocol.system_state.position.longitude = subsolar_longitude;
+            protocol.system_state.temperature = 20.0;
+            protocol.update_system_state(Duration::seconds(10));
+            steps += 1;
+        }
+
+        assert!(!protocol.is_in_emergency_mode());
+        assert!(protocol.system_state.power_level >= 0.6);
+        // Recovery requires the thresholds to hold for the full sustain period
+        let charging_steps = ((0.6 - 0.2) / 0.002_f64).ceil() as i32;
+        assert!(steps >= charging_steps + 12);
+    }
+
+    #[test]
+    fn test_command_queue_ordering_and_status() {
+        let mut protocol = CubeSatProtocol::new(1);
+        let now = Utc::now();
+        let command = |command_id: u32, minutes: i64, priority: u8, mode: &str| CubeSatCommand {
+            command_id,
+            command_type: CommandType::PowerManagement,
+            parameters: HashMap::from([("mode".to_string(), mode.to_string())]),
+            scheduled_execution: Some(now + Duration::minutes(minutes)),
+            priority,
+            status: CommandStatus::Executing,
+        };
+
+        assert!(protocol.enqueue_command(command(1, 20, 1, "normal")).is_none());
+        assert!(protocol.enqueue_command(command(2, 10, 1, "bogus")).is_none());
+        assert!(protocol.enqueue_command(command(3, 10, 8, "normal")).is_none());
+        assert!(protocol.enqueue_command(command(4, 600, 5, "normal")).is_none());
+
+        let order: Vec<u32> = protocol.queued_commands().iter().map(|c| c.command_id).collect();
+        assert_eq!(order, vec![3, 2, 1, 4]);
+        assert!(protocol.queued_commands().iter().all(|c| c.status == CommandStatus::Queued));
+
+        // Due commands run in schedule order, priority first on ties; later ones wait
+        let outcomes = |executed: Vec<CubeSatCommand>| -> Vec<(u32, CommandStatus)> {
+            executed.into_iter().map(|c| (c.command_id, c.status)).collect()
+        };
+        assert_eq!(outcomes(protocol.tick(now + Duration::minutes(10))), vec![
+            (3, CommandStatus::Completed),
+            (2, CommandStatus::Failed),
+        ]);
+        assert_eq!(outcomes(protocol.tick(now + Duration::minutes(20))), vec![(1, CommandStatus::Completed)]);
+        assert_eq!(protocol.queued_commands().len(), 1);
+        assert_eq!(protocol.queued_commands()[0].command_id, 4);
+
+        // A command scheduled in the past does not wait for the next tick
+        let executed = protocol.enqueue_command(command(5, -1, 1, "normal")).unwrap();
+        assert_eq!(executed.status, CommandStatus::Completed);
+        assert_eq!(protocol.queued_commands().len(), 1);
+    }
+
+    #[test]
+    fn test_data_download_packs_buffered_telemetry() {
+        let mut protocol = CubeSatProtocol::new(1);
+        protocol.generate_telemetry();
+        let buffered = protocol.get_telemetry_buffer().len();
+
+        let download = CubeSatCommand {
+            command_id: 11,
+            command_type: CommandType::DataDownload,
+            parameters: HashMap::from([("max_points".to_string(), "3".to_string())]),
+            scheduled_execution: None,
+            priority: 4,
+            status: CommandStatus::Queued,
+        };
+        assert!(protocol.execute_command(download.clone()).is_ok());
+
+        let packets = protocol.take_downlink_packets();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].data_points.len(), 3);
+        assert_eq!(packets[0].source_node, 1);
+        assert_eq!(protocol.get_telemetry_buffer().len(), buffered - 3);
+        assert_eq!(protocol.data_points_downlinked(), 3);
+        assert!(protocol.take_downlink_packets().is_empty());
+
+        let invalid = CubeSatCommand {
+            parameters: HashMap::from([("max_points".to_string(), "lots".to_string())]),
+            ..download
+        };
+        assert!(protocol.execute_command(invalid).is_err());
+    }
+
+    #[test]
+    fn test_config_update_changes_mission_config() {
+        let mut protocol = CubeSatProtocol::new(1);
+        let update = |parameters: &[(&str, &str)]| CubeSatCommand {
+            command_id: 12,
+            command_type: CommandType::ConfigUpdate,
+            parameters: parameters.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            scheduled_execution: None,
+            priority: 5,
+            status: CommandStatus::Queued,
+        };
+        assert!(protocol.execute_command(update(&[("beacon_interval_secs", "30")])).is_err());
+
+        protocol.configure_mission(MissionConfig::default()).unwrap();
+        protocol.execute_command(update(&[("beacon_interval_secs", "30"), ("payload_power", "2.5")])).unwrap();
+        let config = protocol.mission_config().unwrap();
+        assert_eq!(config.communication_schedule.beacon_interval, Duration::seconds(30));
+        assert_eq!(config.power_budget.payload_power, 2.5);
+
+        // A bad parameter rejects the whole update
+        assert!(protocol.execute_command(update(&[("beacon_interval_secs", "10"), ("warp_drive", "on")])).is_err());
+        assert!(protocol.execute_command(update(&[("telemetry_interval_secs", "-5")])).is_err());
+        let config = protocol.mission_config().unwrap();
+        assert_eq!(config.communication_schedule.beacon_interval, Duration::seconds(30));
+        assert_eq!(config.communication_schedule.telemetry_interval, Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_stale_command_rejected_as_expired() {
+        let mut protocol = CubeSatProtocol::new(1);
+        protocol.set_command_expiry_tolerance(Duration::minutes(5));
+        let now = Utc::now();
+
+        let command = CubeSatCommand {
+            command_id: 7,
+            command_type: CommandType::EmergencyMode,
+            parameters: HashMap::new(),
+            scheduled_execution: Some(now - Duration::minutes(10)),
+            priority: 5,
+            status: CommandStatus::Queued,
+        };
+
+        let dispatched = protocol.dispatch_command(command.clone(), now);
+        assert_eq!(dispatched.status, CommandStatus::Expired);
+        assert_eq!(protocol.system_state.power_level, 1.0);
+
+        let within_window = CubeSatCommand {
+            scheduled_execution: Some(now - Duration::minutes(1)),
+            ..command
+        };
+        let dispatched = protocol.dispatch_command(within_window, now);
+        assert_eq!(dispatched.status, CommandStatus::Completed);
+        assert_eq!(protocol.system_state.power_level, 0.2);
+    }
+
+    #[test]
+    fn test_commissioning_self_test() {
+        let mut protocol = CubeSatProtocol::new(1);
+        protocol.configure_mission(MissionConfig::default()).unwrap();
+
+        let report = protocol.run_self_test();
+        assert!(report.power_ok && report.thermal_ok && report.comm_ok && report.payload_ok);
+        assert!(report.overall);
+
+        protocol.system_state.power_level = 0.2;
+        let report = protocol.run_self_test();
+        assert!(!report.power_ok);
+        assert!(!report.overall);
+    }
+
     #[test]
     fn test_telemetry_generation() {
         let mut protocol = CubeSatProtocol::new(1);
@@ -732,6 +1602,152 @@ mod tests {
         assert_eq!(beacon.destination_id, 0); // Broadcast
     }
 
+    #[test]
+    fn test_payload_duration_limit() {
+        let mut protocol = CubeSatProtocol::new(1);
+        let activate = |mode: &str| CubeSatCommand {
+            command_id: 1,
+            command_type: CommandType::PayloadActivate,
+            parameters: HashMap::from([("mode".to_string(), mode.to_string())]),
+            scheduled_execution: None,
+            priority: 1,
+            status: CommandStatus::Queued,
+        };
+        assert!(protocol.execute_command(activate("Active")).unwrap_err().contains("No operating mode"));
+
+        protocol.configure_mission(MissionConfig::default()).unwrap();
+        assert!(protocol.execute_command(activate("Survey")).is_err());
+        protocol.execute_command(activate("Active")).unwrap();
+        let activated_at = protocol.active_payload().unwrap().activated_at;
+        assert!((protocol.system_state.power_level - 0.9).abs() < 1e-9);
+
+        protocol.tick(activated_at + Duration::minutes(29));
+        assert_eq!(protocol.active_payload().unwrap().mode.mode_name, "Active");
+
+        protocol.tick(activated_at + Duration::minutes(31));
+        assert!(protocol.active_payload().is_none());
+        assert!((protocol.system_state.power_level - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_payload_activation_checks_power() {
+        let mut protocol = CubeSatProtocol::new(1);
+        let mut config = MissionConfig::default();
+        let mode = |name: &str, watts: f64| {
+            let mut mode = config.payload_config.operating_modes[0].clone();
+            mode.mode_name = name.to_string();
+            mode.power_consumption = watts;
+            mode
+        };
+        let extra = [mode("Negative", -1.0), mode("Greedy", 6.0), mode("Broken", f64::NAN)];
+        config.payload_config.operating_modes.extend(extra);
+        protocol.configure_mission(config).unwrap();
+
+        assert!(protocol.activate_payload("Negative", Utc::now()).unwrap_err().contains("invalid power"));
+        assert!(protocol.activate_payload("Broken", Utc::now()).unwrap_err().contains("invalid power"));
+        assert!(protocol.activate_payload("Greedy", Utc::now()).unwrap_err().contains("payload budget"));
+        assert!(protocol.active_payload().is_none());
+        assert!((protocol.system_state.power_level - 1.0).abs() < 1e-9);
+
+        protocol.system_state.power_level = 0.05;
+        assert!(protocol.activate_payload("Active", Utc::now()).unwrap_err().contains("Not enough power"));
+        protocol.activate_payload("Standby", Utc::now()).unwrap();
+        assert!(protocol.system_state.power_level >= 0.0);
+    }
+
+    #[test]
+    fn test_emergency_mode_refuses_non_essential_commands() {
+        let mut protocol = CubeSatProtocol::new(1);
+        protocol.configure_mission(MissionConfig::default()).unwrap();
+        let command = |command_type: CommandType, parameters: &[(&str, &str)]| CubeSatCommand {
+            command_id: 1,
+            command_type,
+            parameters: parameters.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            scheduled_execution: None,
+            priority: 1,
+            status: CommandStatus::Queued,
+        };
+        protocol.execute_command(command(CommandType::EmergencyMode, &[])).unwrap();
+
+        let refused = protocol.execute_command(command(CommandType::PayloadActivate, &[("mode", "Active")]));
+        assert!(refused.unwrap_err().contains("emergency mode"));
+        assert!(protocol.active_payload().is_none());
+        let attitude = [("roll", "1"), ("pitch", "2"), ("yaw", "3")];
+        assert!(protocol.execute_command(command(CommandType::AttitudeControl, &attitude)).is_err());
+        assert_ne!(protocol.system_state.attitude, (1.0, 2.0, 3.0));
+        protocol.execute_command(command(CommandType::PowerManagement, &[("mode", "low_power")])).unwrap();
+
+        protocol.emergency_mode = false;
+        protocol.execute_command(command(CommandType::PayloadActivate, &[("mode", "Active")])).unwrap();
+        assert!(protocol.active_payload().is_some());
+    }
+
+    #[test]
+    fn test_sunlight_follows_onboard_clock() {
+        let mut protocol = CubeSatProtocol::new(1);
+        protocol.system_state.position.latitude = 0.0;
+        protocol.system_state.position.longitude = 0.0;
+        protocol.system_state.position.altitude = 400.0;
+
+        // Steer the onboard clock to local noon and midnight over the prime meridian
+        let noon = Utc.with_ymd_and_hms(2024, 3, 20, 12, 0, 0).unwrap();
+        protocol.clock_bias = noon - Utc::now();
+        assert!(protocol.is_in_sunlight());
+        protocol.clock_bias = noon + Duration::hours(12) - Utc::now();
+        assert!(!protocol.is_in_sunlight());
+    }
+
+    #[test]
+    fn test_time_sync_offset_estimation() {
+        let mut protocol = CubeSatProtocol::new(1);
+        assert!(protocol.handle_time_sync(Utc::now(), Utc::now()).is_err());
+
+        // Ground clock runs 5 s ahead of the satellite and each leg of the link takes 1.2 s
+        let request = protocol.request_time_sync();
+        assert_eq!(request.frame_type, FrameType::TimeSync);
+        let sent_at = request.timestamp;
+        let ground_time = sent_at + Duration::milliseconds(1200) + Duration::seconds(5);
+        let rx_time = sent_at + Duration::milliseconds(2400);
+
+        let offset = protocol.handle_time_sync(ground_time, rx_time).unwrap();
+        assert!((offset - Duration::seconds(5)).num_milliseconds().abs() <= 1);
+        assert_eq!(protocol.clock_bias(), offset);
+        assert!(protocol.handle_time_sync(ground_time, rx_time).is_err());
+
+        let skew = protocol.generate_beacon().timestamp - Utc::now();
+        assert!((skew - Duration::seconds(5)).num_milliseconds().abs() < 500);
+        let telemetry = protocol.generate_telemetry();
+        assert!(telemetry[0].timestamp > Utc::now() + Duration::seconds(4));
+    }
+
+    #[test]
+    fn test_beacon_report_round_trip() {
+        let mut protocol = CubeSatProtocol::new(7);
+        protocol.system_state.power_level = 0.876;
+        protocol.system_state.temperature = -12.34;
+        protocol.system_state.uptime = Duration::seconds(5400);
+        protocol.generate_beacon();
+        let beacon = protocol.generate_beacon();
+
+        let decoded = CubeSatFrame::decode(&beacon.encode()).unwrap();
+        let report = BeaconReport::parse(&decoded).unwrap();
+        assert_eq!(report, BeaconReport {
+            satellite_id: 7,
+            beacon_number: 2,
+            power_percent: 87.6,
+            temperature_c: -12.3,
+            health: 1.0,
+            uptime_secs: 5400,
+        });
+
+        let frame = |payload: &str, frame_type: FrameType| CubeSatFrame::new(frame_type, payload.as_bytes().to_vec(), 7, 0);
+        let error = |payload: &str| BeaconReport::parse(&frame(payload, FrameType::Beacon)).unwrap_err();
+        assert!(BeaconReport::parse(&frame("BEACON:1", FrameType::Telemetry)).unwrap_err().contains("Expected a beacon"));
+        assert!(error("BEACON:1;PWR:50.0;TEMP:20.0;HEALTH:1.00").contains("missing UPTIME"));
+        assert!(error("BEACON:1;PWR:high;TEMP:20.0;HEALTH:1.00;UPTIME:0").contains("PWR"));
+        assert!(error("BEACON:1;PWR").contains("Malformed"));
+    }
+
     #[test]
     fn test_mission_control() {
         let mut mission_control = MissionControl::new();
@@ -744,6 +1760,75 @@ mod tests {
         assert!(telemetry.contains_key(&1));
     }
 
+    #[test]
+    fn test_power_critical_procedure() {
+        let mut mission_control = MissionControl::new();
+        mission_control.add_satellite(CubeSatProtocol::new(1));
+        let action = |action_type: &str, delay_minutes: i64| EmergencyAction {
+            action_type: action_type.to_string(),
+            parameters: HashMap::from([("mode".to_string(), "low_power".to_string())]),
+            delay: Duration::minutes(delay_minutes),
+        };
+        mission_control.register_procedure(EmergencyProcedure {
+            procedure_id: "PWR-CRIT-1".to_string(),
+            emergency_type: EmergencyType::PowerCritical,
+            actions: vec![action("EmergencyMode", 0), action("PowerManagement", 10), action("SystemReboot", 60)],
+            timeout: Duration::minutes(30),
+        }).unwrap();
+        assert!(mission_control.register_procedure(EmergencyProcedure {
+            procedure_id: "BAD".to_string(),
+            emergency_type: EmergencyType::ThermalEmergency,
+            actions: vec![action("SelfDestruct", 0)],
+            timeout: Duration::minutes(5),
+        }).is_err());
+
+        let now = Utc::now();
+        let issued = mission_control.trigger_emergency(1, EmergencyType::PowerCritical, now).unwrap();
+        assert_eq!(issued.len(), 2);
+        assert_eq!(issued[0].status, CommandStatus::Completed);
+        assert_eq!(issued[1].status, CommandStatus::Queued);
+        assert_eq!(mission_control.get_statistics().emergency_events, 1);
+
+        let satellite = mission_control.get_satellite_mut(1).unwrap();
+        assert!(satellite.is_in_emergency_mode());
+        assert_eq!(satellite.queued_commands().len(), 1);
+        assert_eq!(satellite.queued_commands()[0].scheduled_execution, Some(now + Duration::minutes(10)));
+
+        assert!(mission_control.trigger_emergency(1, EmergencyType::CommunicationLoss, now).is_err());
+        assert!(mission_control.trigger_emergency(9, EmergencyType::PowerCritical, now).is_err());
+        assert_eq!(mission_control.get_statistics().emergency_events, 1);
+    }
+
+    #[test]
+    fn test_mission_isolation() {
+        let mut mission_control = MissionControl::new();
+        for (satellite_id, mission_id) in [(1, "MISSION-A"), (2, "MISSION-B")] {
+            let mut satellite = CubeSatProtocol::new(satellite_id);
+            satellite.configure_mission(MissionConfig {
+                mission_id: mission_id.to_string(),
+                satellite_id,
+                ..MissionConfig::default()
+            }).unwrap();
+            mission_control.add_satellite(satellite);
+        }
+
+        let command = CubeSatCommand {
+            command_id: 1,
+            command_type: CommandType::SystemReboot,
+            parameters: HashMap::new(),
+            scheduled_execution: None,
+            priority: 5,
+            status: CommandStatus::Queued,
+        };
+
+        assert_eq!(mission_control.satellites_in_mission("MISSION-A"), vec![1]);
+        assert!(mission_control.send_mission_command("MISSION-A", 2, command.clone()).is_err());
+        assert!(mission_control.send_mission_command("MISSION-A", 1, command).is_ok());
+
+        let telemetry = mission_control.collect_mission_telemetry("MISSION-B");
+        assert_eq!(telemetry.keys().copied().collect::<Vec<_>>(), vec![2]);
+    }
+
     #[test]
     fn test_system_state_update() {
         let mut protocol = CubeSatProtocol::new(1);
@@ -2,9 +2,11 @@
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use chrono::{DateTime, Utc, Duration};
-use log::{info, warn, error, debug};
-use crate::protocol::network::{NetworkNode, NodeType, OrbitalPosition};
-use crate::telemetry::{TelemetryPacket, MissionEvent, EventType, EventStatus};
+use log::{info, error, debug};
+use crate::telemetry::TelemetryPacket;
+use crate::protocol::ccsds::{PacketType, SpacePacket};
+use crate::protocol::network::OrbitalPosition;
+use crate::simulation::{ground_state_eci, OrbitalElements, EARTH_RADIUS_KM};
 
 /// Ground station configuration and capabilities
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +36,68 @@ pub struct StationCapabilities {
     pub tracking_capability: bool,
     pub uplink_power: f64,   // Watts
     pub supported_protocols: Vec<String>,
+    #[serde(default = "default_system_noise_temp_k")]
+    pub system_noise_temp_k: f64,  // Kelvin
+    #[serde(default)]
+    pub feed_loss_db: f64,
+    #[serde(default)]
+    pub cable_loss_db: f64,
+    #[serde(default)]
+    pub pointing_loss_db: f64,
+}
+
+fn default_system_noise_temp_k() -> f64 {
+    290.0
+}
+
+/// Boltzmann's constant in dBW/K/Hz
+pub const BOLTZMANN_DBW_PER_K_HZ: f64 = -228.6;
+/// Reference noise temperature for noise-figure based budgets
+pub const REFERENCE_NOISE_TEMP_K: f64 = 290.0;
+/// Receiver noise bandwidth assumed by `link_budget`
+pub const LINK_NOISE_BANDWIDTH_HZ: f64 = 100_000.0;
+/// Nominal orbit altitude used to turn pass elevation into slant range
+pub const CONTACT_ORBIT_ALTITUDE_KM: f64 = 500.0;
+/// Satellite receive antenna gain for uplink predictions
+pub const SATELLITE_RX_GAIN_DBI: f64 = 2.0;
+/// Satellite receiver noise figure for uplink predictions
+pub const SATELLITE_NOISE_FIGURE_DB: f64 = 3.0;
+/// Speed of light in km/s
+pub const SPEED_OF_LIGHT_KM_S: f64 = 299_792.458;
+/// Spacing between samples of a pass Doppler profile
+pub const DOPPLER_SAMPLE_INTERVAL_SECS: i64 = 10;
+
+/// Satellite transmitter and geometry for a downlink budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownlinkParameters {
+    pub tx_power_w: f64,
+    pub antenna_gain_dbi: f64,
+    pub distance_km: f64,
+    pub data_rate_bps: f64,
+    pub required_eb_n0_db: f64,
+}
+
+/// Line-by-line downlink budget, all terms in dB units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkBudgetReport {
+    pub frequency_mhz: f64,
+    pub distance_km: f64,
+    pub tx_power_dbw: f64,
+    pub tx_antenna_gain_dbi: f64,
+    pub eirp_dbw: f64,
+    pub free_space_path_loss_db: f64,
+    pub feed_loss_db: f64,
+    pub cable_loss_db: f64,
+    pub pointing_loss_db: f64,
+    pub rx_antenna_gain_dbi: f64,
+    pub system_noise_temp_db_k: f64,
+    pub g_over_t_db_k: f64,
+    pub boltzmann_dbw_per_k_hz: f64,
+    pub c_over_n0_db_hz: f64,
+    pub data_rate_db_hz: f64,
+    pub eb_n0_db: f64,
+    pub required_eb_n0_db: f64,
+    pub margin_db: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +129,8 @@ pub enum StationStatus {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContactWindow {
     pub window_id: u32,
+    #[serde(default)]
+    pub station_id: u32,
     pub satellite_id: u32,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
@@ -194,6 +260,11 @@ pub struct ESAProtocolHandler {
     protocol_version: String,
 }
 
+/// CCSDS Space Packet protocol handler
+pub struct CCSDSProtocolHandler {
+    protocol_version: String,
+}
+
 impl ESAGroundNetwork {
     pub fn new() -> Self {
         let mut network = Self {
@@ -213,6 +284,15 @@ impl ESAGroundNetwork {
             Box::new(esa_handler)
         );
 
+        // Register CCSDS Space Packet handler
+        let ccsds_handler = CCSDSProtocolHandler {
+            protocol_version: "CCSDS-133.0-B-2".to_string(),
+        };
+        network.protocol_handlers.insert(
+            "CCSDS".to_string(),
+            Box::new(ccsds_handler)
+        );
+
         network
     }
 
@@ -262,6 +342,10 @@ impl ESAGroundNetwork {
                 tracking_capability: true,
                 uplink_power: 1000.0,
                 supported_protocols: vec!["ESA-CUBESAT".to_string(), "CCSDS".to_string()],
+                system_noise_temp_k: 150.0,
+                feed_loss_db: 0.3,
+                cable_loss_db: 1.0,
+                pointing_loss_db: 0.5,
             },
             status: StationStatus::Online,
             contact_schedule: Vec::new(),
@@ -299,6 +383,10 @@ impl ESAGroundNetwork {
                 tracking_capability: true,
                 uplink_power: 500.0,
                 supported_protocols: vec!["ESA-CUBESAT".to_string()],
+                system_noise_temp_k: 500.0,
+                feed_loss_db: 0.2,
+                cable_loss_db: 1.5,
+                pointing_loss_db: 0.3,
             },
             status: StationStatus::Online,
             contact_schedule: Vec::new(),
@@ -330,6 +418,10 @@ impl ESAGroundNetwork {
                 tracking_capability: true,
                 uplink_power: 750.0,
                 supported_protocols: vec!["ESA-CUBESAT".to_string(), "CCSDS".to_string()],
+                system_noise_temp_k: 180.0,
+                feed_loss_db: 0.3,
+                cable_loss_db: 1.2,
+                pointing_loss_db: 0.5,
             },
             status: StationStatus::Online,
             contact_schedule: Vec::new(),
@@ -350,22 +442,32 @@ impl ESAGroundNetwork {
                 let contact_start = now + Duration::hours(hour) + Duration::minutes(rand::random::<i64>() % 60);
                 let contact_duration = Duration::minutes(8 + rand::random::<i64>() % 12); // 8-20 minutes
                 
+                let max_elevation = 30.0 + rand::random::<f64>() * 60.0; // 30-90 degrees
                 let contact_window = ContactWindow {
                     window_id: (station_id * 1000 + hour as u32),
+                    station_id: *station_id,
                     satellite_id: 1, // Default satellite
                     start_time: contact_start,
                     end_time: contact_start + contact_duration,
-                    max_elevation: 30.0 + rand::random::<f64>() * 60.0, // 30-90 degrees
+                    max_elevation,
                     azimuth_range: (0.0, 360.0),
-                    predicted_snr: 10.0 + rand::random::<f64>() * 20.0, // 10-30 dB
+                    predicted_snr: station.capabilities.uplink_snr_db(slant_range_km(max_elevation, CONTACT_ORBIT_ALTITUDE_KM)),
                     priority: 1,
                 };
                 
                 station.contact_schedule.push(contact_window);
             }
             
-            // Sort by start time
-            station.contact_schedule.sort_by_key(|w| w.start_time);
+        }
+
+        // Drop passes that would need a station or satellite twice at once
+        let candidates: Vec<ContactWindow> = self.stations.values_mut()
+            .flat_map(|station| station.contact_schedule.drain(..))
+            .collect();
+        for window in schedule_passes(&candidates) {
+            if let Some(station) = self.stations.get_mut(&window.station_id) {
+                station.contact_schedule.push(window);
+            }
         }
 
         Ok(())
@@ -382,16 +484,8 @@ impl ESAGroundNetwork {
 
         // Check if there's a scheduled contact window
         let now = Utc::now();
-        let contact_window = station.contact_schedule.iter()
-            .find(|w| w.satellite_id == satellite_id && 
-                     w.start_time <= now && 
-                     w.end_time >= now);
-
-        if contact_window.is_none() {
-            return Err("No active contact window".to_string());
-        }
-
-        let window = contact_window.unwrap();
+        let window = Self::active_window(station, satellite_id, now)
+            .ok_or("No active contact window")?;
         let session_id = rand::random::<u32>();
 
         let contact_session = ContactSession {
@@ -401,7 +495,7 @@ impl ESAGroundNetwork {
             start_time: now,
             expected_end_time: window.end_time,
             data_transferred: 0,
-            signal_quality: window.predicted_snr / 30.0, // Normalize to 0-1
+            signal_quality: (window.predicted_snr / 30.0).clamp(0.0, 1.0), // Normalize to 0-1
             status: SessionStatus::Establishing,
         };
 
@@ -461,15 +555,11 @@ impl ESAGroundNetwork {
 
         // Validate critical commands
         match command.command_type {
-            CommandType::EmergencyShutdown => {
-                if command.priority < 9 {
-                    return Err("Emergency shutdown requires high priority".to_string());
-                }
+            CommandType::EmergencyShutdown if command.priority < 9 => {
+                return Err("Emergency shutdown requires high priority".to_string());
             },
-            CommandType::OrbitManeuver => {
-                if !command.parameters.contains_key("delta_v") {
-                    return Err("Orbit maneuver requires delta_v parameter".to_string());
-                }
+            CommandType::OrbitManeuver if !command.parameters.contains_key("delta_v") => {
+                return Err("Orbit maneuver requires delta_v parameter".to_string());
             },
             _ => {}
         }
@@ -519,11 +609,36 @@ impl ESAGroundNetwork {
             .map(|(&id, _)| id)
             .ok_or("No active session found for handover")?;
 
-        // Terminate old session
+        // Make sure the target can take the satellite before giving up the current session
+        let target = self.stations.get(&to_station)
+            .ok_or(format!("Station {} not found", to_station))?;
+        if target.status != StationStatus::Online {
+            return Err(format!("Station {} is not online", to_station));
+        }
+        if Self::active_window(target, satellite_id, Utc::now()).is_none() {
+            return Err(format!("Station {} has no active contact window for satellite {}", to_station, satellite_id));
+        }
+
+        // Terminate old session, keeping enough state to restore it
+        let old_session = self.active_contacts[&old_session_id].clone();
+        let old_station_status = self.stations.get(&from_station).map(|s| s.status.clone());
+        let old_statistics = self.network_statistics.clone();
         self.terminate_contact(old_session_id)?;
 
-        // Establish new session
-        let new_session_id = self.establish_contact(to_station, satellite_id)?;
+        // Establish new session, re-establishing the source on failure
+        let new_session_id = match self.establish_contact(to_station, satellite_id) {
+            Ok(session_id) => session_id,
+            Err(e) => {
+                self.active_contacts.insert(old_session_id, old_session);
+                if let (Some(station), Some(status)) = (self.stations.get_mut(&from_station), old_station_status) {
+                    station.status = status;
+                }
+                self.network_statistics = old_statistics;
+                error!("Handover of satellite {} to station {} failed, keeping session {}: {}",
+                       satellite_id, to_station, old_session_id, e);
+                return Err(e);
+            }
+        };
 
         info!("Completed handover from station {} to station {} for satellite {}", 
               from_station, to_station, satellite_id);
@@ -531,6 +646,12 @@ impl ESAGroundNetwork {
         Ok(new_session_id)
     }
 
+    /// Scheduled window at a station covering `now` for a satellite
+    fn active_window(station: &GroundStation, satellite_id: u32, now: DateTime<Utc>) -> Option<&ContactWindow> {
+        station.contact_schedule.iter()
+            .find(|w| w.satellite_id == satellite_id && w.start_time <= now && w.end_time >= now)
+    }
+
     /// Terminate contact session
     pub fn terminate_contact(&mut self, session_id: u32) -> Result<(), String> {
         if let Some(mut session) = self.active_contacts.remove(&session_id) {
@@ -609,6 +730,212 @@ impl ESAGroundNetwork {
     }
 }
 
+impl GroundStation {
+    /// Range rate in km/s from this station to a satellite at `time`, negative while approaching
+    pub fn range_rate_km_s(&self, elements: &OrbitalElements, time: DateTime<Utc>) -> f64 {
+        let satellite = elements.state_at(time);
+        let station = ground_state_eci(self.location.latitude, self.location.longitude, self.location.altitude, time);
+
+        let (p, s) = (satellite.position_eci, station.position_eci);
+        let (v, w) = (satellite.velocity_eci, station.velocity_eci);
+        let relative_position = (p.0 - s.0, p.1 - s.1, p.2 - s.2);
+        let relative_velocity = (v.0 - w.0, v.1 - w.1, v.2 - w.2);
+        let range = (relative_position.0.powi(2) + relative_position.1.powi(2) + relative_position.2.powi(2)).sqrt();
+        if range == 0.0 {
+            return 0.0;
+        }
+        (relative_position.0 * relative_velocity.0
+            + relative_position.1 * relative_velocity.1
+            + relative_position.2 * relative_velocity.2) / range
+    }
+
+    /// Doppler shift on the primary band sampled across a contact window; empty with no band configured
+    pub fn doppler_profile(&self, window: &ContactWindow, elements: &OrbitalElements) -> Vec<(DateTime<Utc>, f64)> {
+        let Some(band) = self.capabilities.frequency_bands.first() else {
+            return Vec::new();
+        };
+
+        let mut profile = Vec::new();
+        let mut time = window.start_time;
+        while time <= window.end_time {
+            profile.push((time, doppler_shift_hz(self.range_rate_km_s(elements, time), band.frequency_mhz)));
+            time += Duration::seconds(DOPPLER_SAMPLE_INTERVAL_SECS);
+        }
+        profile
+    }
+}
+
+impl StationCapabilities {
+    /// Receiver figure of merit G/T in dB/K
+    pub fn g_over_t_db(&self) -> f64 {
+        self.antenna_gain - 10.0 * self.system_noise_temp_k.log10()
+    }
+
+    /// Fixed receive-chain losses (feed, cable and pointing) in dB
+    pub fn fixed_losses_db(&self) -> f64 {
+        self.feed_loss_db + self.cable_loss_db + self.pointing_loss_db
+    }
+
+    /// Predicted uplink SNR in dB on the primary band at the given range, or 0 with no band configured
+    pub fn uplink_snr_db(&self, distance_km: f64) -> f64 {
+        self.frequency_bands.first().map_or(0.0, |band| {
+            link_budget(
+                self.uplink_power,
+                self.antenna_gain,
+                SATELLITE_RX_GAIN_DBI,
+                distance_km,
+                band.frequency_mhz,
+                SATELLITE_NOISE_FIGURE_DB,
+            )
+        })
+    }
+}
+
+/// Free space path loss in dB
+pub fn free_space_path_loss_db(distance_km: f64, frequency_mhz: f64) -> f64 {
+    20.0 * (distance_km * frequency_mhz).log10() + 32.45
+}
+
+/// Choose a conflict-free subset of contact windows, sorted by start time.
+///
+/// Windows are considered greedily from most to least valuable and kept unless they overlap
+/// an already kept window on the same station or the same satellite. Value is ordered by
+/// higher `priority` first, then higher `predicted_snr`, then earlier start and lower
+/// `window_id`, so a window is never dropped in favour of any number of lower-priority ones.
+pub fn schedule_passes(windows: &[ContactWindow]) -> Vec<ContactWindow> {
+    let mut candidates: Vec<&ContactWindow> = windows.iter().collect();
+    candidates.sort_by(|a, b| {
+        b.priority.cmp(&a.priority)
+            .then(b.predicted_snr.total_cmp(&a.predicted_snr))
+            .then(a.start_time.cmp(&b.start_time))
+            .then(a.window_id.cmp(&b.window_id))
+    });
+
+    let mut scheduled: Vec<ContactWindow> = Vec::new();
+    for window in candidates {
+        let conflicts = scheduled.iter().any(|kept| {
+            (kept.station_id == window.station_id || kept.satellite_id == window.satellite_id)
+                && kept.start_time < window.end_time
+                && window.start_time < kept.end_time
+        });
+        if !conflicts {
+            scheduled.push(window.clone());
+        }
+    }
+
+    scheduled.sort_by_key(|w| w.start_time);
+    scheduled
+}
+
+/// Azimuth (0-360 from north), elevation (-90..90) and range in km from a station (altitude in
+/// meters) to a satellite; a negative elevation means the satellite is below the horizon
+pub fn look_angles(station_lat: f64, station_lon: f64, station_alt: f64, satellite: &OrbitalPosition) -> (f64, f64, f64) {
+    let to_ecef = |lat: f64, lon: f64, radius: f64| {
+        let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = lon.to_radians().sin_cos();
+        (radius * cos_lat * cos_lon, radius * cos_lat * sin_lon, radius * sin_lat)
+    };
+    let station = to_ecef(station_lat, station_lon, EARTH_RADIUS_KM + station_alt / 1000.0);
+    let target = to_ecef(satellite.latitude, satellite.longitude, EARTH_RADIUS_KM + satellite.altitude);
+    let (dx, dy, dz) = (target.0 - station.0, target.1 - station.1, target.2 - station.2);
+
+    // Rotate the line of sight into the station's east/north/up frame
+    let (sin_lat, cos_lat) = station_lat.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = station_lon.to_radians().sin_cos();
+    let east = -sin_lon * dx + cos_lon * dy;
+    let north = -sin_lat * cos_lon * dx - sin_lat * sin_lon * dy + cos_lat * dz;
+    let up = cos_lat * cos_lon * dx + cos_lat * sin_lon * dy + sin_lat * dz;
+
+    let range_km = (dx * dx + dy * dy + dz * dz).sqrt();
+    if range_km == 0.0 {
+        return (0.0, 90.0, 0.0);
+    }
+    let azimuth_deg = east.atan2(north).to_degrees().rem_euclid(360.0);
+    let elevation_deg = (up / range_km).clamp(-1.0, 1.0).asin().to_degrees();
+    (azimuth_deg, elevation_deg, range_km)
+}
+
+/// Doppler shift in Hz for a range rate in km/s, positive while the range is closing
+pub fn doppler_shift_hz(range_rate_km_s: f64, frequency_mhz: f64) -> f64 {
+    -range_rate_km_s / SPEED_OF_LIGHT_KM_S * frequency_mhz * 1e6
+}
+
+/// Slant range in km to a satellite at the given altitude seen at the given elevation
+pub fn slant_range_km(elevation_deg: f64, altitude_km: f64) -> f64 {
+    let elevation = elevation_deg.to_radians();
+    let orbit_radius = EARTH_RADIUS_KM + altitude_km;
+    (orbit_radius.powi(2) - (EARTH_RADIUS_KM * elevation.cos()).powi(2)).sqrt() - EARTH_RADIUS_KM * elevation.sin()
+}
+
+/// Received SNR in dB over free space, with noise from the receiver noise figure in dB
+pub fn link_budget(tx_power_w: f64, tx_gain_db: f64, rx_gain_db: f64, distance_km: f64, frequency_mhz: f64, noise_figure: f64) -> f64 {
+    let received_power_dbw = 10.0 * tx_power_w.log10() + tx_gain_db + rx_gain_db
+        - free_space_path_loss_db(distance_km, frequency_mhz);
+    let noise_power_dbw = BOLTZMANN_DBW_PER_K_HZ
+        + 10.0 * REFERENCE_NOISE_TEMP_K.log10()
+        + 10.0 * LINK_NOISE_BANDWIDTH_HZ.log10()
+        + noise_figure;
+    received_power_dbw - noise_power_dbw
+}
+
+/// Downlink Eb/N0 margin in dB at a station for a given geometry and frequency
+pub fn link_margin_db(sat: &DownlinkParameters, station: &StationCapabilities, frequency_mhz: f64) -> f64 {
+    link_budget_report(sat, station, frequency_mhz).margin_db
+}
+
+/// Full downlink budget breakdown at a station for a given geometry and frequency
+pub fn link_budget_report(sat: &DownlinkParameters, station: &StationCapabilities, frequency_mhz: f64) -> LinkBudgetReport {
+    let tx_power_dbw = 10.0 * sat.tx_power_w.log10();
+    let eirp_dbw = tx_power_dbw + sat.antenna_gain_dbi;
+    let free_space_path_loss_db = free_space_path_loss_db(sat.distance_km, frequency_mhz);
+    let g_over_t_db_k = station.g_over_t_db();
+    let c_over_n0_db_hz = eirp_dbw - free_space_path_loss_db - station.fixed_losses_db() + g_over_t_db_k - BOLTZMANN_DBW_PER_K_HZ;
+    let data_rate_db_hz = 10.0 * sat.data_rate_bps.log10();
+    let eb_n0_db = c_over_n0_db_hz - data_rate_db_hz;
+
+    LinkBudgetReport {
+        frequency_mhz,
+        distance_km: sat.distance_km,
+        tx_power_dbw,
+        tx_antenna_gain_dbi: sat.antenna_gain_dbi,
+        eirp_dbw,
+        free_space_path_loss_db,
+        feed_loss_db: station.feed_loss_db,
+        cable_loss_db: station.cable_loss_db,
+        pointing_loss_db: station.pointing_loss_db,
+        rx_antenna_gain_dbi: station.antenna_gain,
+        system_noise_temp_db_k: 10.0 * station.system_noise_temp_k.log10(),
+        g_over_t_db_k,
+        boltzmann_dbw_per_k_hz: BOLTZMANN_DBW_PER_K_HZ,
+        c_over_n0_db_hz,
+        data_rate_db_hz,
+        eb_n0_db,
+        required_eb_n0_db: sat.required_eb_n0_db,
+        margin_db: eb_n0_db - sat.required_eb_n0_db,
+    }
+}
+
+impl ProtocolHandler for CCSDSProtocolHandler {
+    fn handle_message(&self, message: &[u8]) -> Result<Vec<u8>, String> {
+        let packet = SpacePacket::decode(message)?;
+        info!("Processing space packet APID {} with {}", packet.header.apid, self.protocol_version);
+
+        // Echo the user data back as a telemetry packet on the same APID and sequence count
+        let response = SpacePacket::new(
+            PacketType::Telemetry,
+            packet.header.apid,
+            packet.header.sequence_flags,
+            packet.header.sequence_count,
+            packet.data,
+        )?;
+        Ok(response.encode())
+    }
+
+    fn get_protocol_name(&self) -> &str {
+        "CCSDS"
+    }
+}
+
 impl Default for ESAGroundNetwork {
     fn default() -> Self {
         Self::new()
@@ -648,7 +975,7 @@ mod tests {
     fn test_ground_network_initialization() {
         let mut network = ESAGroundNetwork::new();
         assert!(network.initialize().is_ok());
-        assert!(network.stations.len() > 0);
+        assert!(!network.stations.is_empty());
     }
 
     #[test]
@@ -660,6 +987,103 @@ mod tests {
         assert!(network.establish_contact(1, 1).is_err());
     }
 
+    /// Network with station 1 in contact with satellite 7 and, optionally, a current window at station 2
+    fn network_mid_pass(target_window: bool) -> (ESAGroundNetwork, u32) {
+        let mut network = ESAGroundNetwork::new();
+        network.initialize().unwrap();
+        let now = Utc::now();
+        let window = |station_id: u32| ContactWindow {
+            window_id: station_id,
+            station_id,
+            satellite_id: 7,
+            start_time: now - Duration::minutes(5),
+            end_time: now + Duration::minutes(5),
+            max_elevation: 45.0,
+            azimuth_range: (0.0, 360.0),
+            predicted_snr: 20.0,
+            priority: 1,
+        };
+        network.stations.get_mut(&1).unwrap().contact_schedule.push(window(1));
+        if target_window {
+            network.stations.get_mut(&2).unwrap().contact_schedule.push(window(2));
+        }
+        let session_id = network.establish_contact(1, 7).unwrap();
+        (network, session_id)
+    }
+
+    #[test]
+    fn test_handover_to_station_in_view() {
+        let (mut network, old_session) = network_mid_pass(true);
+        let new_session = network.handle_handover(1, 2, 7).unwrap();
+
+        assert!(!network.active_contacts.contains_key(&old_session));
+        assert_eq!(network.active_contacts[&new_session].station_id, 2);
+        assert_eq!(network.get_station(1).unwrap().status, StationStatus::Online);
+        assert_eq!(network.get_station(2).unwrap().status, StationStatus::Tracking);
+        assert_eq!(network.get_statistics().total_contacts, 1);
+    }
+
+    #[test]
+    fn test_failed_handover_keeps_original_session() {
+        let (mut network, old_session) = network_mid_pass(false);
+        assert!(network.handle_handover(1, 2, 7).is_err());
+
+        assert_eq!(network.active_contacts.len(), 1);
+        assert_eq!(network.active_contacts[&old_session].station_id, 1);
+        assert_eq!(network.get_station(1).unwrap().status, StationStatus::Tracking);
+        assert_eq!(network.get_station(2).unwrap().status, StationStatus::Online);
+        assert_eq!(network.get_statistics().total_contacts, 0);
+        assert!(network.handle_handover(1, 99, 7).is_err());
+    }
+
+    fn pass(window_id: u32, station_id: u32, satellite_id: u32, minutes: (i64, i64), priority: u8, predicted_snr: f64) -> ContactWindow {
+        let start = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        ContactWindow {
+            window_id,
+            station_id,
+            satellite_id,
+            start_time: start + Duration::minutes(minutes.0),
+            end_time: start + Duration::minutes(minutes.1),
+            max_elevation: 45.0,
+            azimuth_range: (0.0, 360.0),
+            predicted_snr,
+            priority,
+        }
+    }
+
+    #[test]
+    fn test_schedule_passes_resolves_station_conflicts() {
+        // 2 overlaps both 1 and 3; 1 and 3 are compatible
+        let windows = [
+            pass(1, 1, 1, (0, 10), 1, 25.0),
+            pass(2, 1, 2, (5, 15), 1, 30.0),
+            pass(3, 1, 3, (12, 20), 1, 20.0),
+        ];
+        let ids: Vec<u32> = schedule_passes(&windows).iter().map(|w| w.window_id).collect();
+        assert_eq!(ids, vec![2]);
+
+        // Priority outranks SNR
+        let windows = [
+            pass(1, 1, 1, (0, 10), 2, 10.0),
+            pass(2, 1, 2, (5, 15), 1, 30.0),
+            pass(3, 1, 3, (12, 20), 2, 5.0),
+        ];
+        let ids: Vec<u32> = schedule_passes(&windows).iter().map(|w| w.window_id).collect();
+        assert_eq!(ids, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_schedule_passes_resolves_satellite_conflicts() {
+        let windows = [
+            pass(1, 1, 1, (0, 10), 1, 15.0),
+            pass(2, 2, 1, (5, 15), 1, 25.0),
+            pass(3, 3, 2, (5, 15), 1, 10.0),
+            pass(4, 2, 1, (15, 25), 1, 5.0),
+        ];
+        let ids: Vec<u32> = schedule_passes(&windows).iter().map(|w| w.window_id).collect();
+        assert_eq!(ids, vec![2, 3, 4]);
+    }
+
     #[test]
     fn test_command_validation() {
         let network = ESAGroundNetwork::new();
@@ -699,4 +1123,212 @@ mod tests {
         assert!(response.len() > test_message.len());
         assert_eq!(handler.get_protocol_name(), "ESA-CUBESAT");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_ccsds_protocol_handler() {
+        use crate::protocol::ccsds::SequenceFlags;
+
+        let network = ESAGroundNetwork::new();
+        let handler = network.protocol_handlers.get("CCSDS").unwrap();
+        assert_eq!(handler.get_protocol_name(), "CCSDS");
+
+        let request = SpacePacket::new(PacketType::Telecommand, 0x42, SequenceFlags::Unsegmented, 7, b"PING".to_vec()).unwrap();
+        let response = SpacePacket::decode(&handler.handle_message(&request.encode()).unwrap()).unwrap();
+
+        assert_eq!(response.header.packet_type, PacketType::Telemetry);
+        assert_eq!(response.header.apid, 0x42);
+        assert_eq!(response.header.sequence_count, 7);
+        assert_eq!(response.data, b"PING");
+        assert!(handler.handle_message(&[0x00, 0x42]).is_err());
+    }
+
+    #[test]
+    fn test_link_budget_snr_trends() {
+        let distances = [500.0, 1000.0, 1500.0, 2500.0];
+        let snrs: Vec<f64> = distances.iter()
+            .map(|&d| link_budget(100.0, 35.0, 2.0, d, 2200.0, 3.0))
+            .collect();
+        assert!(snrs.windows(2).all(|pair| pair[1] < pair[0]));
+        // Doubling the range costs 6 dB
+        assert!((snrs[0] - snrs[1] - 20.0 * 2f64.log10()).abs() < 1e-9);
+
+        let low_gain = link_budget(100.0, 30.0, 2.0, 1000.0, 2200.0, 3.0);
+        let high_gain = link_budget(100.0, 40.0, 2.0, 1000.0, 2200.0, 3.0);
+        assert!((high_gain - low_gain - 10.0).abs() < 1e-9);
+        assert!(link_budget(100.0, 35.0, 2.0, 1000.0, 2200.0, 6.0) < snrs[1]);
+    }
+
+    #[test]
+    fn test_contact_windows_use_link_budget() {
+        let mut network = ESAGroundNetwork::new();
+        network.initialize().unwrap();
+        let station = network.get_station(1).unwrap();
+
+        for window in &station.contact_schedule {
+            let distance = slant_range_km(window.max_elevation, CONTACT_ORBIT_ALTITUDE_KM);
+            assert!((window.predicted_snr - station.capabilities.uplink_snr_db(distance)).abs() < 1e-9);
+        }
+        assert!((slant_range_km(90.0, CONTACT_ORBIT_ALTITUDE_KM) - CONTACT_ORBIT_ALTITUDE_KM).abs() < 1e-6);
+        assert!(slant_range_km(30.0, CONTACT_ORBIT_ALTITUDE_KM) > slant_range_km(60.0, CONTACT_ORBIT_ALTITUDE_KM));
+    }
+
+    #[test]
+    fn test_doppler_scales_with_frequency() {
+        assert_eq!(doppler_shift_hz(0.0, 2200.0), 0.0);
+        assert!(doppler_shift_hz(-7.0, 2200.0) > 0.0);
+        assert!(doppler_shift_hz(7.0, 2200.0) < 0.0);
+        let ratio = doppler_shift_hz(-5.0, 8400.0) / doppler_shift_hz(-5.0, 2100.0);
+        assert!((ratio - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_doppler_profile_changes_sign_at_closest_approach() {
+        use crate::simulation::gmst_rad;
+
+        let mut network = ESAGroundNetwork::new();
+        network.initialize().unwrap();
+        let mut station = network.get_station(1).unwrap().clone();
+        station.location.latitude = 0.0;
+        station.location.longitude = 0.0;
+        station.location.altitude = 0.0;
+
+        // Equatorial orbit starting 20 degrees west of the station
+        let epoch = Utc::now();
+        let elements = OrbitalElements {
+            semi_major_axis: 6871.0,
+            eccentricity: 0.0,
+            inclination: 0.0,
+            raan: 0.0,
+            argument_of_perigee: 0.0,
+            mean_anomaly: gmst_rad(epoch).to_degrees() - 20.0,
+            epoch,
+        };
+        let window = ContactWindow {
+            window_id: 1,
+            station_id: station.station_id,
+            satellite_id: 1,
+            start_time: epoch,
+            end_time: epoch + Duration::minutes(10),
+            max_elevation: 90.0,
+            azimuth_range: (270.0, 90.0),
+            predicted_snr: 20.0,
+            priority: 1,
+        };
+
+        let profile = station.doppler_profile(&window, &elements);
+        assert_eq!(profile.len(), 61);
+        assert!(profile.first().unwrap().1 > 0.0);
+        assert!(profile.last().unwrap().1 < 0.0);
+        let sign_changes = profile.windows(2).filter(|pair| (pair[0].1 > 0.0) != (pair[1].1 > 0.0)).count();
+        assert_eq!(sign_changes, 1);
+
+        let peak = profile.iter().map(|(_, shift)| shift.abs()).fold(0.0, f64::max);
+        let near_tca = profile.iter().map(|(_, shift)| shift.abs()).fold(f64::MAX, f64::min);
+        assert!(peak > 10_000.0);
+        assert!(near_tca < peak / 10.0);
+    }
+
+    #[test]
+    fn test_look_angles_overhead_and_horizon() {
+        let overhead = OrbitalPosition { latitude: 48.0, longitude: 11.0, altitude: 500.0, velocity: (7.6, 0.0, 0.0) };
+        let (_, elevation, range) = look_angles(48.0, 11.0, 0.0, &overhead);
+        assert!((elevation - 90.0).abs() < 1e-6);
+        assert!((range - 500.0).abs() < 1e-6);
+
+        // A satellite on the station's horizon sits where the tangent line meets its orbit
+        let altitude = 500.0;
+        let central_angle = (EARTH_RADIUS_KM / (EARTH_RADIUS_KM + altitude)).acos().to_degrees();
+        let due_east = OrbitalPosition { latitude: 0.0, longitude: central_angle, altitude, velocity: (7.6, 0.0, 0.0) };
+        let (azimuth, elevation, range) = look_angles(0.0, 0.0, 0.0, &due_east);
+        assert!((azimuth - 90.0).abs() < 1e-6);
+        assert!(elevation.abs() < 1e-6);
+        assert!((range - slant_range_km(0.0, altitude)).abs() < 1e-6);
+
+        let north = OrbitalPosition { latitude: 10.0, longitude: 0.0, altitude, velocity: (7.6, 0.0, 0.0) };
+        assert!(look_angles(0.0, 0.0, 0.0, &north).0.abs() < 1e-6);
+        let west = OrbitalPosition { latitude: 0.0, longitude: -10.0, altitude, velocity: (7.6, 0.0, 0.0) };
+        assert!((look_angles(0.0, 0.0, 0.0, &west).0 - 270.0).abs() < 1e-6);
+
+        let antipode = OrbitalPosition { latitude: 0.0, longitude: 180.0, altitude, velocity: (7.6, 0.0, 0.0) };
+        let (_, elevation, _) = look_angles(0.0, 0.0, 0.0, &antipode);
+        assert!((elevation + 90.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_noise_temperature_affects_link_margin() {
+        let mut network = ESAGroundNetwork::new();
+        network.initialize().unwrap();
+        let quiet = network.get_station(1).unwrap().capabilities.clone();
+        let noisy = StationCapabilities {
+            system_noise_temp_k: quiet.system_noise_temp_k * 10.0,
+            ..quiet.clone()
+        };
+        let sat = DownlinkParameters {
+            tx_power_w: 1.0,
+            antenna_gain_dbi: 0.0,
+            distance_km: 1000.0,
+            data_rate_bps: 9600.0,
+            required_eb_n0_db: 9.6,
+        };
+
+        let quiet_margin = link_margin_db(&sat, &quiet, 2200.0);
+        let noisy_margin = link_margin_db(&sat, &noisy, 2200.0);
+        assert!((quiet_margin - noisy_margin - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fixed_losses_reduce_link_margin() {
+        let mut network = ESAGroundNetwork::new();
+        network.initialize().unwrap();
+        let lossless = StationCapabilities {
+            feed_loss_db: 0.0,
+            cable_loss_db: 0.0,
+            pointing_loss_db: 0.0,
+            ..network.get_station(1).unwrap().capabilities.clone()
+        };
+        let lossy = StationCapabilities {
+            feed_loss_db: 0.5,
+            cable_loss_db: 1.5,
+            pointing_loss_db: 1.0,
+            ..lossless.clone()
+        };
+        let sat = DownlinkParameters {
+            tx_power_w: 2.0,
+            antenna_gain_dbi: 3.0,
+            distance_km: 1500.0,
+            data_rate_bps: 9600.0,
+            required_eb_n0_db: 9.6,
+        };
+
+        let difference = link_margin_db(&sat, &lossless, 2200.0) - link_margin_db(&sat, &lossy, 2200.0);
+        assert!((difference - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_link_budget_report_terms_sum_to_margin() {
+        let mut network = ESAGroundNetwork::new();
+        network.initialize().unwrap();
+        let station = network.get_station(2).unwrap().capabilities.clone();
+        let sat = DownlinkParameters {
+            tx_power_w: 2.0,
+            antenna_gain_dbi: 2.0,
+            distance_km: 1200.0,
+            data_rate_bps: 9600.0,
+            required_eb_n0_db: 9.6,
+        };
+
+        let report = link_budget_report(&sat, &station, 2200.0);
+        let summed = report.tx_power_dbw + report.tx_antenna_gain_dbi
+            - report.free_space_path_loss_db
+            - report.feed_loss_db - report.cable_loss_db - report.pointing_loss_db
+            + report.rx_antenna_gain_dbi - report.system_noise_temp_db_k
+            - report.boltzmann_dbw_per_k_hz
+            - report.data_rate_db_hz
+            - report.required_eb_n0_db;
+
+        assert!((report.margin_db - summed).abs() < 1e-9);
+        assert!((report.margin_db - link_margin_db(&sat, &station, 2200.0)).abs() < 1e-9);
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("free_space_path_loss_db"));
+    }
+}
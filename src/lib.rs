@@ -11,10 +11,64 @@ pub mod web;
 pub mod metrics;
 pub mod config;
 
-use protocol::spacecan::SpaceCANFrame;
 use protocol::network::MeshNetwork;
-use cubesat::CubeSatProtocol;
-use simulation::SpaceSimulator;
+use log::{info, warn};
+
+/// Maximum number of telemetry points bundled into a single downlink packet
+pub const TELEMETRY_DOWNLINK_BATCH: usize = 50;
+
+/// End-to-end retry behaviour for `RustSatProtocol::send_message`
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub backoff: std::time::Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: std::time::Duration::ZERO,
+        }
+    }
+}
+
+impl From<&config::NetworkConfig> for RetryPolicy {
+    fn from(config: &config::NetworkConfig) -> Self {
+        Self {
+            max_attempts: config.retry_attempts.max(1),
+            backoff: std::time::Duration::from_millis(config.retry_backoff_ms),
+        }
+    }
+}
+
+/// Confirmation that a message reached its destination
+#[derive(Debug, Clone)]
+pub struct DeliveryReport {
+    pub destination: u32,
+    pub attempts: u32,
+    pub route: Vec<u32>,
+}
+
+impl DeliveryReport {
+    /// Number of attempts beyond the first
+    pub fn retries(&self) -> u32 {
+        self.attempts.saturating_sub(1)
+    }
+}
+
+/// Failure modes of an end-to-end send
+#[derive(Debug, thiserror::Error)]
+pub enum SendError {
+    #[error("Encryption failed: {0}")]
+    Encryption(String),
+    #[error("Delivery to node {destination} failed after {attempts} attempts: {last_error}")]
+    RetriesExhausted {
+        destination: u32,
+        attempts: u32,
+        last_error: String,
+    },
+}
 
 /// Main RustSat protocol stack integrating all layers
 pub struct RustSatProtocol {
@@ -23,6 +77,8 @@ pub struct RustSatProtocol {
     pub application_layer: cubesat::MissionControl,
     pub security_layer: security::CryptoModule,
     pub telemetry: telemetry::TelemetryProcessor,
+    pub retry_policy: RetryPolicy,
+    backoff_delay: protocol::spacecan::BackoffDelay,
 }
 
 impl RustSatProtocol {
@@ -34,6 +90,22 @@ impl RustSatProtocol {
             application_layer: cubesat::MissionControl::new(),
             security_layer: security::CryptoModule::new(),
             telemetry: telemetry::TelemetryProcessor::new(),
+            retry_policy: RetryPolicy::default(),
+            backoff_delay: Box::new(std::thread::sleep),
+        }
+    }
+
+    /// Create a protocol stack with retry and security behaviour taken from the configuration
+    pub fn with_config(config: &config::RustSatConfig) -> Self {
+        let mut physical_layer = protocol::spacecan::SpaceCANAdapter::new();
+        physical_layer.set_arq_config(protocol::spacecan::ArqConfig::from(&config.network));
+        let mut security_layer = security::CryptoModule::new();
+        security_layer.apply_config(&config.security);
+        Self {
+            physical_layer,
+            security_layer,
+            retry_policy: RetryPolicy::from(&config.network),
+            ..Self::new()
         }
     }
 
@@ -53,7 +125,7 @@ impl RustSatProtocol {
             velocity: (7.66, 0.0, 0.0),
         };
         let node1 = protocol::network::NetworkNode::new_cubesat(1, position1);
-        self.network_layer.add_node(node1);
+        self.network_layer.add_node(node1)?;
 
         // Add source node with id 0 to network layer for routing
         let position0 = protocol::network::OrbitalPosition {
@@ -63,35 +135,115 @@ impl RustSatProtocol {
             velocity: (0.0, 0.0, 0.0),
         };
         let node0 = protocol::network::NetworkNode::new_cubesat(0, position0);
-        self.network_layer.add_node(node0);
+        self.network_layer.add_node(node0)?;
 
         self.network_layer.initialize_routing()?;
         self.security_layer.initialize_keys()?;
         Ok(())
     }
 
-    /// Send a message through the complete protocol stack
-    pub fn send_message(&mut self, destination: u32, payload: &[u8]) -> Result<(), String> {
+    /// Replace how the retry backoff is waited out, e.g. with a simulated or async clock
+    pub fn set_backoff_delay<F>(&mut self, delay: F)
+    where
+        F: FnMut(std::time::Duration) + Send + 'static,
+    {
+        self.backoff_delay = Box::new(delay);
+    }
+
+    /// Send a message through the complete protocol stack, retrying up to the configured limit
+    pub fn send_message(&mut self, destination: u32, payload: &[u8]) -> Result<DeliveryReport, SendError> {
         // Encrypt payload
-        let encrypted_payload = self.security_layer.encrypt(payload)?;
-        
+        let encrypted_payload = self.security_layer.encrypt(payload)
+            .map_err(SendError::Encryption)?;
+
+        let max_attempts = self.retry_policy.max_attempts.max(1);
+        let mut last_error = String::new();
+
+        for attempt in 1..=max_attempts {
+            match self.attempt_delivery(destination, &encrypted_payload) {
+                Ok(route) => {
+                    // Log telemetry
+                    self.telemetry.log_transmission(destination, payload.len());
+                    if attempt > 1 {
+                        info!("Message to node {} delivered after {} retries", destination, attempt - 1);
+                    }
+                    return Ok(DeliveryReport { destination, attempts: attempt, route });
+                }
+                Err(e) => {
+                    warn!("Send attempt {}/{} to node {} failed: {}", attempt, max_attempts, destination, e);
+                    last_error = e;
+                    if attempt < max_attempts && !self.retry_policy.backoff.is_zero() {
+                        (self.backoff_delay)(self.retry_policy.backoff);
+                    }
+                }
+            }
+        }
+
+        Err(SendError::RetriesExhausted { destination, attempts: max_attempts, last_error })
+    }
+
+    /// Route and transmit one copy of an encrypted payload
+    fn attempt_delivery(&mut self, destination: u32, encrypted_payload: &[u8]) -> Result<Vec<u32>, String> {
         // Route through network layer
-        let _routed = self.network_layer.route_message(0, destination, &encrypted_payload)?;
-        
+        let packet = self.network_layer.deliver_message(0, destination, encrypted_payload)?
+            .ok_or(format!("No route to node {}", destination))?;
+
         // Create SpaceCAN frame for transmission
         let frame = protocol::spacecan::SpaceCANFrame::new(
-            destination, 
-            encrypted_payload, 
+            destination,
+            encrypted_payload.to_vec(),
             protocol::spacecan::FramePriority::Normal
         );
-        
+
         // Send via physical layer
         self.physical_layer.transmit(&frame)?;
-        
-        // Log telemetry
-        self.telemetry.log_transmission(destination, payload.len());
-        
-        Ok(())
+
+        Ok(packet.route_history)
+    }
+
+    /// Generate telemetry for a satellite and route it through the mesh, relaying via other
+    /// satellites if needed, to the cheapest reachable ground station. Telemetry that cannot
+    /// be delivered stays buffered for the next attempt. Returns the packet as it reached the
+    /// station, or `None` when the mesh took it into custody for delivery once a route reopens.
+    pub fn downlink_telemetry(&mut self, node_id: u32) -> Result<Option<protocol::network::NetworkPacket>, String> {
+        let satellite = self.application_layer.get_satellite_mut(node_id)
+            .ok_or(format!("Satellite {} not found", node_id))?;
+        for data in satellite.generate_telemetry() {
+            self.telemetry.process_telemetry(data)?;
+        }
+
+        let (ground_station, _) = self.network_layer.cheapest_ground_station_route(node_id)?
+            .ok_or(format!("No route from node {} to any ground station", node_id))?;
+
+        let packet = self.telemetry.create_telemetry_packet(node_id, TELEMETRY_DOWNLINK_BATCH)?
+            .ok_or(format!("No telemetry available for node {}", node_id))?;
+        let payload = match serde_json::to_vec(&packet) {
+            Ok(payload) => payload,
+            Err(e) => {
+                self.telemetry.requeue_packet(packet);
+                return Err(format!("Failed to serialize telemetry packet: {}", e));
+            }
+        };
+
+        let custody_before = self.network_layer.custody_count();
+        let routed = match self.network_layer.deliver_message(node_id, ground_station, &payload) {
+            Ok(Some(routed)) => Some(routed),
+            // The mesh holds the payload in custody and delivers it once a route reappears
+            Ok(None) if self.network_layer.custody_count() > custody_before => None,
+            Ok(None) => {
+                self.telemetry.requeue_packet(packet);
+                return Err(format!("Telemetry for ground station {} was dropped", ground_station));
+            }
+            Err(e) => {
+                self.telemetry.requeue_packet(packet);
+                return Err(format!("Telemetry downlink to ground station {} failed: {}", ground_station, e));
+            }
+        };
+
+        self.telemetry.log_transmission(ground_station, payload.len());
+        self.telemetry.record_downlink(&packet, ground_station);
+
+        Ok(routed)
     }
 
     /// Receive and process incoming messages
@@ -132,4 +284,157 @@ mod tests {
         let test_payload = b"Hello CubeSat!";
         assert!(protocol.send_message(1, test_payload).is_ok());
     }
+
+    #[test]
+    fn test_send_retries_after_transient_failure() {
+        let mut protocol = RustSatProtocol::new();
+        protocol.initialize_mission(cubesat::MissionConfig::default()).unwrap();
+        protocol.physical_layer.inject_faults(1);
+
+        let report = protocol.send_message(1, b"retry me").unwrap();
+        assert_eq!(report.attempts, 2);
+        assert_eq!(report.retries(), 1);
+        assert_eq!(report.route.last(), Some(&1));
+        assert_eq!(protocol.physical_layer.get_statistics().frames_sent, 1);
+        assert_eq!(protocol.physical_layer.get_statistics().errors_detected, 1);
+    }
+
+    #[test]
+    fn test_send_fails_when_retries_exhausted() {
+        let mut protocol = RustSatProtocol::new();
+        protocol.initialize_mission(cubesat::MissionConfig::default()).unwrap();
+        protocol.retry_policy.max_attempts = 2;
+        protocol.retry_policy.backoff = std::time::Duration::from_secs(60);
+        let waits = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = waits.clone();
+        protocol.set_backoff_delay(move |backoff| recorded.lock().unwrap().push(backoff));
+        protocol.physical_layer.inject_faults(5);
+
+        match protocol.send_message(1, b"lost") {
+            Err(SendError::RetriesExhausted { attempts, .. }) => assert_eq!(attempts, 2),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        // Only the gap between the two attempts is waited out
+        assert_eq!(*waits.lock().unwrap(), vec![std::time::Duration::from_secs(60)]);
+    }
+
+    #[test]
+    fn test_retry_policy_follows_config() {
+        let mut config = config::RustSatConfig::default();
+        config.network.retry_attempts = 4;
+        config.network.retry_backoff_ms = 0;
+        let mut protocol = RustSatProtocol::with_config(&config);
+        assert_eq!(protocol.retry_policy.max_attempts, 4);
+        protocol.initialize_mission(cubesat::MissionConfig::default()).unwrap();
+        protocol.physical_layer.inject_faults(10);
+
+        match protocol.send_message(1, b"lost") {
+            Err(SendError::RetriesExhausted { attempts, .. }) => assert_eq!(attempts, 4),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_emergency_switches_follow_config() {
+        let mut forger = security::CryptoModule::new();
+        forger.initialize_keys().unwrap();
+        let forged = forger.create_emergency_message(7, b"SAFE MODE").unwrap();
+
+        let mut protocol = RustSatProtocol::with_config(&config::RustSatConfig::default());
+        protocol.initialize_mission(cubesat::MissionConfig::default()).unwrap();
+        assert!(protocol.security_layer.verify_emergency_message(&forged).is_err());
+
+        let mut config = config::RustSatConfig::default();
+        config.security.emergency_bypass_enabled = true;
+        let mut protocol = RustSatProtocol::with_config(&config);
+        protocol.initialize_mission(cubesat::MissionConfig::default()).unwrap();
+        assert_eq!(protocol.security_layer.verify_emergency_message(&forged).unwrap(), b"SAFE MODE");
+    }
+
+    #[test]
+    fn test_telemetry_downlink_reaches_ground_station() {
+        let mut protocol = RustSatProtocol::new();
+        protocol.initialize_mission(cubesat::MissionConfig::default()).unwrap();
+        protocol.network_layer.add_node(protocol::network::NetworkNode::new_ground_station(100, 0.0, 0.0)).unwrap();
+        protocol.network_layer.initialize_routing().unwrap();
+
+        let packet = protocol.downlink_telemetry(1).unwrap().unwrap();
+        assert_eq!(packet.source, 1);
+        assert_eq!(packet.destination, 100);
+        assert_eq!(packet.route_history.last(), Some(&100));
+        assert!(!packet.payload.is_empty());
+        assert_eq!(protocol.telemetry.get_statistics().packets_transmitted, 1);
+    }
+
+    fn satellite_at(node_id: u32, latitude: f64) -> protocol::network::NetworkNode {
+        protocol::network::NetworkNode::new_cubesat(node_id, protocol::network::OrbitalPosition {
+            latitude,
+            longitude: 0.0,
+            altitude: 400.0,
+            velocity: (7.66, 0.0, 0.0),
+        })
+    }
+
+    #[test]
+    fn test_telemetry_downlink_relays_to_out_of_range_ground_station() {
+        let mut protocol = RustSatProtocol::new();
+        protocol.initialize_mission(cubesat::MissionConfig::default()).unwrap();
+        // A chain of relays ~950 km apart leads to a station ~6000 km from node 1
+        for (node_id, latitude) in [(2, 8.0), (3, 16.0), (4, 24.0), (5, 32.0), (6, 40.0), (7, 48.0)] {
+            protocol.network_layer.add_node(satellite_at(node_id, latitude)).unwrap();
+        }
+        protocol.network_layer.add_node(protocol::network::NetworkNode::new_ground_station(100, 54.0, 0.0)).unwrap();
+        protocol.network_layer.initialize_routing().unwrap();
+        assert_eq!(protocol.network_layer.best_ground_station(1).unwrap(), None);
+
+        let packet = protocol.downlink_telemetry(1).unwrap().unwrap();
+        assert_eq!(packet.route_history, vec![1, 2, 3, 4, 5, 6, 7, 100]);
+    }
+
+    #[test]
+    fn test_telemetry_downlink_into_custody_counts_as_sent() {
+        let mut protocol = RustSatProtocol::new();
+        protocol.initialize_mission(cubesat::MissionConfig::default()).unwrap();
+        for (node_id, latitude) in [(2, 8.0), (3, 16.0), (4, 24.0), (5, 32.0), (6, 40.0), (7, 48.0)] {
+            protocol.network_layer.add_node(satellite_at(node_id, latitude)).unwrap();
+        }
+        // A detour around relay 4, which is about to run flat
+        let mut detour = satellite_at(8, 24.0);
+        detour.position.longitude = 2.0;
+        protocol.network_layer.add_node(detour).unwrap();
+        protocol.network_layer.add_node(protocol::network::NetworkNode::new_ground_station(100, 54.0, 0.0)).unwrap();
+        protocol.network_layer.set_routing_algorithm(protocol::network::RoutingAlgo::DistanceVector);
+        protocol.network_layer.initialize_routing().unwrap();
+        // Relay 4 reports a flat battery before the distance-vector tables catch up
+        let mut flat = satellite_at(4, 24.0);
+        flat.battery_level = 0.05;
+        protocol.network_layer.add_node(flat).unwrap();
+
+        assert!(protocol.downlink_telemetry(1).unwrap().is_none());
+        assert_eq!(protocol.network_layer.custody_count(), 1);
+        assert_eq!(protocol.telemetry.get_statistics().packets_transmitted, 1);
+        assert!(protocol.telemetry.query_latest_all(1).is_empty());
+    }
+
+    #[test]
+    fn test_failed_telemetry_downlink_keeps_telemetry() {
+        let mut protocol = RustSatProtocol::new();
+        protocol.initialize_mission(cubesat::MissionConfig::default()).unwrap();
+
+        assert!(protocol.downlink_telemetry(1).is_err());
+        let buffered = protocol.telemetry.query_latest_all(1).len();
+        assert!(buffered > 0);
+
+        protocol.network_layer.add_node(protocol::network::NetworkNode::new_ground_station(100, 0.0, 0.0)).unwrap();
+        protocol.network_layer.initialize_routing().unwrap();
+        protocol.network_layer.set_link_failure_probability(1.0);
+        assert!(protocol.downlink_telemetry(1).is_err());
+        assert!(protocol.telemetry.query_latest_all(1).len() >= buffered);
+        assert_eq!(protocol.telemetry.get_statistics().packets_transmitted, 0);
+
+        protocol.network_layer.set_link_failure_probability(0.0);
+        let packet = protocol.downlink_telemetry(1).unwrap().unwrap();
+        assert_eq!(packet.destination, 100);
+        assert_eq!(protocol.telemetry.get_statistics().packets_transmitted, 1);
+    }
 }
\ No newline at end of file
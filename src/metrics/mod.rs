@@ -5,7 +5,10 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use chrono::{DateTime, Utc};
+use log::warn;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "system-probe")]
+use sysinfo::{Pid, System};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
@@ -17,6 +20,33 @@ pub struct PerformanceMetrics {
     pub network_utilization: f64,    // percentage
     pub uptime: Duration,            // system uptime
     pub last_updated: DateTime<Utc>,
+    // Tail latency over the retained samples, absent until a latency is recorded
+    #[serde(default)]
+    pub latency_histogram: Option<LatencyHistogram>,
+    #[serde(default)]
+    pub latency_p50: Option<Duration>,
+    #[serde(default)]
+    pub latency_p90: Option<Duration>,
+    #[serde(default)]
+    pub latency_p99: Option<Duration>,
+}
+
+/// Default number of latency samples retained for averages and percentiles
+pub const DEFAULT_MAX_LATENCY_SAMPLES: usize = 1000;
+/// Default latency histogram bucket bounds in milliseconds
+pub const DEFAULT_LATENCY_BUCKETS_MS: [u64; 11] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000];
+
+/// Width of each message-count interval kept for windowed throughput
+pub const THROUGHPUT_INTERVAL: Duration = Duration::from_secs(1);
+/// Number of throughput intervals retained, bounding the longest usable window
+pub const MAX_THROUGHPUT_INTERVALS: usize = 3600;
+
+/// Cumulative latency histogram: each bucket counts the samples at or below its upper bound
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    pub buckets: Vec<(Duration, u64)>, // (upper bound, cumulative count)
+    pub count: u64,
+    pub sum: Duration,
 }
 
 #[derive(Debug, Clone)]
@@ -30,8 +60,62 @@ pub struct MetricsCollector {
     message_count: Arc<Mutex<u64>>,
     error_count: Arc<Mutex<u64>>,
     latency_samples: Arc<Mutex<Vec<Duration>>>,
-    throughput_history: Arc<Mutex<Vec<MetricPoint>>>,
+    max_latency_samples: usize,
+    latency_buckets: Vec<Duration>,
+    throughput_history: Arc<Mutex<Vec<MetricPoint>>>, // message count per interval, keyed by interval start
     custom_metrics: Arc<Mutex<HashMap<String, Vec<MetricPoint>>>>,
+    system_probe: Option<Mutex<SystemProbe>>,
+}
+
+/// Reads this process's resource usage from the operating system
+#[cfg(feature = "system-probe")]
+struct SystemProbe {
+    system: System,
+    pid: Pid,
+}
+
+#[cfg(feature = "system-probe")]
+impl SystemProbe {
+    fn new() -> Option<Self> {
+        let probe = sysinfo::get_current_pid().ok().and_then(|pid| {
+            let mut system = System::new();
+            system.refresh_process(pid).then_some(Self { system, pid })
+        });
+        if probe.is_none() {
+            warn!("System probe unavailable, using estimated resource usage");
+        }
+        probe
+    }
+    
+    /// Resident memory in bytes and CPU percentage since the previous sample
+    fn sample(&mut self) -> Option<(u64, f64)> {
+        if !self.system.refresh_process(self.pid) {
+            return None;
+        }
+        let process = self.system.process(self.pid)?;
+        Some((process.memory(), process.cpu_usage() as f64))
+    }
+}
+
+/// Built without the `system-probe` feature there is no probe and usage is always estimated
+#[cfg(not(feature = "system-probe"))]
+enum SystemProbe {}
+
+#[cfg(not(feature = "system-probe"))]
+impl SystemProbe {
+    fn new() -> Option<Self> {
+        None
+    }
+
+    fn sample(&mut self) -> Option<(u64, f64)> {
+        match *self {}
+    }
+}
+
+impl Default for MetricsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MetricsCollector {
@@ -41,15 +125,72 @@ impl MetricsCollector {
             message_count: Arc::new(Mutex::new(0)),
             error_count: Arc::new(Mutex::new(0)),
             latency_samples: Arc::new(Mutex::new(Vec::new())),
+            max_latency_samples: DEFAULT_MAX_LATENCY_SAMPLES,
+            latency_buckets: DEFAULT_LATENCY_BUCKETS_MS.iter().map(|&ms| Duration::from_millis(ms)).collect(),
             throughput_history: Arc::new(Mutex::new(Vec::new())),
             custom_metrics: Arc::new(Mutex::new(HashMap::new())),
+            system_probe: None,
         }
     }
     
+    /// Collector that reports real process memory and CPU usage, falling back to
+    /// estimates without the `system-probe` feature or if the operating system cannot be queried
+    pub fn with_system_probe() -> Self {
+        Self {
+            system_probe: SystemProbe::new().map(Mutex::new),
+            ..Self::new()
+        }
+    }
+    
+    /// Retain at most `limit` latency samples (at least one)
+    pub fn with_latency_sample_limit(mut self, limit: usize) -> Self {
+        self.max_latency_samples = limit.max(1);
+        self
+    }
+    
+    /// Use these histogram bucket upper bounds instead of the defaults
+    pub fn with_latency_buckets(mut self, mut buckets: Vec<Duration>) -> Self {
+        buckets.sort();
+        buckets.dedup();
+        self.latency_buckets = buckets;
+        self
+    }
+    
     pub fn record_message(&self) {
         if let Ok(mut count) = self.message_count.lock() {
             *count += 1;
         }
+        if let Ok(mut history) = self.throughput_history.lock() {
+            let intervals = self.start_time.elapsed().as_nanos() / THROUGHPUT_INTERVAL.as_nanos();
+            let interval_start = self.start_time + THROUGHPUT_INTERVAL * intervals as u32;
+            match history.last_mut() {
+                Some(point) if point.timestamp == interval_start => point.value += 1.0,
+                _ => history.push(MetricPoint { timestamp: interval_start, value: 1.0 }),
+            }
+            
+            if history.len() > MAX_THROUGHPUT_INTERVALS {
+                let excess = history.len() - MAX_THROUGHPUT_INTERVALS;
+                history.drain(0..excess);
+            }
+        }
+    }
+    
+    /// Messages per second over the last `secs` seconds, to interval granularity.
+    /// Until the collector has been running that long the rate covers its uptime.
+    pub fn throughput_window(&self, secs: u64) -> f64 {
+        let now = Instant::now();
+        let window = Duration::from_secs(secs).min(now - self.start_time);
+        if window.is_zero() {
+            return 0.0;
+        }
+        
+        let history = self.throughput_history.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let messages: f64 = history.iter()
+            .rev()
+            .take_while(|point| now - point.timestamp <= window)
+            .map(|point| point.value)
+            .sum();
+        messages / window.as_secs_f64()
     }
     
     pub fn record_error(&self) {
@@ -62,14 +203,27 @@ impl MetricsCollector {
         if let Ok(mut samples) = self.latency_samples.lock() {
             samples.push(latency);
             
-            // Keep only last 1000 samples to prevent memory growth
-            if samples.len() > 1000 {
-                let excess = samples.len() - 1000;
+            // Keep only the most recent samples to prevent memory growth
+            if samples.len() > self.max_latency_samples {
+                let excess = samples.len() - self.max_latency_samples;
                 samples.drain(0..excess);
             }
         }
     }
     
+    /// Latency at or below which `p` percent of the retained samples fall (nearest rank)
+    pub fn latency_percentile(&self, p: f64) -> Option<Duration> {
+        let mut sorted = self.latency_samples.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        sorted.sort();
+        percentile_of_sorted(&sorted, p)
+    }
+    
+    /// Histogram of the retained latency samples over the configured buckets
+    pub fn latency_histogram(&self) -> Option<LatencyHistogram> {
+        let samples = self.latency_samples.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        histogram(&samples, &self.latency_buckets)
+    }
+    
     pub fn record_custom_metric(&self, name: &str, value: f64) {
         if let Ok(mut metrics) = self.custom_metrics.lock() {
             let points = metrics.entry(name.to_string()).or_insert_with(Vec::new);
@@ -91,6 +245,8 @@ impl MetricsCollector {
         let message_count = *self.message_count.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
         let error_count = *self.error_count.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
         let latency_samples = self.latency_samples.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        let mut sorted_latencies = latency_samples.clone();
+        sorted_latencies.sort();
         
         let throughput = if uptime.as_secs() > 0 {
             message_count as f64 / uptime.as_secs() as f64
@@ -111,9 +267,9 @@ impl MetricsCollector {
             Duration::from_millis(0)
         };
         
-        // Simulate system metrics (in a real system, these would come from the OS)
-        let memory_usage = self.estimate_memory_usage();
-        let cpu_usage = self.estimate_cpu_usage();
+        // Measured resource usage when probing, otherwise estimated from traffic
+        let (memory_usage, cpu_usage) = self.sample_system()
+            .unwrap_or_else(|| (self.estimate_memory_usage(), self.estimate_cpu_usage()));
         let network_utilization = self.estimate_network_usage();
         
         PerformanceMetrics {
@@ -125,6 +281,10 @@ impl MetricsCollector {
             network_utilization,
             uptime,
             last_updated: Utc::now(),
+            latency_histogram: histogram(&latency_samples, &self.latency_buckets),
+            latency_p50: percentile_of_sorted(&sorted_latencies, 50.0),
+            latency_p90: percentile_of_sorted(&sorted_latencies, 90.0),
+            latency_p99: percentile_of_sorted(&sorted_latencies, 99.0),
         }
     }
     
@@ -136,6 +296,31 @@ impl MetricsCollector {
         }
     }
     
+    /// Metrics snapshot plus the latest value of each custom metric, in Prometheus text format
+    pub fn export_prometheus(&self) -> String {
+        let mut output = export_prometheus(&self.get_metrics());
+        
+        let mut latest: Vec<(String, f64)> = match self.custom_metrics.lock() {
+            Ok(metrics) => metrics.iter()
+                .filter_map(|(name, points)| points.last().map(|point| (name.clone(), point.value)))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        latest.sort_by(|a, b| a.0.cmp(&b.0));
+        // Distinct names can sanitize to the same metric; the first in name order is exported
+        let mut exported: HashMap<String, String> = HashMap::new();
+        for (name, value) in latest {
+            let metric = format!("rustsat_custom_{}", prometheus_name(&name));
+            if let Some(first) = exported.get(&metric) {
+                warn!("Custom metric '{}' collides with '{}' as {}, skipping it", name, first, metric);
+                continue;
+            }
+            write_metric(&mut output, &metric, "gauge", &format!("Custom metric {}", name), value);
+            exported.insert(metric, name);
+        }
+        output
+    }
+    
     pub fn reset_metrics(&self) {
         if let Ok(mut count) = self.message_count.lock() {
             *count = 0;
@@ -146,11 +331,19 @@ impl MetricsCollector {
         if let Ok(mut samples) = self.latency_samples.lock() {
             samples.clear();
         }
+        if let Ok(mut history) = self.throughput_history.lock() {
+            history.clear();
+        }
         if let Ok(mut metrics) = self.custom_metrics.lock() {
             metrics.clear();
         }
     }
     
+    fn sample_system(&self) -> Option<(u64, f64)> {
+        let mut probe = self.system_probe.as_ref()?.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        probe.sample()
+    }
+    
     // Simulate memory usage estimation
     fn estimate_memory_usage(&self) -> u64 {
         // In a real implementation, this would use system APIs
@@ -184,9 +377,68 @@ impl MetricsCollector {
     }
 }
 
+/// Nearest-rank percentile of ascending samples; `None` if empty or `p` is outside 0-100
+fn percentile_of_sorted(sorted: &[Duration], p: f64) -> Option<Duration> {
+    if sorted.is_empty() || !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+fn histogram(samples: &[Duration], buckets: &[Duration]) -> Option<LatencyHistogram> {
+    if samples.is_empty() {
+        return None;
+    }
+    Some(LatencyHistogram {
+        buckets: buckets.iter()
+            .map(|&bound| (bound, samples.iter().filter(|&&sample| sample <= bound).count() as u64))
+            .collect(),
+        count: samples.len() as u64,
+        sum: samples.iter().sum(),
+    })
+}
+
+/// Render a metrics snapshot in the Prometheus text exposition format
+pub fn export_prometheus(metrics: &PerformanceMetrics) -> String {
+    let mut output = String::new();
+    write_metric(&mut output, "rustsat_message_throughput", "gauge", "Messages processed per second", metrics.message_throughput);
+    write_metric(&mut output, "rustsat_avg_latency_ms", "gauge", "Average message latency in milliseconds", metrics.average_latency.as_secs_f64() * 1000.0);
+    write_metric(&mut output, "rustsat_error_rate", "gauge", "Percentage of failed operations", metrics.error_rate);
+    write_metric(&mut output, "rustsat_memory_usage_bytes", "gauge", "Memory in use in bytes", metrics.memory_usage as f64);
+    write_metric(&mut output, "rustsat_cpu_usage_percent", "gauge", "CPU usage percentage", metrics.cpu_usage);
+    write_metric(&mut output, "rustsat_network_utilization_percent", "gauge", "Network utilization percentage", metrics.network_utilization);
+    write_metric(&mut output, "rustsat_uptime_seconds", "counter", "Seconds since the collector started", metrics.uptime.as_secs_f64());
+    output
+}
+
+fn write_metric(output: &mut String, name: &str, kind: &str, help: &str, value: f64) {
+    // HELP text escapes backslashes and line feeds
+    let help = help.replace('\\', "\\\\").replace('\n', "\\n");
+    output.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n{} {}\n", name, help, name, kind, name, prometheus_value(value)));
+}
+
+/// Prometheus spells non-finite values `NaN`, `+Inf` and `-Inf`
+fn prometheus_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf".to_string() } else { "-Inf".to_string() }
+    } else {
+        value.to_string()
+    }
+}
+
+/// Replace characters not allowed in Prometheus metric names with underscores
+fn prometheus_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == ':' { c } else { '_' })
+        .collect()
+}
+
 // Global metrics instance for easy access
 lazy_static::lazy_static! {
-    pub static ref GLOBAL_METRICS: MetricsCollector = MetricsCollector::new();
+    pub static ref GLOBAL_METRICS: MetricsCollector = MetricsCollector::with_system_probe();
 }
 
 // Convenience macros for recording metrics
@@ -253,7 +505,6 @@ macro_rules! time_operation {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::thread;
     use std::time::Duration;
     
     #[test]
@@ -286,4 +537,131 @@ mod tests {
         assert_eq!(history[0].value, 25.5);
         assert_eq!(history[1].value, 26.0);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_prometheus_export_is_well_formed() {
+        let collector = MetricsCollector::new();
+        collector.record_message();
+        collector.record_latency(Duration::from_millis(40));
+        collector.record_custom_metric("battery.voltage", 7.4);
+        collector.record_custom_metric("battery.voltage", 7.2);
+        
+        let output = collector.export_prometheus();
+        let mut samples = HashMap::new();
+        for line in output.lines() {
+            if let Some(comment) = line.strip_prefix("# ") {
+                let mut parts = comment.splitn(3, ' ');
+                assert!(matches!(parts.next(), Some("HELP") | Some("TYPE")), "bad comment: {}", line);
+                assert!(parts.next().unwrap().starts_with("rustsat_"));
+                assert!(parts.next().is_some_and(|rest| !rest.is_empty()));
+                continue;
+            }
+            let (name, value) = line.split_once(' ').unwrap();
+            assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':'), "bad name: {}", name);
+            samples.insert(name.to_string(), value.parse::<f64>().unwrap());
+        }
+        
+        assert_eq!(samples["rustsat_avg_latency_ms"], 40.0);
+        assert_eq!(samples["rustsat_error_rate"], 0.0);
+        assert!(samples.contains_key("rustsat_message_throughput"));
+        assert_eq!(samples["rustsat_custom_battery_voltage"], 7.2);
+        assert!(output.contains("# TYPE rustsat_uptime_seconds counter"));
+    }
+    
+    #[test]
+    fn test_prometheus_export_deduplicates_sanitized_names() {
+        let collector = MetricsCollector::new();
+        collector.record_custom_metric("link.snr", 12.0);
+        collector.record_custom_metric("link-snr", 3.0);
+        collector.record_custom_metric("path\\to\nnext", 1.0);
+        
+        let output = collector.export_prometheus();
+        assert_eq!(output.matches("# TYPE rustsat_custom_link_snr gauge").count(), 1);
+        assert_eq!(output.matches("\nrustsat_custom_link_snr ").count(), 1);
+        assert!(output.contains("\nrustsat_custom_link_snr 3\n"));
+        assert!(output.contains("# HELP rustsat_custom_path_to_next Custom metric path\\\\to\\nnext\n"));
+        assert!(output.lines().all(|line| line.starts_with('#') || line.starts_with("rustsat_")));
+    }
+    
+    #[test]
+    fn test_latency_percentiles_expose_tail() {
+        let collector = MetricsCollector::new();
+        assert!(collector.latency_percentile(50.0).is_none());
+        assert!(collector.get_metrics().latency_histogram.is_none());
+        
+        for i in 0..1000 {
+            let latency = if i % 20 == 0 { 1000 } else { 10 };
+            collector.record_latency(Duration::from_millis(latency));
+        }
+        
+        let p50 = collector.latency_percentile(50.0).unwrap();
+        let p99 = collector.latency_percentile(99.0).unwrap();
+        assert_eq!(p50, Duration::from_millis(10));
+        assert_eq!(p99, Duration::from_millis(1000));
+        assert!(p99 >= p50 * 50);
+        assert!(collector.latency_percentile(101.0).is_none());
+        
+        let metrics = collector.get_metrics();
+        assert_eq!(metrics.latency_p50, Some(p50));
+        assert_eq!(metrics.latency_p90, Some(Duration::from_millis(10)));
+        assert_eq!(metrics.latency_p99, Some(p99));
+        let histogram = metrics.latency_histogram.unwrap();
+        assert_eq!(histogram.count, 1000);
+        assert!(histogram.buckets.contains(&(Duration::from_millis(10), 950)));
+        assert!(histogram.buckets.contains(&(Duration::from_millis(1000), 1000)));
+    }
+    
+    #[test]
+    fn test_configurable_latency_retention_and_buckets() {
+        let collector = MetricsCollector::new()
+            .with_latency_sample_limit(100)
+            .with_latency_buckets(vec![Duration::from_millis(100), Duration::from_millis(20)]);
+        for ms in 0..150 {
+            collector.record_latency(Duration::from_millis(ms));
+        }
+        
+        let histogram = collector.latency_histogram().unwrap();
+        assert_eq!(histogram.count, 100);
+        assert_eq!(histogram.buckets, vec![(Duration::from_millis(20), 0), (Duration::from_millis(100), 51)]);
+        assert_eq!(collector.latency_percentile(0.0), Some(Duration::from_millis(50)));
+    }
+    
+    #[test]
+    #[cfg(feature = "system-probe")]
+    fn test_system_probe_reports_process_memory() {
+        let collector = MetricsCollector::with_system_probe();
+        assert!(collector.system_probe.is_some());
+        
+        let metrics = collector.get_metrics();
+        assert!(metrics.memory_usage > 1024 * 1024, "implausibly small RSS: {}", metrics.memory_usage);
+        assert!(metrics.memory_usage < 64 * 1024 * 1024 * 1024, "implausibly large RSS: {}", metrics.memory_usage);
+        assert!(metrics.cpu_usage >= 0.0);
+        
+        // Without the probe the traffic-based estimate is used
+        assert_eq!(MetricsCollector::new().get_metrics().memory_usage, 50 * 1024 * 1024);
+    }
+    
+    #[test]
+    #[cfg(not(feature = "system-probe"))]
+    fn test_resource_usage_estimated_without_system_probe() {
+        let collector = MetricsCollector::with_system_probe();
+        assert!(collector.system_probe.is_none());
+        assert_eq!(collector.get_metrics().memory_usage, 50 * 1024 * 1024);
+    }
+    
+    #[test]
+    fn test_windowed_throughput_decays_after_burst() {
+        let collector = MetricsCollector::new();
+        for _ in 0..100 {
+            collector.record_message();
+        }
+        assert!(collector.throughput_window(1) > 0.0);
+        
+        std::thread::sleep(Duration::from_millis(1200));
+        
+        assert_eq!(collector.throughput_window(1), 0.0);
+        assert!(collector.throughput_window(60) > 0.0);
+        // The lifetime average still counts the burst
+        assert!(collector.get_metrics().message_throughput >= 50.0);
+    }
+}
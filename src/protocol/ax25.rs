@@ -0,0 +1,181 @@
+// AX.25 UI framing for amateur-radio UHF beacon downlinks
+use serde::{Deserialize, Serialize};
+use crate::cubesat::CubeSatFrame;
+
+/// Control field for an Unnumbered Information frame (P/F bit clear)
+pub const AX25_CONTROL_UI: u8 = 0x03;
+/// Protocol identifier for "no layer 3 protocol"
+pub const AX25_PID_NO_LAYER3: u8 = 0xF0;
+/// Length of one encoded address (6 callsign bytes + SSID byte)
+const ADDRESS_LEN: usize = 7;
+/// CRC residue obtained when the FCS is included in the computation
+const FCS_GOOD_RESIDUE: u16 = 0xF0B8;
+
+/// Amateur radio station identifier, e.g. `ON0ESA-1`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Callsign {
+    pub call: String,
+    pub ssid: u8,
+}
+
+/// Decoded AX.25 UI frame carrying a CubeSat frame
+#[derive(Debug, Clone)]
+pub struct Ax25UiFrame {
+    pub destination: Callsign,
+    pub source: Callsign,
+    pub pid: u8,
+    pub frame: CubeSatFrame,
+}
+
+impl Callsign {
+    /// Parse `CALL` or `CALL-SSID`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (call, ssid) = match text.split_once('-') {
+            Some((call, ssid)) => (call, ssid.parse::<u8>().map_err(|_| format!("Invalid SSID in {}", text))?),
+            None => (text, 0),
+        };
+        let call = call.to_ascii_uppercase();
+        if call.is_empty() || call.len() > 6 || !call.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("Invalid callsign {}", text));
+        }
+        if ssid > 15 {
+            return Err(format!("SSID must be 0-15, got {}", ssid));
+        }
+        Ok(Self { call, ssid })
+    }
+
+    /// Encode as a 7-byte address field, setting the extension bit on the last address
+    fn encode(&self, last: bool) -> [u8; ADDRESS_LEN] {
+        let mut bytes = [b' ' << 1; ADDRESS_LEN];
+        for (byte, c) in bytes.iter_mut().zip(self.call.bytes()) {
+            *byte = c << 1;
+        }
+        bytes[6] = 0x60 | (self.ssid << 1) | last as u8;
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let call: String = bytes[..6].iter()
+            .map(|&b| (b >> 1) as char)
+            .collect::<String>()
+            .trim_end()
+            .to_string();
+        Self::parse(&call).map(|callsign| Self { ssid: (bytes[6] >> 1) & 0x0F, ..callsign })
+    }
+}
+
+impl std::fmt::Display for Callsign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ssid == 0 {
+            write!(f, "{}", self.call)
+        } else {
+            write!(f, "{}-{}", self.call, self.ssid)
+        }
+    }
+}
+
+/// AX.25 frame check sequence (CRC-16/X.25)
+pub fn fcs(data: &[u8]) -> u16 {
+    !crc_register(data)
+}
+
+/// Shift data through the CRC register bit by bit, LSB first as sent on air
+fn crc_register(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for &byte in data {
+        for bit in 0..8 {
+            let feedback = (crc as u8 & 1) ^ ((byte >> bit) & 1);
+            crc >>= 1;
+            if feedback != 0 {
+                crc ^= 0x8408;
+            }
+        }
+    }
+    crc
+}
+
+/// Wrap a CubeSat frame in an AX.25 UI frame (without HDLC flags or bit stuffing)
+pub fn to_ax25(frame: &CubeSatFrame, src_call: &str, dst_call: &str) -> Result<Vec<u8>, String> {
+    let destination = Callsign::parse(dst_call)?;
+    let source = Callsign::parse(src_call)?;
+
+    let mut encoded = Vec::new();
+    encoded.extend_from_slice(&destination.encode(false));
+    encoded.extend_from_slice(&source.encode(true));
+    encoded.push(AX25_CONTROL_UI);
+    encoded.push(AX25_PID_NO_LAYER3);
+    encoded.extend_from_slice(&frame.encode());
+
+    // FCS is sent low byte first
+    let fcs = fcs(&encoded);
+    encoded.extend_from_slice(&fcs.to_le_bytes());
+    Ok(encoded)
+}
+
+/// Verify and unwrap an AX.25 UI frame produced by `to_ax25`
+pub fn from_ax25(bytes: &[u8]) -> Result<Ax25UiFrame, String> {
+    let header_len = 2 * ADDRESS_LEN + 2;
+    if bytes.len() < header_len + 2 {
+        return Err("AX.25 frame too short".to_string());
+    }
+
+    if crc_register(bytes) != FCS_GOOD_RESIDUE {
+        return Err("AX.25 FCS mismatch".to_string());
+    }
+
+    if bytes[ADDRESS_LEN - 1] & 0x01 != 0 || bytes[2 * ADDRESS_LEN - 1] & 0x01 == 0 {
+        return Err("Digipeater paths are not supported".to_string());
+    }
+    if bytes[2 * ADDRESS_LEN] != AX25_CONTROL_UI {
+        return Err(format!("Not a UI frame (control 0x{:02X})", bytes[2 * ADDRESS_LEN]));
+    }
+
+    let info = &bytes[header_len..bytes.len() - 2];
+    let frame = CubeSatFrame::decode(info).ok_or("Invalid CubeSat frame in AX.25 information field")?;
+
+    Ok(Ax25UiFrame {
+        destination: Callsign::decode(&bytes[..ADDRESS_LEN])?,
+        source: Callsign::decode(&bytes[ADDRESS_LEN..2 * ADDRESS_LEN])?,
+        pid: bytes[2 * ADDRESS_LEN + 1],
+        frame,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cubesat::FrameType;
+
+    #[test]
+    fn test_fcs_known_vectors() {
+        // CRC-16/X.25 check value
+        assert_eq!(fcs(b"123456789"), 0x906E);
+
+        // Appending the FCS low byte first leaves the standard good-frame residue
+        let mut framed = b"123456789".to_vec();
+        framed.extend_from_slice(&0x906Eu16.to_le_bytes());
+        assert_eq!(crc_register(&framed), FCS_GOOD_RESIDUE);
+    }
+
+    #[test]
+    fn test_ui_frame_round_trip() {
+        let beacon = CubeSatFrame::new(FrameType::Beacon, b"BEACON:1;PWR:98.0".to_vec(), 1, 0);
+        let encoded = to_ax25(&beacon, "ON0ESA-1", "CQ").unwrap();
+
+        // Callsign characters are shifted left one bit, padded with spaces
+        assert_eq!(&encoded[..7], &[b'C' << 1, b'Q' << 1, 0x40, 0x40, 0x40, 0x40, 0x60]);
+        assert_eq!(encoded[13], 0x60 | (1 << 1) | 1);
+        assert_eq!(encoded[14..16], [AX25_CONTROL_UI, AX25_PID_NO_LAYER3]);
+
+        let decoded = from_ax25(&encoded).unwrap();
+        assert_eq!(decoded.source.to_string(), "ON0ESA-1");
+        assert_eq!(decoded.destination.to_string(), "CQ");
+        assert_eq!(decoded.frame.payload, beacon.payload);
+        assert_eq!(decoded.frame.frame_type, FrameType::Beacon);
+
+        let mut corrupted = encoded.clone();
+        corrupted[20] ^= 0x01;
+        assert!(from_ax25(&corrupted).is_err());
+        assert!(to_ax25(&beacon, "TOOLONGCALL", "CQ").is_err());
+    }
+}
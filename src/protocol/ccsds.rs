@@ -0,0 +1,196 @@
+// CCSDS Space Packet Protocol (CCSDS 133.0-B) encoding for SpaceCAN payloads
+use serde::{Deserialize, Serialize};
+use super::spacecan::{FramePriority, SpaceCANFrame};
+
+/// Length of the Space Packet primary header in bytes
+pub const PRIMARY_HEADER_LEN: usize = 6;
+/// Largest Application Process Identifier (11 bits)
+pub const MAX_APID: u16 = 0x07FF;
+/// Idle packets use the all-ones APID
+pub const IDLE_APID: u16 = MAX_APID;
+/// Packet sequence count wraps at 14 bits
+pub const SEQUENCE_COUNT_MODULO: u16 = 0x4000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PacketType {
+    Telemetry = 0,
+    Telecommand = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SequenceFlags {
+    Continuation = 0b00,
+    First = 0b01,
+    Last = 0b10,
+    Unsegmented = 0b11,
+}
+
+/// Space Packet primary header fields
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrimaryHeader {
+    pub version: u8,                 // 3 bits, always 0 for version 1 packets
+    pub packet_type: PacketType,
+    pub secondary_header: bool,
+    pub apid: u16,                   // 11 bits
+    pub sequence_flags: SequenceFlags,
+    pub sequence_count: u16,         // 14 bits
+    pub data_length: u16,            // packet data field length minus one
+}
+
+/// A complete Space Packet: primary header plus packet data field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpacePacket {
+    pub header: PrimaryHeader,
+    pub data: Vec<u8>,
+}
+
+impl PrimaryHeader {
+    /// Pack the header into its 6-byte big-endian wire form
+    pub fn encode(&self) -> [u8; PRIMARY_HEADER_LEN] {
+        let identification = ((self.version as u16 & 0x07) << 13)
+            | ((self.packet_type as u16) << 12)
+            | ((self.secondary_header as u16) << 11)
+            | (self.apid & MAX_APID);
+        let sequence = ((self.sequence_flags as u16) << 14) | (self.sequence_count % SEQUENCE_COUNT_MODULO);
+
+        let mut bytes = [0u8; PRIMARY_HEADER_LEN];
+        bytes[0..2].copy_from_slice(&identification.to_be_bytes());
+        bytes[2..4].copy_from_slice(&sequence.to_be_bytes());
+        bytes[4..6].copy_from_slice(&self.data_length.to_be_bytes());
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < PRIMARY_HEADER_LEN {
+            return Err("Space packet header too short".to_string());
+        }
+        let identification = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let sequence = u16::from_be_bytes([bytes[2], bytes[3]]);
+
+        let version = (identification >> 13) as u8;
+        if version != 0 {
+            return Err(format!("Unsupported space packet version {}", version));
+        }
+
+        Ok(Self {
+            version,
+            packet_type: if identification & 0x1000 != 0 { PacketType::Telecommand } else { PacketType::Telemetry },
+            secondary_header: identification & 0x0800 != 0,
+            apid: identification & MAX_APID,
+            sequence_flags: match sequence >> 14 {
+                0b00 => SequenceFlags::Continuation,
+                0b01 => SequenceFlags::First,
+                0b10 => SequenceFlags::Last,
+                _ => SequenceFlags::Unsegmented,
+            },
+            sequence_count: sequence & (SEQUENCE_COUNT_MODULO - 1),
+            data_length: u16::from_be_bytes([bytes[4], bytes[5]]),
+        })
+    }
+}
+
+impl SpacePacket {
+    pub fn new(packet_type: PacketType, apid: u16, sequence_flags: SequenceFlags, sequence_count: u16, data: Vec<u8>) -> Result<Self, String> {
+        if apid > MAX_APID {
+            return Err(format!("APID {} exceeds 11 bits", apid));
+        }
+        if data.is_empty() || data.len() > u16::MAX as usize + 1 {
+            return Err(format!("Packet data field must be 1-65536 bytes, got {}", data.len()));
+        }
+
+        Ok(Self {
+            header: PrimaryHeader {
+                version: 0,
+                packet_type,
+                secondary_header: false,
+                apid,
+                sequence_flags,
+                sequence_count: sequence_count % SEQUENCE_COUNT_MODULO,
+                data_length: (data.len() - 1) as u16,
+            },
+            data,
+        })
+    }
+
+    /// Wrap a SpaceCAN frame payload as a telemetry packet, mapping fragments to sequence flags
+    pub fn from_frame(frame: &SpaceCANFrame) -> Result<Self, String> {
+        let sequence_flags = match (frame.fragment_index, frame.fragment_total) {
+            (_, 0..=1) => SequenceFlags::Unsegmented,
+            (0, _) => SequenceFlags::First,
+            (index, total) if index + 1 == total => SequenceFlags::Last,
+            _ => SequenceFlags::Continuation,
+        };
+        Self::new(
+            PacketType::Telemetry,
+            (frame.id & MAX_APID as u32) as u16,
+            sequence_flags,
+            frame.sequence_number,
+            frame.data.clone(),
+        )
+    }
+
+    /// Rebuild a SpaceCAN frame carrying this packet's user data
+    pub fn to_frame(&self, priority: FramePriority) -> SpaceCANFrame {
+        let mut frame = SpaceCANFrame::new(self.header.apid as u32, self.data.clone(), priority);
+        frame.sequence_number = self.header.sequence_count;
+        frame
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(PRIMARY_HEADER_LEN + self.data.len());
+        encoded.extend_from_slice(&self.header.encode());
+        encoded.extend_from_slice(&self.data);
+        encoded
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let header = PrimaryHeader::decode(bytes)?;
+        let end = PRIMARY_HEADER_LEN + header.data_length as usize + 1;
+        if bytes.len() < end {
+            return Err(format!("Space packet truncated: expected {} bytes, got {}", end, bytes.len()));
+        }
+        Ok(Self {
+            data: bytes[PRIMARY_HEADER_LEN..end].to_vec(),
+            header,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primary_header_field_layout() {
+        let packet = SpacePacket::new(PacketType::Telecommand, 0x5A5, SequenceFlags::First, 0x1234, vec![0xAB; 10]).unwrap();
+        let bytes = packet.header.encode();
+
+        // 000 | 1 | 0 | 101 1010 0101
+        assert_eq!(bytes[0..2], [0b0001_0101, 0xA5]);
+        // 01 | 01 0010 0011 0100
+        assert_eq!(bytes[2..4], [0b0101_0010, 0x34]);
+        // Data length is octets minus one
+        assert_eq!(bytes[4..6], [0x00, 0x09]);
+        assert_eq!(PrimaryHeader::decode(&bytes).unwrap(), packet.header);
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let frames = SpaceCANFrame::fragment(0x123, &[9u8; 40], FramePriority::High, 16).unwrap();
+        let flags: Vec<SequenceFlags> = frames.iter()
+            .map(|frame| SpacePacket::from_frame(frame).unwrap().header.sequence_flags)
+            .collect();
+        assert_eq!(flags, vec![SequenceFlags::First, SequenceFlags::Continuation, SequenceFlags::Last]);
+
+        let frame = SpaceCANFrame::new(0x123, b"housekeeping".to_vec(), FramePriority::Normal);
+        let packet = SpacePacket::from_frame(&frame).unwrap();
+        let decoded = SpacePacket::decode(&packet.encode()).unwrap();
+        assert_eq!(decoded, packet);
+        assert_eq!(decoded.header.sequence_flags, SequenceFlags::Unsegmented);
+
+        let rebuilt = decoded.to_frame(FramePriority::Normal);
+        assert_eq!(rebuilt.id, 0x123);
+        assert_eq!(rebuilt.data, frame.data);
+        assert!(SpacePacket::decode(&packet.encode()[..10]).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// Contact plan for contact-graph routing over scheduled ground-station passes
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Duration, Utc};
+use crate::ground_station::ContactWindow;
+
+/// One-way opportunity for `from` to transmit to `to` between `start` and `end`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    pub from: u32,
+    pub to: u32,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Contact {
+    /// Whether a transmission starting at `time` and taking `delay` fits inside the contact
+    pub fn carries(&self, time: DateTime<Utc>, delay: Duration) -> bool {
+        self.start <= time && time + delay <= self.end
+    }
+}
+
+/// Scheduled contacts between nodes, used to route across links that come and go
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContactPlan {
+    contacts: Vec<Contact>,
+}
+
+impl ContactPlan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a contact usable in both directions
+    pub fn add_contact(&mut self, node1: u32, node2: u32, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<(), String> {
+        if end <= start {
+            return Err(format!("Contact between {} and {} ends before it starts", node1, node2));
+        }
+        self.contacts.push(Contact { from: node1, to: node2, start, end });
+        self.contacts.push(Contact { from: node2, to: node1, start, end });
+        Ok(())
+    }
+
+    /// Add a ground station's scheduled passes as station <-> satellite contacts
+    pub fn ingest_windows(&mut self, station_id: u32, windows: &[ContactWindow]) -> Result<usize, String> {
+        for window in windows {
+            self.add_contact(station_id, window.satellite_id, window.start_time, window.end_time)?;
+        }
+        Ok(windows.len())
+    }
+
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// Drop contacts that ended before `now`
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        self.contacts.retain(|contact| contact.end >= now);
+    }
+
+    /// Earliest-arrival route from `source` departing at `time`, with no waiting at relays:
+    /// each hop must fit inside a contact that is open when the packet reaches that node.
+    /// Returns the hops after the source and the delivery time.
+    pub fn earliest_arrival<F>(&self, source: u32, destination: u32, time: DateTime<Utc>, delay: F) -> Option<(Vec<u32>, DateTime<Utc>)>
+    where
+        F: Fn(u32, u32) -> Duration,
+    {
+        let mut arrival: HashMap<u32, DateTime<Utc>> = HashMap::from([(source, time)]);
+        let mut previous: HashMap<u32, u32> = HashMap::new();
+        let mut settled: HashSet<u32> = HashSet::new();
+
+        while let Some((&current, &reached)) = arrival.iter()
+            .filter(|(node_id, _)| !settled.contains(node_id))
+            .min_by_key(|(&node_id, &reached)| (reached, node_id))
+        {
+            if current == destination {
+                break;
+            }
+            settled.insert(current);
+
+            for contact in self.contacts.iter().filter(|contact| contact.from == current) {
+                let hop_delay = delay(contact.from, contact.to);
+                if settled.contains(&contact.to) || !contact.carries(reached, hop_delay) {
+                    continue;
+                }
+                let candidate = reached + hop_delay;
+                if arrival.get(&contact.to).map_or(true, |&best| candidate < best) {
+                    arrival.insert(contact.to, candidate);
+                    previous.insert(contact.to, current);
+                }
+            }
+        }
+
+        let delivered_at = *arrival.get(&destination)?;
+        let mut path = Vec::new();
+        let mut current = destination;
+        while current != source {
+            path.push(current);
+            current = previous[&current];
+        }
+        path.reverse();
+        Some((path, delivered_at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_only_inside_overlapping_windows() {
+        let start = Utc::now();
+        let mut plan = ContactPlan::new();
+        // Station 10 sees satellite 1 for minutes 0-15, station 20 for minutes 10-25
+        plan.add_contact(10, 1, start, start + Duration::minutes(15)).unwrap();
+        plan.add_contact(1, 20, start + Duration::minutes(10), start + Duration::minutes(25)).unwrap();
+        let delay = |_, _| Duration::milliseconds(5);
+
+        let departure = start + Duration::minutes(12);
+        let (path, delivered_at) = plan.earliest_arrival(10, 20, departure, delay).unwrap();
+        assert_eq!(path, vec![1, 20]);
+        assert_eq!(delivered_at, departure + Duration::milliseconds(10));
+
+        assert!(plan.earliest_arrival(10, 20, start + Duration::minutes(5), delay).is_none());
+        assert!(plan.earliest_arrival(10, 20, start + Duration::minutes(20), delay).is_none());
+        assert!(plan.add_contact(1, 2, start, start).is_err());
+    }
+}
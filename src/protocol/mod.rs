@@ -2,6 +2,11 @@
 
 pub mod spacecan;
 pub mod network;
+pub mod ccsds;
+pub mod ax25;
+pub mod contact_plan;
+pub mod reed_solomon;
 
-pub use spacecan::{SpaceCANFrame, SpaceCANAdapter, FramePriority, PowerMode};
-pub use network::{MeshNetwork, RoutingTable, NetworkNode};
\ No newline at end of file
+pub use spacecan::{SpaceCANFrame, SpaceCANAdapter, FramePriority, PowerMode, Reassembler, FrequencyBand, DedupWindow};
+pub use network::{MeshNetwork, RoutingTable, NetworkNode, RouteSearch};
+pub use contact_plan::{Contact, ContactPlan};
\ No newline at end of file
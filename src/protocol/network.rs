@@ -1,8 +1,12 @@
 // Mesh networking and routing algorithms for CubeSat constellations
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 use chrono::{DateTime, Utc, Duration};
-use log::{info, warn, error, debug};
+use log::{info, warn, debug};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use super::contact_plan::ContactPlan;
 
 /// Network node representing a CubeSat or ground station
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +19,7 @@ pub struct NetworkNode {
     pub last_seen: DateTime<Utc>,
     pub battery_level: f64,  // 0.0 to 1.0
     pub neighbors: HashSet<u32>,
+    pub routing_table: RoutingTable,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -44,10 +49,24 @@ pub struct RoutingEntry {
 }
 
 /// Routing table for mesh network
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RoutingTable {
     entries: HashMap<u32, RoutingEntry>,
-    update_interval: Duration,
+}
+
+impl RoutingTable {
+    /// Best known route to a destination
+    pub fn route_to(&self, destination: u32) -> Option<&RoutingEntry> {
+        self.entries.get(&destination)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 }
 
 /// Network packet for routing through the mesh
@@ -64,23 +83,126 @@ pub struct NetworkPacket {
     pub route_history: Vec<u32>,
 }
 
+/// Route computation used by `MeshNetwork` when forwarding packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RoutingAlgo {
+    #[default]
+    LinkState,       // Dijkstra over the full topology
+    DistanceVector,  // Bellman-Ford style per-node distance vectors
+    AStar,           // Dijkstra guided by straight-line distance to the destination
+}
+
+/// Result of a shortest-path search, with the work it took
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteSearch {
+    pub path: Vec<u32>,  // hops after the source, ending at the destination
+    pub cost: f64,
+    pub nodes_expanded: usize,
+}
+
+/// Open-set entry ordered so the binary heap pops the lowest estimate first
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SearchEntry {
+    estimate: f64,
+    node_id: u32,
+}
+
+impl Eq for SearchEntry {}
+
+impl Ord for SearchEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+            .then_with(|| other.node_id.cmp(&self.node_id))
+    }
+}
+
+impl PartialOrd for SearchEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Payload prefix marking a network-layer acknowledgment packet
+pub const ACK_PAYLOAD_PREFIX: &[u8] = b"ACK";
+/// Packet priorities, lowest to highest
+pub const PRIORITY_LOW: u8 = 0;
+pub const PRIORITY_NORMAL: u8 = 1;
+pub const PRIORITY_HIGH: u8 = 2;
+pub const PRIORITY_EMERGENCY: u8 = 3;
+/// Extra cost per priority level applied to the unreliable fraction of a link
+const PRIORITY_RELIABILITY_WEIGHT: f64 = 2.0;
+/// Nodes below this battery level only relay emergency traffic
+pub const LOW_BATTERY_RELAY_THRESHOLD: f64 = 0.2;
+/// Offered load (packets) at which a link's cost doubles
+pub const LINK_CONGESTION_PACKETS: f64 = 10.0;
+/// Time constant for offered load to decay back towards idle
+pub const LINK_LOAD_DECAY_SECS: f64 = 60.0;
+/// Reliability assumed for a link with no observed transmissions
+pub const INITIAL_LINK_RELIABILITY: f64 = 0.9;
+/// Weight of the newest outcome in the link reliability moving average
+const LINK_RELIABILITY_SMOOTHING: f64 = 0.2;
+/// Default time a packet is held in the buffer waiting for a contact or route
+pub const DEFAULT_CUSTODY_LIFETIME_SECS: i64 = 3600;
+
+/// Packet held in the store-and-forward buffer until it is flushed or expires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BufferedPacket {
+    packet: NetworkPacket,
+    expires_at: DateTime<Utc>,
+    custody: bool, // Held under DTN custody because no route existed; retried by `flush_custody`
+}
+
 /// Advanced mesh network implementation for CubeSat constellations
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshNetwork {
     nodes: HashMap<u32, NetworkNode>,
-    routing_table: RoutingTable,
-    packet_buffer: VecDeque<NetworkPacket>,
+    packet_buffer: VecDeque<BufferedPacket>,
     network_topology: NetworkTopology,
     ground_stations: HashSet<u32>,
     statistics: NetworkStatistics,
+    routing_algorithm: RoutingAlgo,
+    custody_lifetime: Duration,
+    component_count: usize,
+    link_failure_probability: f64,
+    contact_plan: ContactPlan,
+    rng: ChaCha12Rng, // Drives packet ids and simulated link failures
+}
+
+/// Exportable snapshot of the mesh for visualization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyGraph {
+    pub nodes: Vec<(u32, OrbitalPosition)>,
+    pub edges: Vec<(u32, u32, f64)>,  // (node1, node2, link quality)
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NetworkTopology {
+    #[serde(with = "node_pair_map")]
     adjacency_matrix: HashMap<(u32, u32), f64>,  // (node1, node2) -> link quality
     connectivity_graph: HashMap<u32, HashSet<u32>>,
+    #[serde(with = "node_pair_map")]
+    offered_load: HashMap<(u32, u32), f64>,  // (lower id, higher id) -> recent packets carried
+    #[serde(with = "node_pair_map")]
+    link_reliability: HashMap<(u32, u32), f64>,  // (lower id, higher id) -> observed success rate
+}
+
+/// Maps keyed by node pairs serialize as sorted lists, since JSON object keys must be strings
+mod node_pair_map {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(map: &HashMap<(u32, u32), f64>, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entries: Vec<(&(u32, u32), &f64)> = map.iter().collect();
+        entries.sort_by_key(|(key, _)| **key);
+        serializer.collect_seq(entries)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<(u32, u32), f64>, D::Error> {
+        Ok(Vec::<((u32, u32), f64)>::deserialize(deserializer)?.into_iter().collect())
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NetworkStatistics {
     pub packets_routed: u64,
     pub packets_dropped: u64,
@@ -88,20 +210,53 @@ pub struct NetworkStatistics {
     pub network_utilization: f64,
     pub handovers_completed: u64,
     pub total_latency: Duration,
+    pub custody_deliveries: u64,
+    pub custody_expired: u64,
+    pub routed_by_priority: HashMap<u8, u64>,
+    pub dropped_by_priority: HashMap<u8, u64>,
+    pub emergency_replicas: u64,
+    pub partition_events: u64,
 }
 
 impl MeshNetwork {
     pub fn new() -> Self {
+        Self::with_rng(ChaCha12Rng::from_entropy())
+    }
+
+    /// Network whose packet ids and simulated link failures are determined by `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(ChaCha12Rng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: ChaCha12Rng) -> Self {
         Self {
             nodes: HashMap::new(),
-            routing_table: RoutingTable::default(),
             packet_buffer: VecDeque::new(),
             network_topology: NetworkTopology::default(),
             ground_stations: HashSet::new(),
             statistics: NetworkStatistics::default(),
+            routing_algorithm: RoutingAlgo::default(),
+            custody_lifetime: Duration::seconds(DEFAULT_CUSTODY_LIFETIME_SECS),
+            component_count: 0,
+            link_failure_probability: 0.0,
+            contact_plan: ContactPlan::default(),
+            rng,
         }
     }
 
+    /// Select the algorithm used to compute routes
+    pub fn set_routing_algorithm(&mut self, algorithm: RoutingAlgo) {
+        info!("Routing algorithm set to {:?}", algorithm);
+        self.routing_algorithm = algorithm;
+        if algorithm == RoutingAlgo::DistanceVector {
+            self.build_routing_table();
+        }
+    }
+
+    pub fn routing_algorithm(&self) -> RoutingAlgo {
+        self.routing_algorithm
+    }
+
     /// Initialize routing protocols and network discovery
     pub fn initialize_routing(&mut self) -> Result<(), String> {
         info!("Initializing mesh network routing protocols");
@@ -110,7 +265,7 @@ impl MeshNetwork {
         self.discover_neighbors()?;
         
         // Build initial routing table
-        self.build_routing_table()?;
+        self.build_routing_table();
         
         // Initialize ground station connections
         self.initialize_ground_stations()?;
@@ -118,10 +273,16 @@ impl MeshNetwork {
         Ok(())
     }
 
-    /// Add a new node to the mesh network
-    pub fn add_node(&mut self, node: NetworkNode) {
+    /// Add a new node to the mesh network, rejecting ids already used by a different node type
+    pub fn add_node(&mut self, node: NetworkNode) -> Result<(), String> {
         let node_id = node.node_id;
         
+        if let Some(existing) = self.nodes.get(&node_id) {
+            if existing.node_type != node.node_type {
+                return Err(format!("Node id {} already in use by a {:?} node", node_id, existing.node_type));
+            }
+        }
+        
         if node.node_type == NodeType::GroundStation {
             self.ground_stations.insert(node_id);
         }
@@ -130,6 +291,12 @@ impl MeshNetwork {
         self.update_network_topology();
         
         info!("Added node {} to mesh network", node_id);
+        Ok(())
+    }
+
+    /// Whether a node with this id is part of the mesh
+    pub fn contains_node(&self, node_id: u32) -> bool {
+        self.nodes.contains_key(&node_id)
     }
 
     /// Remove a node from the mesh network
@@ -137,10 +304,13 @@ impl MeshNetwork {
         if let Some(_node) = self.nodes.remove(&node_id) {
             self.ground_stations.remove(&node_id);
             
-            // Update routing table to remove routes through this node
-            self.routing_table.entries.retain(|_, entry| {
-                entry.next_hop != node_id && entry.destination != node_id
-            });
+            // Update routing tables to remove routes through this node
+            for node in self.nodes.values_mut() {
+                node.neighbors.remove(&node_id);
+                node.routing_table.entries.retain(|_, entry| {
+                    entry.next_hop != node_id && entry.destination != node_id
+                });
+            }
             
             // Update topology
             self.update_network_topology();
@@ -151,100 +321,399 @@ impl MeshNetwork {
 
     /// Route a message through the mesh network using advanced algorithms
     pub fn route_message(&mut self, source: u32, destination: u32, data: &[u8]) -> Result<bool, String> {
+        Ok(self.deliver_message(source, destination, data)?.is_some())
+    }
+
+    /// Route a message and return the packet as it arrived at the destination,
+    /// or `None` if no route exists
+    pub fn deliver_message(&mut self, source: u32, destination: u32, data: &[u8]) -> Result<Option<NetworkPacket>, String> {
+        self.deliver_message_with_priority(source, destination, data, PRIORITY_NORMAL)
+    }
+
+    /// Route a message at the given priority; higher priorities favor reliable links
+    /// and emergency traffic may relay through low-battery nodes
+    pub fn route_message_with_priority(&mut self, source: u32, destination: u32, data: &[u8], priority: u8) -> Result<bool, String> {
+        Ok(self.deliver_message_with_priority(source, destination, data, priority)?.is_some())
+    }
+
+    /// Priority-aware variant of `deliver_message`
+    pub fn deliver_message_with_priority(&mut self, source: u32, destination: u32, data: &[u8], priority: u8) -> Result<Option<NetworkPacket>, String> {
         // Create network packet
         let packet = NetworkPacket {
-            packet_id: rand::random::<u32>(),
+            packet_id: self.rng.gen::<u32>(),
             source,
             destination,
             next_hop: 0,  // Will be determined by routing
             ttl: 32,  // Maximum hops
-            priority: 1,
+            priority,
             timestamp: Utc::now(),
             payload: data.to_vec(),
             route_history: vec![source],
         };
 
-        // Find optimal route
-        let route = self.find_optimal_route(source, destination)?;
+        // Find optimal route, taking custody if the destination is currently unreachable
+        let route = match self.find_route(source, destination, priority) {
+            Ok(route) => route,
+            Err(_) if self.nodes.contains_key(&source) && self.nodes.contains_key(&destination) => {
+                info!("No contemporaneous route from {} to {}, holding packet {} in custody",
+                      source, destination, packet.packet_id);
+                self.buffer_packet(packet, true);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
         
         if route.is_empty() {
             warn!("No route found from {} to {}", source, destination);
-            self.statistics.packets_dropped += 1;
-            return Ok(false);
+            self.record_drop(priority);
+            return Ok(None);
+        }
+
+        // Emergency packets are replicated over a node-disjoint backup path when one exists
+        if priority >= PRIORITY_EMERGENCY {
+            let excluded: HashSet<u32> = route[..route.len() - 1].iter().copied().collect();
+            let backup = self.disjoint_routes(source, destination, 1, priority, excluded, route.len() == 1);
+            if let Some(backup_route) = backup.first() {
+                if self.forward_packet(packet.clone(), backup_route).is_ok() {
+                    self.statistics.emergency_replicas += 1;
+                    debug!("Replicated emergency packet {} over {} hops", packet.packet_id, backup_route.len());
+                }
+            }
         }
 
         // Forward packet along the route
-        self.forward_packet(packet, &route)?;
+        let delivered = self.forward_packet(packet, &route)?;
+        self.record_delivery(route.len(), priority);
+
+        info!("Successfully routed message from {} to {} via {} hops", 
+              source, destination, route.len());
         
+        Ok(Some(delivered))
+    }
+
+    /// Scheduled contacts used by `route_message_at`
+    pub fn set_contact_plan(&mut self, plan: ContactPlan) {
+        self.contact_plan = plan;
+    }
+
+    pub fn contact_plan_mut(&mut self) -> &mut ContactPlan {
+        &mut self.contact_plan
+    }
+
+    /// Route a message departing at `time` over the contact plan rather than the
+    /// instantaneous topology. Returns the earliest delivery time, or `None` if no
+    /// chain of open contacts reaches the destination.
+    pub fn route_message_at(&mut self, source: u32, destination: u32, data: &[u8], time: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, String> {
+        let packet = NetworkPacket {
+            packet_id: self.rng.gen::<u32>(),
+            source,
+            destination,
+            next_hop: 0,
+            ttl: 32,
+            priority: PRIORITY_NORMAL,
+            timestamp: time,
+            payload: data.to_vec(),
+            route_history: vec![source],
+        };
+
+        let delay = |from: u32, to: u32| {
+            Duration::microseconds((self.calculate_transmission_delay(from, to) * 1_000_000.0) as i64)
+        };
+        let Some((route, delivered_at)) = self.contact_plan.earliest_arrival(source, destination, time, delay) else {
+            warn!("No contact route from {} to {} at {}", source, destination, time);
+            self.record_drop(packet.priority);
+            return Ok(None);
+        };
+
+        self.forward_packet(packet, &route)?;
+        self.record_delivery(route.len(), PRIORITY_NORMAL);
+        info!("Routed message from {} to {} over {} contacts, delivered at {}",
+              source, destination, route.len(), delivered_at);
+        Ok(Some(delivered_at))
+    }
+
+    fn record_delivery(&mut self, hops: usize, priority: u8) {
         self.statistics.packets_routed += 1;
+        *self.statistics.routed_by_priority.entry(priority).or_insert(0) += 1;
         self.statistics.average_hop_count = 
-            (self.statistics.average_hop_count * (self.statistics.packets_routed - 1) as f64 + route.len() as f64) 
+            (self.statistics.average_hop_count * (self.statistics.packets_routed - 1) as f64 + hops as f64) 
             / self.statistics.packets_routed as f64;
+    }
 
-        info!("Successfully routed message from {} to {} via {} hops", 
-              source, destination, route.len());
-        
+    fn record_drop(&mut self, priority: u8) {
+        self.statistics.packets_dropped += 1;
+        *self.statistics.dropped_by_priority.entry(priority).or_insert(0) += 1;
+    }
+
+    /// How long buffered packets, stored or in custody, are held before being dropped
+    pub fn set_custody_lifetime(&mut self, lifetime: Duration) {
+        self.custody_lifetime = lifetime;
+    }
+
+    /// Number of buffered packets held in custody waiting for a route
+    pub fn custody_count(&self) -> usize {
+        self.packet_buffer.iter().filter(|entry| entry.custody).count()
+    }
+
+    fn buffer_packet(&mut self, packet: NetworkPacket, custody: bool) {
+        self.packet_buffer.push_back(BufferedPacket {
+            expires_at: packet.timestamp + self.custody_lifetime,
+            packet,
+            custody,
+        });
+    }
+
+    /// Drop buffered packets past their expiry, counting custody packets in `custody_expired`
+    fn expire_buffered_packets(&mut self, now: DateTime<Utc>) {
+        let mut expired_custody = 0;
+        self.packet_buffer.retain(|entry| {
+            if now < entry.expires_at {
+                return true;
+            }
+            warn!("Packet {} expired in the store-and-forward buffer", entry.packet.packet_id);
+            if entry.custody {
+                expired_custody += 1;
+            }
+            false
+        });
+        self.statistics.custody_expired += expired_custody;
+    }
+
+    /// Retry routing every packet in custody, returning those delivered.
+    /// Expired packets are dropped first.
+    pub fn flush_custody(&mut self, now: DateTime<Utc>) -> Vec<NetworkPacket> {
+        self.expire_buffered_packets(now);
+
+        let mut delivered = Vec::new();
+        for entry in std::mem::take(&mut self.packet_buffer) {
+            if !entry.custody {
+                self.packet_buffer.push_back(entry);
+                continue;
+            }
+
+            let packet = &entry.packet;
+            let route = match self.find_route(packet.source, packet.destination, packet.priority) {
+                Ok(route) if !route.is_empty() => route,
+                _ => {
+                    self.packet_buffer.push_back(entry);
+                    continue;
+                }
+            };
+
+            let (packet_id, priority) = (packet.packet_id, packet.priority);
+            match self.forward_packet(entry.packet, &route) {
+                Ok(packet) => {
+                    self.record_delivery(route.len(), priority);
+                    self.statistics.custody_deliveries += 1;
+                    info!("Delivered packet {} from custody via {} hops", packet_id, route.len());
+                    delivered.push(packet);
+                }
+                Err(e) => warn!("Custody packet {} lost while forwarding: {}", packet_id, e),
+            }
+        }
+        delivered
+    }
+
+    /// Route a message and wait for the destination's acknowledgment along the reverse route.
+    /// Returns `false` if no acknowledgment arrives within `timeout`.
+    pub fn route_message_reliable(&mut self, source: u32, destination: u32, data: &[u8], timeout: Duration) -> Result<bool, String> {
+        let delivered = match self.deliver_message(source, destination, data) {
+            Ok(Some(packet)) => packet,
+            Ok(None) | Err(_) => {
+                warn!("No acknowledgment from {}: message was not delivered", destination);
+                return Ok(false);
+            }
+        };
+
+        // Destination returns an ack along the reverse of the delivered route
+        let reverse_route: Vec<u32> = delivered.route_history.iter().rev().skip(1).cloned().collect();
+        let mut ack_payload = ACK_PAYLOAD_PREFIX.to_vec();
+        ack_payload.extend_from_slice(&delivered.packet_id.to_be_bytes());
+        let ack = NetworkPacket {
+            packet_id: self.rng.gen::<u32>(),
+            source: destination,
+            destination: source,
+            next_hop: 0,
+            ttl: 32,
+            priority: delivered.priority,
+            timestamp: Utc::now(),
+            payload: ack_payload,
+            route_history: vec![destination],
+        };
+        if self.forward_packet(ack, &reverse_route).is_err() {
+            warn!("Acknowledgment for packet {} was lost", delivered.packet_id);
+            return Ok(false);
+        }
+
+        let round_trip_secs = self.route_delay(&delivered.route_history) * 2.0;
+        let round_trip = Duration::microseconds((round_trip_secs * 1_000_000.0) as i64);
+        if round_trip > timeout {
+            warn!("Acknowledgment for packet {} timed out ({} ms round trip)",
+                  delivered.packet_id, round_trip.num_milliseconds());
+            return Ok(false);
+        }
+
+        debug!("Packet {} acknowledged by {} ({} us round trip)",
+               delivered.packet_id, destination, round_trip.num_microseconds().unwrap_or(0));
         Ok(true)
     }
 
-    /// Find optimal route using Dijkstra's algorithm with space-specific metrics
-    fn find_optimal_route(&self, source: u32, destination: u32) -> Result<Vec<u32>, String> {
+    /// Total one-way transmission delay in seconds along a path of node ids
+    fn route_delay(&self, path: &[u32]) -> f64 {
+        path.windows(2)
+            .map(|hop| self.calculate_transmission_delay(hop[0], hop[1]))
+            .sum()
+    }
+
+    /// Find a route with the selected routing algorithm
+    fn find_route(&self, source: u32, destination: u32, priority: u8) -> Result<Vec<u32>, String> {
+        match self.routing_algorithm {
+            RoutingAlgo::LinkState => self.find_optimal_route(source, destination, priority),
+            RoutingAlgo::DistanceVector => self.find_distance_vector_route(source, destination, priority),
+            RoutingAlgo::AStar => self.astar_search(source, destination, priority).map(|search| search.path),
+        }
+    }
+
+    /// Find a route by following each node's routing table entry for the destination
+    fn find_distance_vector_route(&self, source: u32, destination: u32, priority: u8) -> Result<Vec<u32>, String> {
         if source == destination {
             return Ok(vec![]);
         }
 
-        let mut distances: HashMap<u32, f64> = HashMap::new();
-        let mut previous: HashMap<u32, u32> = HashMap::new();
-        let mut unvisited: HashSet<u32> = self.nodes.keys().cloned().collect();
+        let mut path = Vec::new();
+        let mut current = source;
+        while current != destination {
+            let node = self.nodes.get(&current).ok_or(format!("Node {} not found", current))?;
+            let next_hop = node.routing_table.route_to(destination)
+                .map(|entry| entry.next_hop)
+                .ok_or("No route found")?;
+            if !node.neighbors.contains(&next_hop) {
+                return Err(format!("Routing table of node {} is stale: {} is no longer a neighbor", current, next_hop));
+            }
+            if next_hop != destination && !self.can_relay(next_hop, priority) {
+                return Err(format!("Next hop {} cannot relay priority {} traffic", next_hop, priority));
+            }
+            path.push(next_hop);
+            if path.len() > self.nodes.len() {
+                return Err("Routing loop detected".to_string());
+            }
+            current = next_hop;
+        }
 
-        // Initialize distances
-        for &node_id in &unvisited {
-            distances.insert(node_id, if node_id == source { 0.0 } else { f64::INFINITY });
+        Ok(path)
+    }
+
+    /// Find optimal route using Dijkstra's algorithm with space-specific metrics
+    fn find_optimal_route(&self, source: u32, destination: u32, priority: u8) -> Result<Vec<u32>, String> {
+        self.best_first_search(source, destination, priority, |_, _| true, |_| 0.0).map(|search| search.path)
+    }
+
+    /// Least-cost route found by exploring outward from the source in every direction
+    pub fn find_route_dijkstra(&self, source: u32, destination: u32) -> Result<RouteSearch, String> {
+        self.best_first_search(source, destination, PRIORITY_NORMAL, |_, _| true, |_| 0.0)
+    }
+
+    /// Up to `k` node-disjoint routes, cheapest first, found by repeatedly removing the
+    /// intermediate nodes of each route found. Returns fewer if the topology runs out.
+    pub fn find_k_disjoint_routes(&self, source: u32, destination: u32, k: usize) -> Vec<Vec<u32>> {
+        self.disjoint_routes(source, destination, k, PRIORITY_NORMAL, HashSet::new(), false)
+    }
+
+    fn disjoint_routes(&self, source: u32, destination: u32, k: usize, priority: u8,
+                       mut excluded: HashSet<u32>, mut direct_link_used: bool) -> Vec<Vec<u32>> {
+        let mut routes = Vec::new();
+        while routes.len() < k {
+            let usable = |from: u32, to: u32| {
+                let reused_direct_link = direct_link_used && from == source && to == destination;
+                !excluded.contains(&to) && !reused_direct_link
+            };
+            let route = match self.best_first_search(source, destination, priority, usable, |_| 0.0) {
+                Ok(search) if !search.path.is_empty() => search.path,
+                _ => break,
+            };
+            excluded.extend(&route[..route.len() - 1]);
+            direct_link_used |= route.len() == 1;
+            routes.push(route);
         }
+        routes
+    }
 
-        while !unvisited.is_empty() {
-            // Find unvisited node with minimum distance
-            let current = *unvisited.iter()
-                .min_by(|&&a, &&b| distances[&a].partial_cmp(&distances[&b]).unwrap())
-                .ok_or("No unvisited nodes")?;
+    /// Least-cost route found by A*, using straight-line distance to the destination as the heuristic.
+    /// Every link costs at least its length / 1000, so the heuristic never overestimates.
+    pub fn find_route_astar(&self, source: u32, destination: u32) -> Result<RouteSearch, String> {
+        self.astar_search(source, destination, PRIORITY_NORMAL)
+    }
+
+    fn astar_search(&self, source: u32, destination: u32, priority: u8) -> Result<RouteSearch, String> {
+        let target = self.nodes.get(&destination).ok_or("No route found")?;
+        self.best_first_search(source, destination, priority, |_, _| true, |node_id| {
+            self.nodes.get(&node_id)
+                .map(|node| self.calculate_distance(&node.position, &target.position) / 1000.0)
+                .unwrap_or(0.0)
+        })
+    }
 
+    fn best_first_search<L, H>(&self, source: u32, destination: u32, priority: u8, usable_link: L, heuristic: H) -> Result<RouteSearch, String>
+    where
+        L: Fn(u32, u32) -> bool,
+        H: Fn(u32) -> f64,
+    {
+        if source == destination {
+            return Ok(RouteSearch { path: vec![], cost: 0.0, nodes_expanded: 0 });
+        }
+        if !self.nodes.contains_key(&source) || !self.nodes.contains_key(&destination) {
+            return Err("No route found".to_string());
+        }
+
+        let mut distances: HashMap<u32, f64> = HashMap::from([(source, 0.0)]);
+        let mut previous: HashMap<u32, u32> = HashMap::new();
+        let mut closed: HashSet<u32> = HashSet::new();
+        let mut open = BinaryHeap::from([SearchEntry { estimate: heuristic(source), node_id: source }]);
+
+        while let Some(SearchEntry { node_id: current, .. }) = open.pop() {
+            if !closed.insert(current) {
+                continue;  // Stale entry for an already expanded node
+            }
             if current == destination {
                 break;
             }
 
-            unvisited.remove(&current);
-
-            // Update distances to neighbors
+            // Relax links to neighbors not yet expanded
             if let Some(current_node) = self.nodes.get(&current) {
                 for &neighbor in &current_node.neighbors {
-                    if unvisited.contains(&neighbor) {
-                        let edge_cost = self.calculate_link_cost(current, neighbor);
-                        let alt_distance = distances[&current] + edge_cost;
-
-                        if alt_distance < distances[&neighbor] {
-                            distances.insert(neighbor, alt_distance);
-                            previous.insert(neighbor, current);
-                        }
+                    if closed.contains(&neighbor)
+                        || !usable_link(current, neighbor)
+                        || (neighbor != destination && !self.can_relay(neighbor, priority)) {
+                        continue;
+                    }
+                    let alt_distance = distances[&current] + self.priority_link_cost(current, neighbor, priority);
+                    if alt_distance < distances.get(&neighbor).copied().unwrap_or(f64::INFINITY) {
+                        distances.insert(neighbor, alt_distance);
+                        previous.insert(neighbor, current);
+                        open.push(SearchEntry { estimate: alt_distance + heuristic(neighbor), node_id: neighbor });
                     }
                 }
             }
         }
 
+        if !closed.contains(&destination) {
+            return Err("No route found".to_string());
+        }
+
         // Reconstruct path
         let mut path = Vec::new();
         let mut current = destination;
-
         while let Some(&prev) = previous.get(&current) {
             path.push(current);
             current = prev;
         }
-
-        if current != source {
-            return Err("No route found".to_string());
-        }
-
         path.reverse();
-        Ok(path)
+
+        Ok(RouteSearch {
+            path,
+            cost: distances[&destination],
+            nodes_expanded: closed.len(),
+        })
     }
 
     /// Calculate link cost considering orbital mechanics and power constraints
@@ -259,21 +728,85 @@ impl MeshNetwork {
         let mut cost = distance / 1000.0;  // Normalize to reasonable range
 
         // Adjust for battery levels (prefer nodes with higher battery)
-        cost *= (2.0 - n1.battery_level.min(n2.battery_level));
+        cost *= 2.0 - n1.battery_level.min(n2.battery_level);
 
-        // Adjust for node reliability
-        if let Some(entry) = self.routing_table.entries.get(&node2) {
-            cost *= (2.0 - entry.reliability);
-        }
+        // Adjust for observed link reliability
+        cost *= 2.0 - self.link_reliability(node1, node2);
 
         // Penalty for ground station handovers (more complex)
         if n1.node_type != n2.node_type {
             cost *= 1.5;
         }
 
+        // Steer traffic away from busy links
+        cost *= 1.0 + self.link_load(node1, node2) / LINK_CONGESTION_PACKETS;
+
         cost
     }
 
+    /// Link cost weighted so higher priorities pay more for unreliable links
+    fn priority_link_cost(&self, node1: u32, node2: u32, priority: u8) -> f64 {
+        let link_quality = self.network_topology.adjacency_matrix
+            .get(&(node1, node2))
+            .copied()
+            .unwrap_or(0.1);
+        self.calculate_link_cost(node1, node2)
+            * (1.0 + PRIORITY_RELIABILITY_WEIGHT * priority as f64 * (1.0 - link_quality))
+    }
+
+    /// Chance that each hop of a forwarded packet is lost, e.g. a scenario's `failure_probability`
+    pub fn set_link_failure_probability(&mut self, probability: f64) {
+        self.link_failure_probability = probability.clamp(0.0, 1.0);
+    }
+
+    /// Fold a transmission outcome into the link's reliability moving average
+    pub fn record_link_outcome(&mut self, from: u32, to: u32, success: bool) {
+        let observed = if success { 1.0 } else { 0.0 };
+        let reliability = self.network_topology.link_reliability
+            .entry(link_key(from, to))
+            .or_insert(INITIAL_LINK_RELIABILITY);
+        *reliability += LINK_RELIABILITY_SMOOTHING * (observed - *reliability);
+        let reliability = *reliability;
+
+        // Keep direct routing entries in step with the observation
+        for (node_id, neighbor) in [(from, to), (to, from)] {
+            if let Some(entry) = self.nodes.get_mut(&node_id)
+                .and_then(|node| node.routing_table.entries.get_mut(&neighbor))
+                .filter(|entry| entry.next_hop == neighbor) {
+                entry.reliability = reliability;
+                entry.last_updated = Utc::now();
+            }
+        }
+    }
+
+    /// Observed success rate of the link between two nodes
+    pub fn link_reliability(&self, node1: u32, node2: u32) -> f64 {
+        self.network_topology.link_reliability
+            .get(&link_key(node1, node2))
+            .copied()
+            .unwrap_or(INITIAL_LINK_RELIABILITY)
+    }
+
+    /// Recent offered load on the link between two nodes, in packets
+    pub fn link_load(&self, node1: u32, node2: u32) -> f64 {
+        self.network_topology.offered_load.get(&link_key(node1, node2)).copied().unwrap_or(0.0)
+    }
+
+    /// Exponentially decay every link's offered load over `elapsed_secs`
+    fn decay_link_load(&mut self, elapsed_secs: f64) {
+        let factor = (-elapsed_secs.max(0.0) / LINK_LOAD_DECAY_SECS).exp();
+        self.network_topology.offered_load.retain(|_, load| {
+            *load *= factor;
+            *load > 0.01
+        });
+    }
+
+    /// Whether a node may relay traffic of this priority given its battery level
+    fn can_relay(&self, node_id: u32, priority: u8) -> bool {
+        priority >= PRIORITY_EMERGENCY
+            || self.nodes.get(&node_id).is_some_and(|node| node.battery_level >= LOW_BATTERY_RELAY_THRESHOLD)
+    }
+
     /// Calculate 3D distance between two orbital positions
     fn calculate_distance(&self, pos1: &OrbitalPosition, pos2: &OrbitalPosition) -> f64 {
         let earth_radius = 6371.0; // km
@@ -299,29 +832,37 @@ impl MeshNetwork {
     }
 
     /// Forward packet along the determined route
-    fn forward_packet(&mut self, mut packet: NetworkPacket, route: &[u32]) -> Result<(), String> {
+    fn forward_packet(&mut self, mut packet: NetworkPacket, route: &[u32]) -> Result<NetworkPacket, String> {
+        if route.len() > packet.ttl as usize {
+            warn!("Packet {} needs {} hops but has TTL {}", packet.packet_id, route.len(), packet.ttl);
+            self.record_drop(packet.priority);
+            return Err(format!("TTL exceeded: route needs {} hops, TTL is {}", route.len(), packet.ttl));
+        }
+
+        packet.route_history = std::iter::once(packet.source).chain(route.iter().copied()).collect();
         for (i, &next_hop) in route.iter().enumerate() {
             packet.next_hop = next_hop;
-            packet.route_history.push(next_hop);
             packet.ttl -= 1;
 
-            if packet.ttl == 0 {
-                warn!("Packet {} exceeded TTL", packet.packet_id);
-                self.statistics.packets_dropped += 1;
-                return Err("TTL exceeded".to_string());
-            }
-
             // Simulate packet transmission delay
-            let transmission_delay = self.calculate_transmission_delay(
-                if i == 0 { packet.source } else { route[i-1] },
-                next_hop
-            );
+            let from = if i == 0 { packet.source } else { route[i-1] };
+            let transmission_delay = self.calculate_transmission_delay(from, next_hop);
+            *self.network_topology.offered_load.entry(link_key(from, next_hop)).or_insert(0.0) += 1.0;
+
+            // Simulated link failures feed back into link reliability
+            let failed = self.link_failure_probability > 0.0 && self.rng.gen::<f64>() < self.link_failure_probability;
+            self.record_link_outcome(from, next_hop, !failed);
+            if failed {
+                warn!("Packet {} lost on link {} -> {}", packet.packet_id, from, next_hop);
+                self.record_drop(packet.priority);
+                return Err(format!("Link {} -> {} failed", from, next_hop));
+            }
 
-            debug!("Forwarding packet {} to node {} (delay: {:.2}ms)", 
-                   packet.packet_id, next_hop, transmission_delay * 1000.0);
+            debug!("Forwarding packet {} to node {} (delay: {:.2}ms, ttl {})", 
+                   packet.packet_id, next_hop, transmission_delay * 1000.0, packet.ttl);
         }
 
-        Ok(())
+        Ok(packet)
     }
 
     /// Calculate transmission delay based on distance and link quality
@@ -351,17 +892,18 @@ impl MeshNetwork {
     fn discover_neighbors(&mut self) -> Result<(), String> {
         let node_ids: Vec<u32> = self.nodes.keys().cloned().collect();
         
-        for &node1_id in &node_ids {
-            for &node2_id in &node_ids {
-                if node1_id != node2_id {
-                    if let (Some(node1), Some(node2)) = (self.nodes.get(&node1_id), self.nodes.get(&node2_id)) {
-                        let distance = self.calculate_distance(&node1.position, &node2.position);
-                        
-                        if distance <= node1.communication_range.min(node2.communication_range) {
-                            // Nodes are within communication range
-                            if let Some(node1_mut) = self.nodes.get_mut(&node1_id) {
-                                node1_mut.neighbors.insert(node2_id);
-                            }
+        for (i, &node1_id) in node_ids.iter().enumerate() {
+            for &node2_id in &node_ids[i + 1..] {
+                if let (Some(node1), Some(node2)) = (self.nodes.get(&node1_id), self.nodes.get(&node2_id)) {
+                    let distance = self.calculate_distance(&node1.position, &node2.position);
+                    
+                    if distance <= node1.communication_range.min(node2.communication_range) {
+                        // Nodes are within communication range of each other, so the link is mutual
+                        if let Some(node1_mut) = self.nodes.get_mut(&node1_id) {
+                            node1_mut.neighbors.insert(node2_id);
+                        }
+                        if let Some(node2_mut) = self.nodes.get_mut(&node2_id) {
+                            node2_mut.neighbors.insert(node1_id);
                         }
                     }
                 }
@@ -374,75 +916,83 @@ impl MeshNetwork {
         Ok(())
     }
 
-    /// Build and update routing table using distance vector algorithm
-    fn build_routing_table(&mut self) -> Result<(), String> {
-        self.routing_table.entries.clear();
-
+    /// Build each node's routing table by exchanging distance vectors between neighbors, costed for normal priority
+    fn build_routing_table(&mut self) {
         // Initialize direct routes
-        for (&node_id, node) in &self.nodes {
-            for &neighbor in &node.neighbors {
-                let cost = self.calculate_link_cost(node_id, neighbor);
-                let entry = RoutingEntry {
+        let node_ids: Vec<u32> = self.nodes.keys().copied().collect();
+        for &node_id in &node_ids {
+            let direct: HashMap<u32, RoutingEntry> = self.nodes[&node_id].neighbors.iter()
+                .map(|&neighbor| (neighbor, RoutingEntry {
                     destination: neighbor,
                     next_hop: neighbor,
                     hop_count: 1,
-                    cost,
+                    cost: self.priority_link_cost(node_id, neighbor, PRIORITY_NORMAL),
                     last_updated: Utc::now(),
-                    reliability: 0.9,  // Initial reliability
-                };
-                self.routing_table.entries.insert(neighbor, entry);
+                    reliability: self.link_reliability(node_id, neighbor),
+                }))
+                .collect();
+            if let Some(node) = self.nodes.get_mut(&node_id) {
+                node.routing_table.entries = direct;
             }
         }
 
-        // Bellman-Ford algorithm for multi-hop routes
+        // Bellman-Ford rounds: every node merges the vectors its neighbors advertised last round
         for _ in 0..self.nodes.len() {
-            let mut updated = false;
+            let mut updates: Vec<(u32, RoutingEntry)> = Vec::new();
             
             for (&node_id, node) in &self.nodes {
+                let mut best: HashMap<u32, RoutingEntry> = HashMap::new();
                 for &neighbor in &node.neighbors {
-                    if let Some(neighbor_routes) = self.get_routes_from_node(neighbor) {
-                        for (dest, route) in neighbor_routes {
-                            if dest != node_id {  // Avoid loops
-                                let new_cost = self.calculate_link_cost(node_id, neighbor) + route.cost;
-                                let new_hop_count = route.hop_count + 1;
-                                
-                                let should_update = match self.routing_table.entries.get(&dest) {
-                                    Some(existing) => new_cost < existing.cost,
-                                    None => true,
-                                };
-                                
-                                if should_update && new_hop_count < 16 {  // Prevent infinite loops
-                                    let entry = RoutingEntry {
-                                        destination: dest,
-                                        next_hop: neighbor,
-                                        hop_count: new_hop_count,
-                                        cost: new_cost,
-                                        last_updated: Utc::now(),
-                                        reliability: route.reliability * 0.95,  // Degrade with hops
-                                    };
-                                    self.routing_table.entries.insert(dest, entry);
-                                    updated = true;
-                                }
-                            }
+                    let Some(neighbor_routes) = self.get_routes_from_node(neighbor) else {
+                        continue;
+                    };
+                    let link_cost = self.priority_link_cost(node_id, neighbor, PRIORITY_NORMAL);
+                    for (&dest, route) in &neighbor_routes.entries {
+                        // Avoid loops, including routes the neighbor learned through us
+                        if dest == node_id || route.next_hop == node_id || route.hop_count >= 15 {
+                            continue;
+                        }
+                        let new_cost = link_cost + route.cost;
+                        let current_cost = best.get(&dest)
+                            .or_else(|| node.routing_table.entries.get(&dest))
+                            .map_or(f64::INFINITY, |existing| existing.cost);
+                        if new_cost < current_cost {
+                            best.insert(dest, RoutingEntry {
+                                destination: dest,
+                                next_hop: neighbor,
+                                hop_count: route.hop_count + 1,
+                                cost: new_cost,
+                                last_updated: Utc::now(),
+                                reliability: route.reliability * 0.95,  // Degrade with hops
+                            });
                         }
                     }
                 }
+                updates.extend(best.into_values().map(|entry| (node_id, entry)));
             }
             
-            if !updated {
+            if updates.is_empty() {
                 break;  // Convergence reached
             }
+            for (node_id, entry) in updates {
+                if let Some(node) = self.nodes.get_mut(&node_id) {
+                    node.routing_table.entries.insert(entry.destination, entry);
+                }
+            }
         }
 
-        info!("Routing table built with {} entries", self.routing_table.entries.len());
-        Ok(())
+        info!("Routing tables built with {} entries",
+              self.nodes.values().map(|node| node.routing_table.len()).sum::<usize>());
     }
 
-    /// Get routing entries for a specific node (helper for routing algorithm)
-    fn get_routes_from_node(&self, _node_id: u32) -> Option<HashMap<u32, RoutingEntry>> {
-        // In a real implementation, this would query the specific node
-        // For simulation, we use the global routing table
-        Some(self.routing_table.entries.clone())
+    /// Routing table advertised by a specific node
+    fn get_routes_from_node(&self, node_id: u32) -> Option<&RoutingTable> {
+        self.nodes.get(&node_id).map(|node| &node.routing_table)
+    }
+
+    /// A node's routing table, as last built by distance-vector exchange
+    pub fn routing_table(&self, node_id: u32) -> Option<&RoutingTable> {
+        self.get_routes_from_node(node_id)
     }
 
     /// Initialize ground station connections and handover protocols
@@ -484,6 +1034,21 @@ impl MeshNetwork {
         }
     }
 
+    /// Recompute the topology and the neighbor sets used for routing
+    fn refresh_neighbors(&mut self) {
+        self.update_network_topology();
+        for (node_id, node) in self.nodes.iter_mut() {
+            node.neighbors = self.network_topology.connectivity_graph
+                .get(node_id)
+                .cloned()
+                .unwrap_or_default();
+        }
+        // Distance-vector routes are read from the tables, so they must track the topology
+        if self.routing_algorithm == RoutingAlgo::DistanceVector {
+            self.build_routing_table();
+        }
+    }
+
     /// Handle ground station handover for continuous connectivity
     pub fn handle_ground_station_handover(&mut self, cubesat_id: u32) -> Result<Option<u32>, String> {
         let cubesat = self.nodes.get(&cubesat_id)
@@ -493,32 +1058,126 @@ impl MeshNetwork {
             return Err("Node is not a CubeSat".to_string());
         }
 
+        let best_gs = self.best_ground_station(cubesat_id)?;
+
+        if let Some((gs_id, quality)) = best_gs {
+            self.statistics.handovers_completed += 1;
+            info!("Handover completed: CubeSat {} -> Ground Station {} (quality: {:.2})", 
+                  cubesat_id, gs_id, quality);
+        }
+
+        Ok(best_gs.map(|(gs_id, _)| gs_id))
+    }
+
+    /// Find the ground station with the best signal quality for a node
+    pub fn best_ground_station(&self, node_id: u32) -> Result<Option<(u32, f64)>, String> {
+        let node = self.nodes.get(&node_id)
+            .ok_or(format!("Node {} not found", node_id))?;
+
         // Find best ground station based on signal strength and availability
         let mut best_gs = None;
         let mut best_quality = 0.0;
 
         for &gs_id in &self.ground_stations {
             if let Some(gs_node) = self.nodes.get(&gs_id) {
-                let distance = self.calculate_distance(&cubesat.position, &gs_node.position);
+                let distance = self.calculate_distance(&node.position, &gs_node.position);
                 
                 if distance <= gs_node.communication_range {
                     let signal_quality = (1.0 - (distance / gs_node.communication_range)).max(0.0);
                     
                     if signal_quality > best_quality {
                         best_quality = signal_quality;
-                        best_gs = Some(gs_id);
+                        best_gs = Some((gs_id, signal_quality));
                     }
                 }
             }
         }
 
-        if let Some(gs_id) = best_gs {
-            self.statistics.handovers_completed += 1;
-            info!("Handover completed: CubeSat {} -> Ground Station {} (quality: {:.2})", 
-                  cubesat_id, gs_id, best_quality);
+        Ok(best_gs)
+    }
+
+    /// Ground station reachable from a node at the lowest route cost, relaying through
+    /// other nodes where needed, together with the route to it
+    pub fn cheapest_ground_station_route(&self, node_id: u32) -> Result<Option<(u32, RouteSearch)>, String> {
+        if !self.nodes.contains_key(&node_id) {
+            return Err(format!("Node {} not found", node_id));
         }
 
-        Ok(best_gs)
+        let mut ground_stations: Vec<u32> = self.ground_stations.iter().copied().collect();
+        ground_stations.sort_unstable();
+        Ok(ground_stations.into_iter()
+            .filter(|&gs_id| gs_id != node_id)
+            .filter_map(|gs_id| self.find_route_dijkstra(node_id, gs_id).ok().map(|search| (gs_id, search)))
+            .min_by(|(_, a), (_, b)| a.cost.total_cmp(&b.cost)))
+    }
+
+    /// Hold a packet until a contact opens (store-and-forward)
+    pub fn store_packet(&mut self, packet: NetworkPacket) {
+        self.buffer_packet(packet, false);
+    }
+
+    /// Number of buffered packets, including those in custody
+    pub fn stored_packet_count(&self) -> usize {
+        self.packet_buffer.len()
+    }
+
+    /// Drain up to `max_packets` unexpired buffered packets for an open contact, highest priority then oldest first
+    pub fn flush_stored_packets(&mut self, max_packets: usize) -> Vec<NetworkPacket> {
+        self.expire_buffered_packets(Utc::now());
+        self.packet_buffer.make_contiguous()
+            .sort_by(|a, b| b.packet.priority.cmp(&a.packet.priority).then(a.packet.timestamp.cmp(&b.packet.timestamp)));
+        let count = max_packets.min(self.packet_buffer.len());
+        let flushed: Vec<NetworkPacket> = self.packet_buffer.drain(..count).map(|entry| entry.packet).collect();
+        debug!("Flushed {} stored packets, {} remain", flushed.len(), self.packet_buffer.len());
+        flushed
+    }
+
+    /// Export nodes and links with their quality, one edge per node pair
+    pub fn topology_graph(&self) -> TopologyGraph {
+        let mut nodes: Vec<(u32, OrbitalPosition)> = self.nodes.iter()
+            .map(|(&id, node)| (id, node.position.clone()))
+            .collect();
+        nodes.sort_by_key(|(id, _)| *id);
+
+        let mut edges: Vec<(u32, u32, f64)> = self.network_topology.adjacency_matrix.iter()
+            .filter(|((node1, node2), _)| node1 < node2)
+            .map(|(&(node1, node2), &quality)| (node1, node2, quality))
+            .collect();
+        edges.sort_by_key(|&(node1, node2, _)| (node1, node2));
+
+        TopologyGraph { nodes, edges }
+    }
+
+    /// Groups of mutually reachable nodes, each sorted by id, ordered by their lowest id
+    pub fn connected_components(&self) -> Vec<Vec<u32>> {
+        let mut node_ids: Vec<u32> = self.nodes.keys().copied().collect();
+        node_ids.sort_unstable();
+
+        let mut visited = HashSet::new();
+        let mut components = Vec::new();
+        for start in node_ids {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut component = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(node_id) = queue.pop_front() {
+                for &neighbor in self.network_topology.connectivity_graph.get(&node_id).into_iter().flatten() {
+                    if visited.insert(neighbor) {
+                        component.push(neighbor);
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    /// Whether the mesh has split into more than one group of reachable nodes
+    pub fn is_partitioned(&self) -> bool {
+        self.connected_components().len() > 1
     }
 
     /// Get network statistics for monitoring and optimization
@@ -533,7 +1192,7 @@ impl MeshNetwork {
             node.last_seen = Utc::now();
             
             // Trigger topology update
-            self.update_network_topology();
+            self.refresh_neighbors();
             
             debug!("Updated position for node {}", node_id);
             Ok(())
@@ -563,18 +1222,37 @@ impl MeshNetwork {
             }
         }
 
-        // Update network topology
-        self.update_network_topology();
+        // Let link congestion drain
+        self.decay_link_load(time_delta.num_milliseconds() as f64 / 1000.0);
+
+        // Update network topology and the neighbor sets used for routing
+        self.refresh_neighbors();
         
+        // Report the constellation splitting apart
+        let component_count = self.connected_components().len();
+        if self.component_count > 0 && component_count > self.component_count {
+            warn!("Network partitioned: {} components (was {})", component_count, self.component_count);
+            self.statistics.partition_events += 1;
+        }
+        self.component_count = component_count;
+
         // Rebuild routing table periodically
         if self.statistics.packets_routed % 100 == 0 {
-            self.build_routing_table()?;
+            self.build_routing_table();
         }
 
+        // Retry custody packets over the new topology
+        self.flush_custody(Utc::now());
+
         Ok(())
     }
 }
 
+/// Links are undirected, so key them by (lower id, higher id)
+fn link_key(node1: u32, node2: u32) -> (u32, u32) {
+    (node1.min(node2), node1.max(node2))
+}
+
 impl Default for MeshNetwork {
     fn default() -> Self {
         Self::new()
@@ -592,6 +1270,7 @@ impl NetworkNode {
             last_seen: Utc::now(),
             battery_level: 1.0,
             neighbors: HashSet::new(),
+            routing_table: RoutingTable::default(),
         }
     }
 
@@ -610,6 +1289,7 @@ impl NetworkNode {
             last_seen: Utc::now(),
             battery_level: 1.0,  // Always powered
             neighbors: HashSet::new(),
+            routing_table: RoutingTable::default(),
         }
     }
 }
@@ -618,6 +1298,16 @@ impl NetworkNode {
 mod tests {
     use super::*;
 
+    /// A satellite at 400 km drifting north at `speed` km/s
+    fn position_at(latitude: f64, longitude: f64, speed: f64) -> OrbitalPosition {
+        OrbitalPosition {
+            latitude,
+            longitude,
+            altitude: 400.0,
+            velocity: (speed, 0.0, 0.0),
+        }
+    }
+
     #[test]
     fn test_network_creation() {
         let network = MeshNetwork::new();
@@ -636,7 +1326,7 @@ mod tests {
         };
         
         let node = NetworkNode::new_cubesat(1, position);
-        network.add_node(node);
+        network.add_node(node).unwrap();
         
         assert_eq!(network.nodes.len(), 1);
         assert!(network.nodes.contains_key(&1));
@@ -692,9 +1382,452 @@ mod tests {
             velocity: (7.66, 0.0, 0.0),
         };
         
-        network.add_node(NetworkNode::new_cubesat(1, pos1));
-        network.add_node(NetworkNode::new_cubesat(2, pos2));
+        network.add_node(NetworkNode::new_cubesat(1, pos1)).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, pos2)).unwrap();
         
         assert!(network.initialize_routing().is_ok());
     }
+
+    #[test]
+    fn test_stored_packets_flush_by_priority_then_age() {
+        let mut network = MeshNetwork::new();
+        let start = Utc::now();
+        let packet = |packet_id: u32, priority: u8, age_secs: i64| NetworkPacket {
+            packet_id,
+            source: 1,
+            destination: 100,
+            next_hop: 100,
+            ttl: 16,
+            priority,
+            timestamp: start - Duration::seconds(age_secs),
+            payload: vec![packet_id as u8],
+            route_history: vec![1],
+        };
+        network.store_packet(packet(1, 1, 300));
+        network.store_packet(packet(2, 1, 600));
+        network.store_packet(packet(3, 255, 10));
+        network.store_packet(packet(4, 5, 100));
+
+        // A short pass only fits two packets
+        let flushed: Vec<u32> = network.flush_stored_packets(2).iter().map(|p| p.packet_id).collect();
+        assert_eq!(flushed, vec![3, 4]);
+        assert_eq!(network.stored_packet_count(), 2);
+
+        let flushed: Vec<u32> = network.flush_stored_packets(10).iter().map(|p| p.packet_id).collect();
+        assert_eq!(flushed, vec![2, 1]);
+        assert_eq!(network.stored_packet_count(), 0);
+    }
+
+    #[test]
+    fn test_topology_graph_export() {
+        let mut network = MeshNetwork::new();
+
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, position_at(1.0, 0.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(3, position_at(60.0, 0.0, 7.66))).unwrap();
+
+        let graph = network.topology_graph();
+        assert_eq!(graph.nodes.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(graph.edges.len(), 1);
+
+        let (node1, node2, quality) = graph.edges[0];
+        assert_eq!((node1, node2), (1, 2));
+        assert_eq!(Some(&quality), network.network_topology.adjacency_matrix.get(&(1, 2)));
+
+        let json = serde_json::to_string(&graph).unwrap();
+        let parsed: TopologyGraph = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.edges, graph.edges);
+    }
+
+    #[test]
+    fn test_reliable_routing_acknowledgment() {
+        let mut network = MeshNetwork::new();
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, position_at(2.0, 0.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(3, position_at(4.0, 0.0, 7.66))).unwrap();
+        network.initialize_routing().unwrap();
+
+        assert!(network.route_message_reliable(1, 3, b"ping", Duration::seconds(1)).unwrap());
+        assert!(!network.route_message_reliable(1, 3, b"ping", Duration::zero()).unwrap());
+
+        network.remove_node(3);
+        assert!(!network.route_message_reliable(1, 3, b"ping", Duration::seconds(1)).unwrap());
+    }
+
+    #[test]
+    fn test_routing_algorithms_agree_on_chain() {
+        let mut network = MeshNetwork::new();
+        // 1 and 3 are out of range of each other, so traffic must relay through 2
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, position_at(6.0, 0.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(3, position_at(12.0, 0.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(4, position_at(7.0, 0.0, 7.66))).unwrap();
+        network.initialize_routing().unwrap();
+
+        let link_state = network.find_route(1, 3, PRIORITY_NORMAL).unwrap();
+        network.set_routing_algorithm(RoutingAlgo::DistanceVector);
+        let distance_vector = network.find_route(1, 3, PRIORITY_NORMAL).unwrap();
+
+        for route in [&link_state, &distance_vector] {
+            assert_eq!(route.last(), Some(&3));
+            let mut previous = 1;
+            for &hop in route.iter() {
+                assert!(network.nodes[&previous].neighbors.contains(&hop));
+                previous = hop;
+            }
+        }
+        assert_eq!(link_state, distance_vector);
+        assert_eq!(network.routing_table(1).unwrap().route_to(3).unwrap().next_hop, distance_vector[0]);
+
+        // Tables follow the topology, so moving a relay away reroutes through the other one
+        let relay = distance_vector[0];
+        let spare = if relay == 2 { 4 } else { 2 };
+        network.update_node_position(relay, position_at(40.0, 0.0, 7.66)).unwrap();
+        assert_eq!(network.find_route(1, 3, PRIORITY_NORMAL).unwrap(), vec![spare, 3]);
+
+        assert!(network.route_message(1, 3, b"dv").unwrap());
+        assert!(network.find_route(1, 99, PRIORITY_NORMAL).is_err());
+    }
+
+    #[test]
+    fn test_astar_matches_dijkstra_with_fewer_expansions() {
+        let mut network = MeshNetwork::new();
+        // 30 satellites evenly spaced around an equatorial ring, each reaching two neighbors per side
+        for i in 0..30u32 {
+            let mut node = NetworkNode::new_cubesat(i, position_at(0.0, i as f64 * 12.0, 7.66));
+            node.communication_range = 3000.0;
+            node.battery_level = 0.5 + (i % 5) as f64 * 0.1;
+            network.add_node(node).unwrap();
+        }
+        network.initialize_routing().unwrap();
+
+        for destination in [4, 9] {
+            let dijkstra = network.find_route_dijkstra(0, destination).unwrap();
+            let astar = network.find_route_astar(0, destination).unwrap();
+            assert_eq!(astar.path, dijkstra.path);
+            assert!((astar.cost - dijkstra.cost).abs() < 1e-9);
+            assert!(astar.nodes_expanded < dijkstra.nodes_expanded,
+                    "A* expanded {} vs Dijkstra {}", astar.nodes_expanded, dijkstra.nodes_expanded);
+        }
+
+        network.set_routing_algorithm(RoutingAlgo::AStar);
+        assert!(network.route_message(0, 9, b"astar").unwrap());
+        assert!(network.find_route_astar(0, 99).is_err());
+    }
+
+    #[test]
+    fn test_custody_delivers_when_nodes_come_into_range() {
+        let mut network = MeshNetwork::new();
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, position_at(30.0, 0.0, -0.05))).unwrap();
+        network.initialize_routing().unwrap();
+
+        // No path yet: the packet is held rather than dropped
+        assert!(!network.route_message(1, 2, b"dtn").unwrap());
+        assert_eq!(network.custody_count(), 1);
+        assert_eq!(network.get_statistics().packets_dropped, 0);
+
+        // Node 2 drifts to within range of node 1 and the packet goes out on the next step
+        network.simulate_network_step(Duration::seconds(60000)).unwrap();
+        assert_eq!(network.custody_count(), 0);
+        assert_eq!(network.get_statistics().custody_deliveries, 1);
+
+        // Packets that never find a route expire
+        network.add_node(NetworkNode::new_cubesat(3, position_at(-60.0, 0.0, 0.0))).unwrap();
+        network.set_custody_lifetime(Duration::minutes(10));
+        assert!(!network.route_message(1, 3, b"late").unwrap());
+        assert!(network.flush_custody(Utc::now()).is_empty());
+        assert_eq!(network.custody_count(), 1);
+        network.flush_custody(Utc::now() + Duration::minutes(11));
+        assert_eq!(network.custody_count(), 0);
+        assert_eq!(network.get_statistics().custody_expired, 1);
+    }
+
+    #[test]
+    fn test_custody_and_stored_packets_share_buffer() {
+        let mut network = MeshNetwork::new();
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_ground_station(100, 60.0, 0.0)).unwrap();
+        network.initialize_routing().unwrap();
+        network.set_custody_lifetime(Duration::minutes(10));
+
+        assert!(!network.route_message_with_priority(1, 100, b"custody", PRIORITY_EMERGENCY).unwrap());
+        let mut stored = NetworkPacket {
+            packet_id: 7,
+            source: 1,
+            destination: 100,
+            next_hop: 100,
+            ttl: 16,
+            priority: PRIORITY_NORMAL,
+            timestamp: Utc::now(),
+            payload: b"stored".to_vec(),
+            route_history: vec![1],
+        };
+        network.store_packet(stored.clone());
+        assert_eq!(network.stored_packet_count(), 2);
+        assert_eq!(network.custody_count(), 1);
+
+        // An open contact takes custody packets too, highest priority first
+        let flushed = network.flush_stored_packets(10);
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed[0].payload, b"custody");
+        assert_eq!(network.custody_count(), 0);
+
+        // Stored packets expire on the same lifetime as custody packets
+        stored.timestamp = Utc::now() - Duration::minutes(11);
+        network.store_packet(stored);
+        assert!(network.flush_stored_packets(10).is_empty());
+        assert_eq!(network.stored_packet_count(), 0);
+    }
+
+    #[test]
+    fn test_neighbor_discovery_is_symmetric() {
+        let mut network = MeshNetwork::new();
+        let mut short_range = NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 7.66));
+        short_range.communication_range = 800.0;
+        let mut long_range = NetworkNode::new_cubesat(2, position_at(5.0, 0.0, 7.66));
+        long_range.communication_range = 3000.0;
+        network.add_node(short_range).unwrap();
+        network.add_node(long_range).unwrap();
+        network.discover_neighbors().unwrap();
+
+        // ~590 km apart, inside the smaller of the two ranges
+        assert!(network.nodes[&1].neighbors.contains(&2));
+        assert!(network.nodes[&2].neighbors.contains(&1));
+    }
+
+    #[test]
+    fn test_emergency_traffic_prefers_reliable_links() {
+        let mut network = MeshNetwork::new();
+        // Direct relay 2 sits at the edge of range; a longer arc of short hops runs through 3, 5 and 6
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, position_at(0.0, 8.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(4, position_at(0.0, 16.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(3, position_at(2.0, 4.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(5, position_at(2.0, 8.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(6, position_at(2.0, 12.0, 7.66))).unwrap();
+        network.initialize_routing().unwrap();
+
+        let weakest_link = |network: &MeshNetwork, route: &[u32]| route.windows(2)
+            .map(|hop| network.network_topology.adjacency_matrix[&(hop[0], hop[1])])
+            .fold(f64::INFINITY, f64::min);
+
+        let low = network.deliver_message_with_priority(1, 4, b"bulk", PRIORITY_LOW).unwrap().unwrap();
+        let emergency = network.deliver_message_with_priority(1, 4, b"mayday", PRIORITY_EMERGENCY).unwrap().unwrap();
+        assert_eq!(low.route_history, vec![1, 2, 4]);
+        assert_ne!(emergency.route_history, low.route_history);
+        assert!(weakest_link(&network, &emergency.route_history) > weakest_link(&network, &low.route_history));
+        assert_eq!(network.get_statistics().routed_by_priority[&PRIORITY_LOW], 1);
+        assert_eq!(network.get_statistics().routed_by_priority[&PRIORITY_EMERGENCY], 1);
+
+        // Only emergency traffic may relay through a nearly flat node
+        for node in network.nodes.values_mut().filter(|node| node.node_id != 1 && node.node_id != 4) {
+            node.battery_level = 0.1;
+        }
+        assert!(!network.route_message_with_priority(1, 4, b"bulk", PRIORITY_LOW).unwrap());
+        assert!(network.route_message_with_priority(1, 4, b"mayday", PRIORITY_EMERGENCY).unwrap());
+    }
+
+    #[test]
+    fn test_k_disjoint_routes() {
+        let mut network = MeshNetwork::new();
+        // Two parallel relay chains between 1 and 6: 2-3 to the north and 4-5 to the south
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, position_at(3.0, 5.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(3, position_at(3.0, 10.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(4, position_at(-3.0, 5.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(5, position_at(-3.0, 10.0, 7.66))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(6, position_at(0.0, 15.0, 7.66))).unwrap();
+        network.initialize_routing().unwrap();
+
+        let routes = network.find_k_disjoint_routes(1, 6, 3);
+        assert_eq!(routes.len(), 2);
+        let mut chains: Vec<Vec<u32>> = routes.clone();
+        chains.sort();
+        assert_eq!(chains, vec![vec![2, 3, 6], vec![4, 5, 6]]);
+        let first: HashSet<u32> = routes[0][..routes[0].len() - 1].iter().copied().collect();
+        assert!(routes[1].iter().all(|hop| !first.contains(hop)));
+
+        // Emergency packets go out over both chains
+        assert!(network.route_message_with_priority(1, 6, b"mayday", PRIORITY_EMERGENCY).unwrap());
+        assert_eq!(network.get_statistics().emergency_replicas, 1);
+        assert!(network.find_k_disjoint_routes(1, 99, 2).is_empty());
+    }
+
+    #[test]
+    fn test_forward_packet_ttl_matches_path_length() {
+        let mut network = MeshNetwork::new();
+        for i in 0..6u32 {
+            network.add_node(NetworkNode::new_cubesat(i + 1, position_at(i as f64 * 6.0, 0.0, 7.66))).unwrap();
+        }
+        let packet = |ttl: u8| NetworkPacket {
+            packet_id: 7,
+            source: 1,
+            destination: 6,
+            next_hop: 0,
+            ttl,
+            priority: PRIORITY_NORMAL,
+            timestamp: Utc::now(),
+            payload: b"five hops".to_vec(),
+            route_history: vec![1],
+        };
+        let route = [2, 3, 4, 5, 6];
+
+        let delivered = network.forward_packet(packet(6), &route).unwrap();
+        assert_eq!(delivered.route_history, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(delivered.ttl, 1);
+        assert_eq!(delivered.next_hop, 6);
+        assert!(network.forward_packet(packet(5), &route).is_ok());
+
+        let error = network.forward_packet(packet(4), &route).unwrap_err();
+        assert!(error.contains("route needs 5 hops, TTL is 4"), "{}", error);
+        assert_eq!(network.get_statistics().packets_dropped, 1);
+    }
+
+    #[test]
+    fn test_congested_link_is_avoided_until_load_decays() {
+        let mut network = MeshNetwork::new();
+        // Relay 2 is slightly closer to the direct line than relay 3
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, position_at(2.0, 5.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(3, position_at(-2.5, 5.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(4, position_at(0.0, 10.0, 0.0))).unwrap();
+        network.initialize_routing().unwrap();
+
+        let mut relays = Vec::new();
+        for _ in 0..10 {
+            let packet = network.deliver_message_with_priority(1, 4, b"load", PRIORITY_LOW).unwrap().unwrap();
+            relays.push(packet.route_history[1]);
+        }
+        assert_eq!(relays[0], 2);
+        let diverged_at = relays.iter().position(|&relay| relay == 3).expect("traffic never diverged");
+        assert!(diverged_at > 0);
+        assert!(network.link_load(1, 2) > 0.0);
+        assert!(network.link_load(3, 1) > 0.0);
+
+        // Once the load drains the cheaper relay is preferred again
+        network.simulate_network_step(Duration::seconds(600)).unwrap();
+        assert!(network.link_load(1, 2) < 0.01);
+        let packet = network.deliver_message_with_priority(1, 4, b"idle", PRIORITY_LOW).unwrap().unwrap();
+        assert_eq!(packet.route_history, vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_partition_detection() {
+        let mut network = MeshNetwork::new();
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, position_at(4.0, 0.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(3, position_at(8.0, 0.0, 0.05))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(4, position_at(12.0, 0.0, 0.05))).unwrap();
+        network.initialize_routing().unwrap();
+        assert!(!network.is_partitioned());
+
+        network.simulate_network_step(Duration::seconds(1)).unwrap();
+        assert_eq!(network.get_statistics().partition_events, 0);
+
+        // Nodes 3 and 4 drift ~27 degrees away together, splitting the mesh in two
+        network.simulate_network_step(Duration::seconds(60000)).unwrap();
+        assert_eq!(network.connected_components(), vec![vec![1, 2], vec![3, 4]]);
+        assert!(network.is_partitioned());
+        assert_eq!(network.get_statistics().partition_events, 1);
+    }
+
+    #[test]
+    fn test_distance_vector_tables_per_node() {
+        let mut network = MeshNetwork::new();
+        for (node_id, latitude) in [(1, 0.0), (2, 6.0), (3, 12.0)] {
+            network.add_node(NetworkNode::new_cubesat(node_id, position_at(latitude, 0.0, 7.66))).unwrap();
+        }
+        network.initialize_routing().unwrap();
+
+        let middle = network.routing_table(2).unwrap();
+        for end in [1, 3] {
+            let route = middle.route_to(end).unwrap();
+            assert_eq!((route.next_hop, route.hop_count), (end, 1));
+        }
+
+        for (from, to) in [(1, 3), (3, 1)] {
+            let table = network.routing_table(from).unwrap();
+            assert_eq!(table.len(), 2);
+            let route = table.route_to(to).unwrap();
+            assert_eq!((route.next_hop, route.hop_count), (2, 2));
+            assert!(route.cost > table.route_to(2).unwrap().cost);
+        }
+    }
+
+    #[test]
+    fn test_failing_link_loses_reliability_and_is_avoided() {
+        let mut network = MeshNetwork::new();
+        network.add_node(NetworkNode::new_cubesat(1, position_at(0.0, 0.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(2, position_at(2.0, 5.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(3, position_at(-2.5, 5.0, 0.0))).unwrap();
+        network.add_node(NetworkNode::new_cubesat(4, position_at(0.0, 10.0, 0.0))).unwrap();
+        network.initialize_routing().unwrap();
+        assert_eq!(network.find_route(1, 4, PRIORITY_LOW).unwrap(), vec![2, 4]);
+
+        for _ in 0..10 {
+            network.record_link_outcome(1, 2, false);
+        }
+        assert!(network.link_reliability(2, 1) < 0.2);
+        assert_eq!(network.routing_table(1).unwrap().route_to(2).unwrap().reliability, network.link_reliability(1, 2));
+        assert_eq!(network.find_route(1, 4, PRIORITY_LOW).unwrap(), vec![3, 4]);
+
+        // Simulated losses are recorded against the link they happened on
+        network.set_link_failure_probability(1.0);
+        assert!(network.route_message_with_priority(1, 4, b"lost", PRIORITY_LOW).is_err());
+        assert!(network.link_reliability(1, 3) < INITIAL_LINK_RELIABILITY);
+    }
+
+    #[test]
+    fn test_route_message_at_uses_contact_windows() {
+        use crate::ground_station::ContactWindow;
+
+        let mut network = MeshNetwork::new();
+        network.add_node(NetworkNode::new_ground_station(10, 52.0, 4.0)).unwrap();
+        network.add_node(NetworkNode::new_ground_station(20, 40.0, -4.0)).unwrap();
+        network.add_node(NetworkNode::new_cubesat(1, position_at(46.0, 0.0, 7.66))).unwrap();
+
+        let start = Utc::now();
+        let window = |window_id: u32, from_min: i64, to_min: i64| ContactWindow {
+            window_id,
+            station_id: 0,
+            satellite_id: 1,
+            start_time: start + Duration::minutes(from_min),
+            end_time: start + Duration::minutes(to_min),
+            max_elevation: 45.0,
+            azimuth_range: (0.0, 180.0),
+            predicted_snr: 12.0,
+            priority: 1,
+        };
+        // The two passes only overlap between minutes 10 and 15
+        let mut plan = ContactPlan::new();
+        plan.ingest_windows(10, &[window(1, 0, 15)]).unwrap();
+        plan.ingest_windows(20, &[window(2, 10, 25)]).unwrap();
+        network.set_contact_plan(plan);
+
+        let departure = start + Duration::minutes(12);
+        let delivered_at = network.route_message_at(10, 20, b"relay", departure).unwrap().unwrap();
+        assert!(delivered_at > departure && delivered_at < departure + Duration::seconds(1));
+
+        for minute in [5, 20] {
+            assert!(network.route_message_at(10, 20, b"relay", start + Duration::minutes(minute)).unwrap().is_none());
+        }
+        assert_eq!(network.get_statistics().packets_dropped, 2);
+    }
+
+    #[test]
+    fn test_duplicate_node_id_across_types_rejected() {
+        let mut network = MeshNetwork::new();
+        let position = position_at(0.0, 0.0, 7.66);
+        network.add_node(NetworkNode::new_cubesat(1, position.clone())).unwrap();
+
+        let result = network.add_node(NetworkNode::new_ground_station(1, 52.5, 13.4));
+        assert!(result.is_err());
+        assert_eq!(network.nodes[&1].node_type, NodeType::CubeSat);
+        assert!(network.ground_stations.is_empty());
+
+        // Re-adding a node of the same type updates it in place
+        assert!(network.add_node(NetworkNode::new_cubesat(1, position)).is_ok());
+        assert_eq!(network.nodes.len(), 1);
+    }
 }
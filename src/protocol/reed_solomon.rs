@@ -0,0 +1,246 @@
+// Reed-Solomon forward error correction over GF(2^8) for SpaceCAN frames
+
+/// Parity symbols per block, correcting up to 16 symbol errors (CCSDS RS(255,223))
+pub const RS_PARITY_SYMBOLS: usize = 32;
+/// Data symbols per full-length block
+pub const RS_DATA_SYMBOLS: usize = 255 - RS_PARITY_SYMBOLS;
+
+/// GF(2^8) arithmetic tables for the primitive polynomial x^8 + x^4 + x^3 + x^2 + 1
+struct GaloisField {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+lazy_static::lazy_static! {
+    static ref GF: GaloisField = GaloisField::new();
+}
+
+impl GaloisField {
+    fn new() -> Self {
+        let mut exp = [0u8; 512];
+        let mut log = [0u8; 256];
+        let mut x: u16 = 1;
+        for (i, entry) in exp.iter_mut().take(255).enumerate() {
+            *entry = x as u8;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11d;
+            }
+        }
+        // Duplicate the table so products can index without a modulo
+        exp.copy_within(0..257, 255);
+        Self { exp, log }
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        if a == 0 {
+            return 0;
+        }
+        self.exp[(self.log[a as usize] as usize + 255 - self.log[b as usize] as usize) % 255]
+    }
+
+    /// alpha^power for any (possibly negative) power
+    fn alpha_pow(&self, power: i32) -> u8 {
+        self.exp[power.rem_euclid(255) as usize]
+    }
+
+    /// Evaluate a polynomial stored lowest degree first
+    fn eval_low_first(&self, poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &coef| self.mul(acc, x) ^ coef)
+    }
+}
+
+/// Systematic Reed-Solomon codec with a configurable number of parity symbols
+#[derive(Debug, Clone)]
+pub struct ReedSolomon {
+    parity_symbols: usize,
+    generator: Vec<u8>,  // highest degree first, monic
+}
+
+impl Default for ReedSolomon {
+    fn default() -> Self {
+        Self::new(RS_PARITY_SYMBOLS)
+    }
+}
+
+impl ReedSolomon {
+    pub fn new(parity_symbols: usize) -> Self {
+        let mut generator = vec![1u8];
+        for i in 0..parity_symbols {
+            let root = GF.alpha_pow(i as i32);
+            let mut next = vec![0u8; generator.len() + 1];
+            for (j, &coef) in generator.iter().enumerate() {
+                next[j] ^= coef;
+                next[j + 1] ^= GF.mul(coef, root);
+            }
+            generator = next;
+        }
+        Self { parity_symbols, generator }
+    }
+
+    /// Largest number of symbol errors per block that is guaranteed to be corrected
+    pub fn correction_capacity(&self) -> usize {
+        self.parity_symbols / 2
+    }
+
+    /// Largest data length a single block can carry
+    pub fn max_data_len(&self) -> usize {
+        255 - self.parity_symbols
+    }
+
+    /// Compute parity symbols for one block of data
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let mut remainder = vec![0u8; self.parity_symbols];
+        for &byte in data {
+            let feedback = byte ^ remainder[0];
+            remainder.rotate_left(1);
+            remainder[self.parity_symbols - 1] = 0;
+            if feedback != 0 {
+                for (r, &g) in remainder.iter_mut().zip(&self.generator[1..]) {
+                    *r ^= GF.mul(g, feedback);
+                }
+            }
+        }
+        remainder
+    }
+
+    /// Correct one block in place, returning the number of symbols repaired
+    pub fn correct(&self, data: &mut [u8], parity: &mut [u8]) -> Result<usize, String> {
+        if parity.len() != self.parity_symbols || data.len() > self.max_data_len() {
+            return Err("Invalid Reed-Solomon block length".to_string());
+        }
+        let n = data.len() + parity.len();
+        let symbol = |codeword_index: usize, data: &[u8], parity: &[u8]| {
+            if codeword_index < data.len() { data[codeword_index] } else { parity[codeword_index - data.len()] }
+        };
+
+        // Syndromes S_i = r(alpha^i)
+        let syndromes: Vec<u8> = (0..self.parity_symbols)
+            .map(|i| {
+                let x = GF.alpha_pow(i as i32);
+                (0..n).fold(0, |acc, k| GF.mul(acc, x) ^ symbol(k, data, parity))
+            })
+            .collect();
+        if syndromes.iter().all(|&s| s == 0) {
+            return Ok(0);
+        }
+
+        // Berlekamp-Massey for the error locator, lowest degree first
+        let mut locator = vec![1u8];
+        let mut previous = vec![1u8];
+        let mut errors = 0;
+        let mut shift = 1;
+        let mut previous_discrepancy = 1u8;
+        for step in 0..self.parity_symbols {
+            let discrepancy = (1..=errors).fold(syndromes[step], |acc, i| {
+                acc ^ GF.mul(*locator.get(i).unwrap_or(&0), syndromes[step - i])
+            });
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+            let scale = GF.div(discrepancy, previous_discrepancy);
+            let mut updated = locator.clone();
+            if updated.len() < previous.len() + shift {
+                updated.resize(previous.len() + shift, 0);
+            }
+            for (i, &coef) in previous.iter().enumerate() {
+                updated[i + shift] ^= GF.mul(scale, coef);
+            }
+            if 2 * errors <= step {
+                previous = locator;
+                errors = step + 1 - errors;
+                previous_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                shift += 1;
+            }
+            locator = updated;
+        }
+        locator.truncate(errors + 1);
+        if errors > self.correction_capacity() {
+            return Err("Too many errors to correct".to_string());
+        }
+
+        // Chien search over the codeword positions actually present in this (possibly shortened) block
+        let positions: Vec<usize> = (0..n)
+            .filter(|&power| GF.eval_low_first(&locator, GF.alpha_pow(-(power as i32))) == 0)
+            .collect();
+        if positions.len() != errors {
+            return Err("Error locator does not match codeword".to_string());
+        }
+
+        // Forney: Omega = S * Lambda mod x^(2t), e = X * Omega(X^-1) / Lambda'(X^-1)
+        let mut evaluator = vec![0u8; self.parity_symbols];
+        for (i, &s) in syndromes.iter().enumerate() {
+            for (j, &l) in locator.iter().enumerate() {
+                if i + j < self.parity_symbols {
+                    evaluator[i + j] ^= GF.mul(s, l);
+                }
+            }
+        }
+        let derivative: Vec<u8> = locator.iter().enumerate().skip(1)
+            .map(|(i, &coef)| if i % 2 == 1 { coef } else { 0 })
+            .collect();
+
+        for &power in &positions {
+            let x = GF.alpha_pow(power as i32);
+            let x_inv = GF.alpha_pow(-(power as i32));
+            let denominator = GF.eval_low_first(&derivative, x_inv);
+            if denominator == 0 {
+                return Err("Degenerate error locator".to_string());
+            }
+            let magnitude = GF.div(GF.mul(x, GF.eval_low_first(&evaluator, x_inv)), denominator);
+            let index = n - 1 - power;
+            if index < data.len() {
+                data[index] ^= magnitude;
+            } else {
+                parity[index - data.len()] ^= magnitude;
+            }
+        }
+
+        Ok(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codeword_has_zero_syndromes() {
+        let codec = ReedSolomon::default();
+        let data: Vec<u8> = (0..RS_DATA_SYMBOLS).map(|i| (i * 7) as u8).collect();
+        let mut parity = codec.encode(&data);
+        let mut received = data.clone();
+
+        assert_eq!(parity.len(), RS_PARITY_SYMBOLS);
+        assert_eq!(codec.correct(&mut received, &mut parity).unwrap(), 0);
+        assert_eq!(received, data);
+    }
+
+    #[test]
+    fn test_corrects_errors_in_parity_and_shortened_blocks() {
+        let codec = ReedSolomon::new(8);
+        let data = b"short block".to_vec();
+        let original_parity = codec.encode(&data);
+
+        let mut received = data.clone();
+        let mut parity = original_parity.clone();
+        received[0] ^= 0x5a;
+        parity[3] ^= 0x01;
+        parity[7] ^= 0xff;
+
+        assert_eq!(codec.correct(&mut received, &mut parity).unwrap(), 3);
+        assert_eq!(received, data);
+        assert_eq!(parity, original_parity);
+    }
+}
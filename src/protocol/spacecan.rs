@@ -1,8 +1,33 @@
 // SpaceCAN base implementation and CubeSat-specific extensions
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use chrono::{DateTime, Utc};
-use log::{info, warn, error};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use chrono::{DateTime, Duration, Utc};
+use log::{info, warn, error, debug};
+use crate::config::NetworkConfig;
+use crate::security::{CryptoModule, GCM_TAG_LEN, NONCE_LEN};
+use super::reed_solomon::{ReedSolomon, RS_DATA_SYMBOLS, RS_PARITY_SYMBOLS};
+
+/// Header flag (high bit of the power mode byte) marking an encrypted payload
+pub const FRAME_FLAG_ENCRYPTED: u8 = 0x80;
+/// Frame id reserved for stop-and-wait ARQ acknowledgments
+pub const ARQ_ACK_FRAME_ID: u32 = 0x1FFF_FFFF;
+
+/// Header flag marking a frame that carries fragment index/total fields
+pub const FRAME_FLAG_FRAGMENTED: u8 = 0x40;
+
+/// Waits out a retransmission backoff; defaults to sleeping the calling thread
+pub type BackoffDelay = Box<dyn FnMut(std::time::Duration) + Send>;
+
+/// Bytes frame encryption adds to a payload: the AES-GCM nonce and tag
+pub const FRAME_ENCRYPTION_OVERHEAD: usize = NONCE_LEN + GCM_TAG_LEN;
+/// Largest payload whose ciphertext still fits the one-byte length field
+pub const MAX_ENCRYPTABLE_PAYLOAD: usize = u8::MAX as usize - FRAME_ENCRYPTION_OVERHEAD;
+
+/// Wire format version carried in the high nibble of the priority byte.
+/// Version 1 added sub-second timestamps and cannot be read by version 0 decoders.
+pub const FRAME_FORMAT_VERSION: u8 = 1;
+/// Smallest possible encoded frame: header, timestamp, checksum and ECC length
+const MIN_FRAME_LEN: usize = 7 + 2 + 12 + 4 + 1;
 
 /// Frame priority levels for CubeSat communications
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -22,6 +47,76 @@ pub enum PowerMode {
     UltraLow,     // Emergency mode, minimal energy
 }
 
+/// Channel modulation schemes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Modulation {
+    Bpsk,
+    Qpsk,
+    Psk8,
+    Qam16,
+}
+
+/// Forward error correction code rates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodingRate {
+    Uncoded,
+    Half,
+    TwoThirds,
+    ThreeQuarters,
+    SevenEighths,
+}
+
+impl Modulation {
+    pub fn bits_per_symbol(&self) -> f64 {
+        match self {
+            Modulation::Bpsk => 1.0,
+            Modulation::Qpsk => 2.0,
+            Modulation::Psk8 => 3.0,
+            Modulation::Qam16 => 4.0,
+        }
+    }
+
+    /// Uncoded bit error rate for a linear Eb/N0
+    fn bit_error_rate(&self, eb_n0: f64) -> f64 {
+        match self {
+            Modulation::Bpsk | Modulation::Qpsk => 0.5 * erfc(eb_n0.sqrt()),
+            Modulation::Psk8 => erfc((3.0 * eb_n0).sqrt() * (std::f64::consts::PI / 8.0).sin()) / 3.0,
+            Modulation::Qam16 => 0.375 * erfc((0.4 * eb_n0).sqrt()),
+        }
+    }
+}
+
+impl CodingRate {
+    pub fn ratio(&self) -> f64 {
+        match self {
+            CodingRate::Uncoded => 1.0,
+            CodingRate::Half => 1.0 / 2.0,
+            CodingRate::TwoThirds => 2.0 / 3.0,
+            CodingRate::ThreeQuarters => 3.0 / 4.0,
+            CodingRate::SevenEighths => 7.0 / 8.0,
+        }
+    }
+
+    /// Approximate coding gain in dB for a convolutional code at this rate
+    pub fn coding_gain_db(&self) -> f64 {
+        match self {
+            CodingRate::Uncoded => 0.0,
+            CodingRate::Half => 5.0,
+            CodingRate::TwoThirds => 4.2,
+            CodingRate::ThreeQuarters => 3.5,
+            CodingRate::SevenEighths => 2.5,
+        }
+    }
+}
+
+/// Complementary error function (Abramowitz and Stegun 7.1.26)
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erfc_abs = poly * (-x * x).exp();
+    if x >= 0.0 { erfc_abs } else { 2.0 - erfc_abs }
+}
+
 /// Enhanced SpaceCAN frame with CubeSat-specific features
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpaceCANFrame {
@@ -34,6 +129,16 @@ pub struct SpaceCANFrame {
     pub sequence_number: u16,
     pub checksum: u32,
     pub error_correction: Vec<u8>,  // Reed-Solomon or similar
+    #[serde(default)]
+    pub encrypted: bool,
+    #[serde(default)]
+    pub fragment_index: u16,
+    #[serde(default = "default_fragment_total")]
+    pub fragment_total: u16,
+}
+
+fn default_fragment_total() -> u16 {
+    1
 }
 
 impl SpaceCANFrame {
@@ -54,9 +159,59 @@ impl SpaceCANFrame {
             sequence_number,
             checksum,
             error_correction,
+            encrypted: false,
+            fragment_index: 0,
+            fragment_total: 1,
         }
     }
 
+    /// Split a payload into ordered frames of at most `max_payload` bytes sharing one sequence number
+    pub fn fragment(id: u32, data: &[u8], priority: FramePriority, max_payload: usize) -> Result<Vec<Self>, String> {
+        // Leave room for encryption, which may be applied when the fragment is transmitted
+        if max_payload == 0 || max_payload > MAX_ENCRYPTABLE_PAYLOAD {
+            return Err(format!("Fragment payload size must be 1-{} bytes, got {}", MAX_ENCRYPTABLE_PAYLOAD, max_payload));
+        }
+        let total = (data.len().max(1) + max_payload - 1) / max_payload;
+        if total > u16::MAX as usize {
+            return Err(format!("Payload of {} bytes needs too many fragments", data.len()));
+        }
+
+        let sequence_number = rand::random::<u16>();
+        let chunks: Vec<&[u8]> = if data.is_empty() { vec![data] } else { data.chunks(max_payload).collect() };
+        Ok(chunks.into_iter().enumerate()
+            .map(|(index, chunk)| {
+                let mut frame = Self::new(id, chunk.to_vec(), priority);
+                frame.sequence_number = sequence_number;
+                frame.fragment_index = index as u16;
+                frame.fragment_total = total as u16;
+                frame
+            })
+            .collect())
+    }
+
+    /// Replace the payload, refreshing length, checksum and error correction
+    pub fn set_payload(&mut self, data: Vec<u8>) {
+        self.dlc = data.len().min(255) as u8;
+        self.checksum = Self::calculate_checksum(&data);
+        self.error_correction = Self::generate_error_correction(&data);
+        self.data = data;
+    }
+
+    /// Build an ARQ acknowledgment for the frame with the given sequence number
+    pub fn acknowledgment(sequence_number: u16) -> Self {
+        let mut ack = Self::new(ARQ_ACK_FRAME_ID, sequence_number.to_be_bytes().to_vec(), FramePriority::High);
+        ack.sequence_number = sequence_number;
+        ack
+    }
+
+    /// Sequence number acknowledged by this frame, if it is an ARQ acknowledgment
+    pub fn acknowledged_sequence(&self) -> Option<u16> {
+        if self.id != ARQ_ACK_FRAME_ID || self.data.len() != 2 {
+            return None;
+        }
+        Some(u16::from_be_bytes([self.data[0], self.data[1]]))
+    }
+
     pub fn with_power_mode(mut self, power_mode: PowerMode) -> Self {
         self.power_mode = power_mode;
         self
@@ -78,32 +233,41 @@ impl SpaceCANFrame {
         !crc
     }
 
-    /// Generate Reed-Solomon error correction codes
+    /// Generate Reed-Solomon parity, one RS(255,223) block per 223 payload bytes
     fn generate_error_correction(data: &[u8]) -> Vec<u8> {
-        // Simplified error correction - in production, use proper Reed-Solomon
-        let mut ecc = Vec::new();
-        for chunk in data.chunks(4) {
-            let sum: u8 = chunk.iter().fold(0, |acc, &x| acc.wrapping_add(x));
-            ecc.push(sum);
-        }
-        ecc
+        let codec = ReedSolomon::default();
+        data.chunks(RS_DATA_SYMBOLS)
+            .flat_map(|block| codec.encode(block))
+            .collect()
     }
 
     /// Encode frame with space-optimized format
     pub fn encode(&self) -> Vec<u8> {
         let mut encoded = Vec::new();
         
-        // Header: ID (4 bytes) + DLC (1 byte) + Priority (1 byte) + Power Mode (1 byte)
+        // Header: ID (4 bytes) + DLC (1 byte) + Version/Priority (1 byte) + Power Mode (1 byte)
         encoded.extend_from_slice(&self.id.to_be_bytes());
         encoded.push(self.dlc);
-        encoded.push(self.priority as u8);
-        encoded.push(self.power_mode as u8);
+        encoded.push((FRAME_FORMAT_VERSION << 4) | self.priority as u8);
+        let fragmented = self.fragment_total > 1;
+        let mut flags = if self.encrypted { FRAME_FLAG_ENCRYPTED } else { 0 };
+        if fragmented {
+            flags |= FRAME_FLAG_FRAGMENTED;
+        }
+        encoded.push(self.power_mode as u8 | flags);
         
         // Sequence number (2 bytes)
         encoded.extend_from_slice(&self.sequence_number.to_be_bytes());
         
-        // Timestamp (8 bytes - Unix timestamp)
+        // Timestamp (8 bytes Unix seconds + 4 bytes nanoseconds)
         encoded.extend_from_slice(&self.timestamp.timestamp().to_be_bytes());
+        encoded.extend_from_slice(&self.timestamp.timestamp_subsec_nanos().to_be_bytes());
+        
+        // Fragment index and total (2 + 2 bytes, fragmented frames only)
+        if fragmented {
+            encoded.extend_from_slice(&self.fragment_index.to_be_bytes());
+            encoded.extend_from_slice(&self.fragment_total.to_be_bytes());
+        }
         
         // Data payload
         encoded.extend_from_slice(&self.data);
@@ -120,7 +284,7 @@ impl SpaceCANFrame {
 
     /// Decode frame with error detection and correction
     pub fn decode(bytes: &[u8]) -> Result<Self, String> {
-        if bytes.len() < 21 {  // Minimum frame size
+        if bytes.len() < MIN_FRAME_LEN {
             return Err("Frame too short".to_string());
         }
 
@@ -133,7 +297,11 @@ impl SpaceCANFrame {
         let dlc = bytes[offset];
         offset += 1;
         
-        let priority = match bytes[offset] {
+        let version = bytes[offset] >> 4;
+        if version != FRAME_FORMAT_VERSION {
+            return Err(format!("Unsupported frame format version {}", version));
+        }
+        let priority = match bytes[offset] & 0x0F {
             0 => FramePriority::Emergency,
             1 => FramePriority::High,
             2 => FramePriority::Normal,
@@ -142,7 +310,9 @@ impl SpaceCANFrame {
         };
         offset += 1;
         
-        let power_mode = match bytes[offset] {
+        let encrypted = bytes[offset] & FRAME_FLAG_ENCRYPTED != 0;
+        let fragmented = bytes[offset] & FRAME_FLAG_FRAGMENTED != 0;
+        let power_mode = match bytes[offset] & !(FRAME_FLAG_ENCRYPTED | FRAME_FLAG_FRAGMENTED) {
             0 => PowerMode::HighPower,
             1 => PowerMode::MediumPower,
             2 => PowerMode::LowPower,
@@ -160,9 +330,25 @@ impl SpaceCANFrame {
             bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3],
             bytes[offset + 4], bytes[offset + 5], bytes[offset + 6], bytes[offset + 7],
         ]);
-        let timestamp = DateTime::from_timestamp(timestamp_secs, 0)
+        let timestamp_nanos = u32::from_be_bytes([
+            bytes[offset + 8], bytes[offset + 9], bytes[offset + 10], bytes[offset + 11],
+        ]);
+        let timestamp = DateTime::from_timestamp(timestamp_secs, timestamp_nanos)
             .ok_or("Invalid timestamp")?;
-        offset += 8;
+        offset += 12;
+        
+        // Parse fragment header
+        let (fragment_index, fragment_total) = if fragmented {
+            let index = u16::from_be_bytes([bytes[offset], bytes[offset + 1]]);
+            let total = u16::from_be_bytes([bytes[offset + 2], bytes[offset + 3]]);
+            offset += 4;
+            if total == 0 || index >= total {
+                return Err("Invalid fragment header".to_string());
+            }
+            (index, total)
+        } else {
+            (0, 1)
+        };
         
         // Parse data payload
         if offset + dlc as usize + 5 > bytes.len() {
@@ -178,12 +364,6 @@ impl SpaceCANFrame {
         ]);
         offset += 4;
         
-        // Verify checksum
-        let calculated_checksum = Self::calculate_checksum(&data);
-        if checksum != calculated_checksum {
-            return Err("Checksum mismatch".to_string());
-        }
-        
         // Parse error correction
         let ecc_len = bytes[offset] as usize;
         offset += 1;
@@ -194,7 +374,7 @@ impl SpaceCANFrame {
         
         let error_correction = bytes[offset..offset + ecc_len].to_vec();
         
-        Ok(Self {
+        let mut frame = Self {
             id,
             data,
             dlc,
@@ -204,7 +384,26 @@ impl SpaceCANFrame {
             sequence_number,
             checksum,
             error_correction,
-        })
+            encrypted,
+            fragment_index,
+            fragment_total,
+        };
+        
+        // Verify checksum and ECC, repairing the payload if either disagrees
+        let checksum_ok = frame.checksum == Self::calculate_checksum(&frame.data);
+        if !checksum_ok || frame.error_correction != Self::generate_error_correction(&frame.data) {
+            warn!("Frame 0x{:X} failed integrity check, attempting error correction", frame.id);
+            if !frame.attempt_error_correction() || frame.checksum != Self::calculate_checksum(&frame.data) {
+                return Err(if checksum_ok {
+                    "Error correction codes do not match payload".to_string()
+                } else {
+                    "Checksum mismatch and payload could not be corrected".to_string()
+                });
+            }
+            info!("Repaired frame 0x{:X} during decode", frame.id);
+        }
+        
+        Ok(frame)
     }
 
     /// Validate frame integrity and attempt error correction
@@ -215,12 +414,10 @@ impl SpaceCANFrame {
             warn!("Checksum mismatch detected, attempting error correction");
             
             // Attempt error correction using ECC
-            let correction_result = self.attempt_error_correction();
-            if correction_result {
-                info!("Error correction successful");
-                self.checksum = Self::calculate_checksum(&self.data);
-                // After correction, verify checksum again
-                if self.checksum == calculated_checksum {
+            if self.attempt_error_correction() {
+                // Only trust the correction if it restores the original checksum
+                if Self::calculate_checksum(&self.data) == self.checksum {
+                    info!("Error correction successful");
                     return Ok(true);
                 } else {
                     error!("Error correction failed to fix checksum");
@@ -235,23 +432,25 @@ impl SpaceCANFrame {
         Ok(false)
     }
 
-    /// Attempt to correct errors using error correction codes
+    /// Attempt to correct errors using the Reed-Solomon parity, block by block
     fn attempt_error_correction(&mut self) -> bool {
-        // Simplified error correction - in production, implement proper Reed-Solomon
-        for (i, chunk) in self.data.chunks_mut(4).enumerate() {
-            if i < self.error_correction.len() {
-                let expected_sum = self.error_correction[i];
-                let actual_sum: u8 = chunk.iter().fold(0, |acc, &x| acc.wrapping_add(x));
-                
-                if expected_sum != actual_sum {
-                    // Simple single-bit error correction
-                    let diff = expected_sum.wrapping_sub(actual_sum);
-                    if chunk.len() > 0 {
-                        chunk[0] = chunk[0].wrapping_add(diff);
-                    }
-                }
+        let codec = ReedSolomon::default();
+        let blocks = (self.data.len() + RS_DATA_SYMBOLS - 1) / RS_DATA_SYMBOLS;
+        if self.error_correction.len() != blocks * RS_PARITY_SYMBOLS {
+            return false;
+        }
+
+        let mut data = self.data.clone();
+        let mut parity = self.error_correction.clone();
+        for (block, block_parity) in data.chunks_mut(RS_DATA_SYMBOLS).zip(parity.chunks_mut(RS_PARITY_SYMBOLS)) {
+            if let Err(e) = codec.correct(block, block_parity) {
+                warn!("Reed-Solomon decoding failed: {}", e);
+                return false;
             }
         }
+
+        self.data = data;
+        self.error_correction = parity;
         true
     }
 
@@ -276,11 +475,192 @@ impl SpaceCANFrame {
     }
 }
 
+/// Default time to wait for the remaining fragments of a message
+pub const DEFAULT_REASSEMBLY_TIMEOUT_SECS: i64 = 30;
+
+/// Fragments of one message received so far
+#[derive(Debug, Clone)]
+struct PartialMessage {
+    total: u16,
+    fragments: BTreeMap<u16, Vec<u8>>,
+    first_seen: DateTime<Utc>,
+}
+
+/// Rebuilds payloads split by `SpaceCANFrame::fragment`, tolerating reordering and duplicates
+#[derive(Debug, Clone)]
+pub struct Reassembler {
+    pending: HashMap<(u32, u16), PartialMessage>,
+    pub timeout: Duration,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self {
+            pending: HashMap::new(),
+            timeout: Duration::seconds(DEFAULT_REASSEMBLY_TIMEOUT_SECS),
+        }
+    }
+
+    /// Accept a frame, returning the full payload once its last missing fragment arrives
+    pub fn accept(&mut self, frame: SpaceCANFrame) -> Result<Option<Vec<u8>>, String> {
+        if frame.fragment_total <= 1 {
+            return Ok(Some(frame.data));
+        }
+
+        let key = (frame.id, frame.sequence_number);
+        let partial = self.pending.entry(key).or_insert_with(|| PartialMessage {
+            total: frame.fragment_total,
+            fragments: BTreeMap::new(),
+            first_seen: Utc::now(),
+        });
+        if partial.total != frame.fragment_total {
+            return Err(format!("Fragment total mismatch for frame 0x{:X} seq {}: expected {}, got {}",
+                               frame.id, frame.sequence_number, partial.total, frame.fragment_total));
+        }
+        if partial.fragments.contains_key(&frame.fragment_index) {
+            debug!("Ignoring duplicate fragment {} of frame 0x{:X}", frame.fragment_index, frame.id);
+            return Ok(None);
+        }
+        partial.fragments.insert(frame.fragment_index, frame.data);
+
+        if partial.fragments.len() < partial.total as usize {
+            return Ok(None);
+        }
+        let complete = self.pending.remove(&key).ok_or("Reassembly state lost")?;
+        Ok(Some(complete.fragments.into_values().flatten().collect()))
+    }
+
+    /// Drop messages older than the timeout, failing with the fragments they were missing
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+        let timeout = self.timeout;
+        let stale: Vec<(u32, u16)> = self.pending.iter()
+            .filter(|(_, partial)| now - partial.first_seen > timeout)
+            .map(|(&key, _)| key)
+            .collect();
+
+        let mut failures = Vec::new();
+        for key in stale {
+            if let Some(partial) = self.pending.remove(&key) {
+                let missing: Vec<u16> = (0..partial.total)
+                    .filter(|index| !partial.fragments.contains_key(index))
+                    .collect();
+                failures.push(format!("frame 0x{:X} seq {} missing fragments {:?}", key.0, key.1, missing));
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Reassembly timed out: {}", failures.join("; ")))
+        }
+    }
+
+    /// Number of messages still waiting for fragments
+    pub fn pending_messages(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Default number of recently received frames remembered for duplicate detection
+pub const DEFAULT_DEDUP_WINDOW: usize = 256;
+
+/// Remembers the most recent `(id, sequence_number, fragment_index)` keys to suppress duplicate frames
+#[derive(Debug, Clone)]
+pub struct DedupWindow {
+    capacity: usize,
+    order: VecDeque<(u32, u16, u16)>,
+    seen: HashSet<(u32, u16, u16)>,
+}
+
+impl DedupWindow {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            order: VecDeque::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Record a frame, returning false if it was already seen within the window
+    pub fn insert(&mut self, frame: &SpaceCANFrame) -> bool {
+        let key = (frame.id, frame.sequence_number, frame.fragment_index);
+        if !self.seen.insert(key) {
+            return false;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+impl Default for DedupWindow {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEDUP_WINDOW)
+    }
+}
+
 /// SpaceCAN adapter for managing multiple communication channels
 pub struct SpaceCANAdapter {
     channels: HashMap<u8, SpaceCANChannel>,
     frame_buffer: Vec<SpaceCANFrame>,
     statistics: CommunicationStats,
+    #[cfg(test)]
+    injected_faults: u32,
+    frame_crypto: Option<CryptoModule>,
+    arq: ArqConfig,
+    pending_acks: HashSet<u16>,
+    dedup: DedupWindow,
+    backoff_delay: BackoffDelay,
+}
+
+/// Stop-and-wait ARQ retransmission settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArqConfig {
+    pub retry_attempts: u32,
+    pub ack_timeout: std::time::Duration,
+    pub retry_backoff: std::time::Duration, // Pause before each retransmission
+}
+
+impl Default for ArqConfig {
+    fn default() -> Self {
+        Self {
+            retry_attempts: 3,
+            ack_timeout: std::time::Duration::from_millis(1000),
+            retry_backoff: std::time::Duration::ZERO,
+        }
+    }
+}
+
+impl From<&NetworkConfig> for ArqConfig {
+    fn from(config: &NetworkConfig) -> Self {
+        Self {
+            retry_attempts: config.retry_attempts,
+            ack_timeout: std::time::Duration::from_millis(config.connection_timeout_ms),
+            retry_backoff: std::time::Duration::from_millis(config.retry_backoff_ms),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -290,6 +670,56 @@ pub struct SpaceCANChannel {
     pub bandwidth: f64,  // kHz
     pub is_active: bool,
     pub power_mode: PowerMode,
+    pub modulation: Modulation,
+    pub coding_rate: CodingRate,
+    pub hop_sequence: Vec<f64>,  // MHz, empty disables hopping
+    pub hop_interval: Duration,
+    hop_index: usize,
+    last_hop: Option<DateTime<Utc>>,
+}
+
+/// Frequency range to avoid when hopping, in MHz
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FrequencyBand {
+    pub low_mhz: f64,
+    pub high_mhz: f64,
+}
+
+impl FrequencyBand {
+    pub fn contains(&self, frequency: f64) -> bool {
+        frequency >= self.low_mhz && frequency <= self.high_mhz
+    }
+}
+
+impl SpaceCANChannel {
+    /// Move to the next frequency in the hop sequence that is outside every jammed band
+    fn advance_hop(&mut self, jammed_bands: &[FrequencyBand]) -> bool {
+        let len = self.hop_sequence.len();
+        for step in 1..=len {
+            let index = (self.hop_index + step) % len;
+            let candidate = self.hop_sequence[index];
+            if !jammed_bands.iter().any(|band| band.contains(candidate)) {
+                self.hop_index = index;
+                self.frequency = candidate;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Information bit rate after modulation and coding (root-raised-cosine, roll-off 0.35)
+    pub fn effective_data_rate_bps(&self) -> f64 {
+        let symbol_rate = self.bandwidth * 1000.0 / 1.35;
+        symbol_rate * self.modulation.bits_per_symbol() * self.coding_rate.ratio()
+    }
+
+    /// Decoded bit error rate for a given symbol SNR (Es/N0) in dB
+    pub fn bit_error_rate(&self, snr_db: f64) -> f64 {
+        let bits_per_symbol = self.modulation.bits_per_symbol() * self.coding_rate.ratio();
+        let eb_n0_db = snr_db - 10.0 * bits_per_symbol.log10() + self.coding_rate.coding_gain_db();
+        let eb_n0 = 10f64.powf(eb_n0_db / 10.0);
+        self.modulation.bit_error_rate(eb_n0).clamp(0.0, 0.5)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -300,6 +730,10 @@ pub struct CommunicationStats {
     pub errors_corrected: u64,
     pub total_bytes_transmitted: u64,
     pub total_power_consumed: f64,  // Watt-hours
+    pub retransmissions: u64,
+    pub acks_received: u64,
+    pub last_frequency_mhz: Option<f64>,
+    pub duplicates_dropped: u64,
 }
 
 impl SpaceCANAdapter {
@@ -308,6 +742,13 @@ impl SpaceCANAdapter {
             channels: HashMap::new(),
             frame_buffer: Vec::new(),
             statistics: CommunicationStats::default(),
+            #[cfg(test)]
+            injected_faults: 0,
+            frame_crypto: None,
+            arq: ArqConfig::default(),
+            pending_acks: HashSet::new(),
+            dedup: DedupWindow::default(),
+            backoff_delay: Box::new(std::thread::sleep),
         };
         
         // Initialize default channels
@@ -324,37 +765,187 @@ impl SpaceCANAdapter {
             bandwidth,
             is_active: true,
             power_mode: PowerMode::MediumPower,
+            modulation: Modulation::Bpsk,
+            coding_rate: CodingRate::Half,
+            hop_sequence: Vec::new(),
+            hop_interval: Duration::seconds(1),
+            hop_index: 0,
+            last_hop: None,
         };
         self.channels.insert(channel_id, channel);
         info!("Added communication channel {} at {} MHz", channel_id, frequency);
     }
 
+    /// Encrypt frame payloads on transmit and decrypt them on receive
+    pub fn enable_frame_encryption(&mut self, crypto: CryptoModule) {
+        self.frame_crypto = Some(crypto);
+    }
+
     pub fn transmit(&mut self, frame: &SpaceCANFrame) -> Result<(), String> {
+        self.transmit_encoded(frame).map(|_| ())
+    }
+
+    /// Transmit a frame and return the bytes sent on the wire
+    pub fn transmit_encoded(&mut self, frame: &SpaceCANFrame) -> Result<Vec<u8>, String> {
         // Select best channel based on frame priority and power requirements
         let channel_id = self.select_optimal_channel(frame)?;
+
+        #[cfg(test)]
+        if self.injected_faults > 0 {
+            self.injected_faults -= 1;
+            self.statistics.errors_detected += 1;
+            warn!("Transmission of frame {} failed on channel {}", frame.sequence_number, channel_id);
+            return Err("Transmission failed: channel fault".to_string());
+        }
+
+        // Apply frame-level encryption if configured
+        let mut frame = frame.clone();
+        if let Some(crypto) = &self.frame_crypto {
+            if !frame.encrypted {
+                if frame.data.len() > MAX_ENCRYPTABLE_PAYLOAD {
+                    return Err(format!("Frame payload of {} bytes exceeds the {} bytes that fit once encrypted",
+                                       frame.data.len(), MAX_ENCRYPTABLE_PAYLOAD));
+                }
+                let ciphertext = crypto.encrypt(&frame.data)?;
+                frame.set_payload(ciphertext);
+                frame.encrypted = true;
+            }
+        }
         
         // Encode and transmit
         let encoded = frame.encode();
         
         // Update statistics
         self.statistics.frames_sent += 1;
+        self.statistics.last_frequency_mhz = self.channels.get(&channel_id).map(|channel| channel.frequency);
         self.statistics.total_bytes_transmitted += encoded.len() as u64;
         self.statistics.total_power_consumed += frame.get_power_requirements() * 0.1; // 0.1 hour transmission
         
-        info!("Transmitted frame {} on channel {} ({} bytes)", 
-              frame.sequence_number, channel_id, encoded.len());
+        info!("Transmitted frame {} on channel {} at {:?} MHz ({} bytes)", 
+              frame.sequence_number, channel_id, self.statistics.last_frequency_mhz, encoded.len());
         
+        Ok(encoded)
+    }
+
+    /// Configure stop-and-wait retransmission
+    pub fn set_arq_config(&mut self, config: ArqConfig) {
+        self.arq = config;
+    }
+
+    /// Replace how retransmission backoff is waited out, e.g. with a simulated or async clock
+    pub fn set_backoff_delay<F>(&mut self, delay: F)
+    where
+        F: FnMut(std::time::Duration) + Send + 'static,
+    {
+        self.backoff_delay = Box::new(delay);
+    }
+
+    /// Send a frame and wait for its acknowledgment, retransmitting on timeout.
+    ///
+    /// `link` carries the encoded frame to the peer and returns whatever the peer sent back,
+    /// or `None` if nothing arrived. Returns the number of transmissions used.
+    pub fn transmit_reliable<F>(&mut self, frame: &SpaceCANFrame, mut link: F) -> Result<u32, String>
+    where
+        F: FnMut(&[u8]) -> Option<Vec<u8>>,
+    {
+        let sequence_number = frame.sequence_number;
+        self.pending_acks.insert(sequence_number);
+        let attempts = self.arq.retry_attempts + 1;
+
+        for attempt in 1..=attempts {
+            if attempt > 1 {
+                if !self.arq.retry_backoff.is_zero() {
+                    (self.backoff_delay)(self.arq.retry_backoff);
+                }
+                self.statistics.retransmissions += 1;
+                warn!("Retransmitting frame {} (attempt {}/{})", sequence_number, attempt, attempts);
+            }
+
+            let encoded = match self.transmit_encoded(frame) {
+                Ok(encoded) => encoded,
+                Err(e) => {
+                    debug!("Attempt {} for frame {} lost: {}", attempt, sequence_number, e);
+                    continue;
+                }
+            };
+
+            let sent_at = std::time::Instant::now();
+            let reply = link(&encoded);
+            if sent_at.elapsed() > self.arq.ack_timeout {
+                debug!("Acknowledgment for frame {} arrived after timeout", sequence_number);
+                continue;
+            }
+            if let Some(reply) = reply {
+                if let Err(e) = self.accept_frame(&reply) {
+                    warn!("Discarding malformed reply to frame {}: {}", sequence_number, e);
+                }
+            }
+            if !self.pending_acks.contains(&sequence_number) {
+                return Ok(attempt);
+            }
+        }
+
+        self.pending_acks.remove(&sequence_number);
+        Err(format!("Frame {} not acknowledged after {} attempts", sequence_number, attempts))
+    }
+
+    /// Accept a frame from the link and return the encoded acknowledgment to send back
+    pub fn receive_reliable(&mut self, bytes: &[u8]) -> Result<Vec<u8>, String> {
+        let frame = SpaceCANFrame::decode(bytes)?;
+        let sequence_number = frame.sequence_number;
+        self.accept_frame(bytes)?;
+        Ok(SpaceCANFrame::acknowledgment(sequence_number).encode())
+    }
+
+    /// Accept raw bytes from the link, decrypting the payload if the frame is marked encrypted
+    pub fn accept_frame(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let mut frame = SpaceCANFrame::decode(bytes)?;
+
+        if let Some(sequence_number) = frame.acknowledged_sequence() {
+            if self.pending_acks.remove(&sequence_number) {
+                self.statistics.acks_received += 1;
+            } else {
+                debug!("Ignoring acknowledgment for unknown frame {}", sequence_number);
+            }
+            return Ok(());
+        }
+
+        if frame.encrypted {
+            let crypto = self.frame_crypto.as_ref()
+                .ok_or("Received encrypted frame but frame encryption is not enabled")?;
+            let plaintext = crypto.decrypt(&frame.data)?;
+            frame.set_payload(plaintext);
+            frame.encrypted = false;
+        }
+
+        self.frame_buffer.push(frame);
         Ok(())
     }
 
+    /// Resize the duplicate detection window, forgetting previously seen frames
+    pub fn set_dedup_window_size(&mut self, size: usize) {
+        self.dedup = DedupWindow::new(size);
+    }
+
+    /// Make the next `count` transmissions fail, for testing link recovery
+    #[cfg(test)]
+    pub fn inject_faults(&mut self, count: u32) {
+        self.injected_faults = count;
+    }
+
     pub fn receive(&mut self) -> Result<Option<Vec<u8>>, String> {
         // Simulate receiving data from active channels
-        for (_channel_id, channel) in &self.channels {
+        for channel in self.channels.values() {
             if channel.is_active {
                 // In a real implementation, this would interface with radio hardware
                 // For simulation, we'll return buffered frames
-                if !self.frame_buffer.is_empty() {
+                while !self.frame_buffer.is_empty() {
                     let frame = self.frame_buffer.remove(0);
+                    if !self.dedup.insert(&frame) {
+                        self.statistics.duplicates_dropped += 1;
+                        debug!("Dropping duplicate frame {} (id 0x{:X})", frame.sequence_number, frame.id);
+                        continue;
+                    }
                     let encoded = frame.encode();
                     self.statistics.frames_received += 1;
                     return Ok(Some(encoded));
@@ -373,7 +964,9 @@ impl SpaceCANAdapter {
             FramePriority::Low => 100.0,
         };
 
-        for (_channel_id, channel) in &self.channels {
+        let mut channel_ids: Vec<u8> = self.channels.keys().copied().collect();
+        channel_ids.sort_unstable();
+        for channel in channel_ids.iter().filter_map(|id| self.channels.get(id)) {
             if channel.is_active {
                 let channel_range = match channel.power_mode {
                     PowerMode::HighPower => 2000.0,
@@ -383,7 +976,7 @@ impl SpaceCANAdapter {
                 };
 
                 if channel_range >= required_range {
-                    return Ok(0); // Return a default channel ID since we can't access the actual ID
+                    return Ok(channel.channel_id);
                 }
             }
         }
@@ -395,6 +988,62 @@ impl SpaceCANAdapter {
         &self.statistics
     }
 
+    pub fn set_channel_modulation(&mut self, channel_id: u8, modulation: Modulation, coding_rate: CodingRate) -> Result<(), String> {
+        let channel = self.channels.get_mut(&channel_id)
+            .ok_or(format!("Channel {} not found", channel_id))?;
+        channel.modulation = modulation;
+        channel.coding_rate = coding_rate;
+        info!("Channel {} set to {:?} with {:?} coding", channel_id, modulation, coding_rate);
+        Ok(())
+    }
+
+    pub fn get_channel(&self, channel_id: u8) -> Option<&SpaceCANChannel> {
+        self.channels.get(&channel_id)
+    }
+
+    /// Configure a channel's frequency-hopping sequence, starting on its first entry
+    pub fn set_channel_hop_sequence(&mut self, channel_id: u8, hop_sequence: Vec<f64>, hop_interval: Duration) -> Result<(), String> {
+        if hop_interval <= Duration::zero() {
+            return Err("Hop interval must be positive".to_string());
+        }
+        let channel = self.channels.get_mut(&channel_id)
+            .ok_or(format!("Channel {} not found", channel_id))?;
+        if let Some(&first) = hop_sequence.first() {
+            channel.frequency = first;
+        }
+        channel.hop_sequence = hop_sequence;
+        channel.hop_interval = hop_interval;
+        channel.hop_index = 0;
+        channel.last_hop = None;
+        Ok(())
+    }
+
+    /// Advance every active hopping channel whose interval has elapsed, avoiding jammed bands.
+    /// Returns the number of channels that changed frequency.
+    pub fn tick_frequency_hopping(&mut self, now: DateTime<Utc>, jammed_bands: &[FrequencyBand]) -> usize {
+        let mut hopped = 0;
+        for channel in self.channels.values_mut() {
+            if !channel.is_active || channel.hop_sequence.is_empty() {
+                continue;
+            }
+            let Some(last_hop) = channel.last_hop else {
+                channel.last_hop = Some(now);
+                continue;
+            };
+            if now - last_hop < channel.hop_interval {
+                continue;
+            }
+            channel.last_hop = Some(now);
+            if channel.advance_hop(jammed_bands) {
+                debug!("Channel {} hopped to {} MHz", channel.channel_id, channel.frequency);
+                hopped += 1;
+            } else {
+                warn!("Channel {} has no unjammed frequency in its hop sequence", channel.channel_id);
+            }
+        }
+        hopped
+    }
+
     pub fn set_channel_power_mode(&mut self, channel_id: u8, power_mode: PowerMode) -> Result<(), String> {
         if let Some(channel) = self.channels.get_mut(&channel_id) {
             channel.power_mode = power_mode;
@@ -429,16 +1078,229 @@ mod tests {
         assert_eq!(frame.priority, decoded.priority);
     }
 
+    #[test]
+    fn test_timestamps_keep_subsecond_ordering() {
+        let first = SpaceCANFrame::new(0x123, vec![1], FramePriority::Normal);
+        let mut second = SpaceCANFrame::new(0x123, vec![2], FramePriority::Normal);
+        second.timestamp = first.timestamp + Duration::microseconds(5);
+
+        let first_decoded = SpaceCANFrame::decode(&first.encode()).unwrap();
+        let second_decoded = SpaceCANFrame::decode(&second.encode()).unwrap();
+        assert_eq!(first_decoded.timestamp, first.timestamp);
+        assert_eq!(second_decoded.timestamp, second.timestamp);
+        assert!(first_decoded.timestamp < second_decoded.timestamp);
+
+        // Frames from an older format version are rejected
+        let mut legacy = first.encode();
+        legacy[5] &= 0x0F;
+        assert!(SpaceCANFrame::decode(&legacy).unwrap_err().contains("version"));
+    }
+
     #[test]
     fn test_error_detection() {
         let data = vec![1, 2, 3, 4, 5];
-        let mut frame = SpaceCANFrame::new(0x123, data, FramePriority::High);
+        let mut frame = SpaceCANFrame::new(0x123, data.clone(), FramePriority::High);
         
         // Corrupt data
         frame.data[0] = 255;
         
-        // Should detect error
-        assert!(frame.validate_and_correct().is_err());
+        // Should detect and repair the error
+        assert_eq!(frame.validate_and_correct(), Ok(true));
+        assert_eq!(frame.data, data);
+        
+        // An intact frame needs no correction
+        assert_eq!(frame.validate_and_correct(), Ok(false));
+    }
+
+    #[test]
+    fn test_reed_solomon_burst_error_recovery() {
+        let data: Vec<u8> = (0..200).map(|i| (i * 31 + 7) as u8).collect();
+
+        for errors in 1..=16 {
+            let mut frame = SpaceCANFrame::new(0x200, data.clone(), FramePriority::High);
+            // Contiguous burst starting mid-payload
+            for byte in frame.data.iter_mut().skip(50).take(errors) {
+                *byte ^= 0xA5;
+            }
+            assert_eq!(frame.validate_and_correct(), Ok(true), "{} errors", errors);
+            assert_eq!(frame.data, data);
+        }
+
+        for errors in [17, 24, 40] {
+            let mut frame = SpaceCANFrame::new(0x200, data.clone(), FramePriority::High);
+            for byte in frame.data.iter_mut().skip(50).take(errors) {
+                *byte ^= 0xA5;
+            }
+            assert!(frame.validate_and_correct().is_err(), "{} errors", errors);
+        }
+    }
+
+    #[test]
+    fn test_fragmentation_round_trip_out_of_order() {
+        let payload: Vec<u8> = (0..10 * 1024).map(|i| (i % 251) as u8).collect();
+        let frames = SpaceCANFrame::fragment(0x300, &payload, FramePriority::Normal, 200).unwrap();
+        assert_eq!(frames.len(), 52);
+
+        // Send over the wire, then deliver shuffled with a duplicate
+        let mut received: Vec<SpaceCANFrame> = frames.iter()
+            .map(|frame| SpaceCANFrame::decode(&frame.encode()).unwrap())
+            .collect();
+        received.reverse();
+        received.swap(3, 40);
+        received.insert(10, received[20].clone());
+
+        let mut reassembler = Reassembler::new();
+        let mut result = None;
+        for frame in received {
+            if let Some(data) = reassembler.accept(frame).unwrap() {
+                assert!(result.is_none());
+                result = Some(data);
+            }
+        }
+        assert_eq!(result.unwrap(), payload);
+        assert_eq!(reassembler.pending_messages(), 0);
+    }
+
+    #[test]
+    fn test_reassembly_single_and_missing_fragments() {
+        let mut reassembler = Reassembler::new();
+        let single = SpaceCANFrame::fragment(0x301, &[1, 2, 3], FramePriority::High, 200).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(reassembler.accept(single[0].clone()).unwrap(), Some(vec![1, 2, 3]));
+
+        let frames = SpaceCANFrame::fragment(0x302, &[7u8; 50], FramePriority::High, 20).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(reassembler.accept(frames[0].clone()).unwrap(), None);
+        assert_eq!(reassembler.accept(frames[2].clone()).unwrap(), None);
+
+        assert!(reassembler.expire(Utc::now()).is_ok());
+        let error = reassembler.expire(Utc::now() + Duration::seconds(60)).unwrap_err();
+        assert!(error.contains("[1]"), "{}", error);
+        assert_eq!(reassembler.pending_messages(), 0);
+    }
+
+    #[test]
+    fn test_decode_repairs_or_rejects_corrupted_payload() {
+        let data: Vec<u8> = (0..64).collect();
+        let frame = SpaceCANFrame::new(0x123, data.clone(), FramePriority::High);
+        let encoded = frame.encode();
+        let payload_offset = 21;
+
+        let mut repairable = encoded.clone();
+        for byte in repairable.iter_mut().skip(payload_offset + 10).take(5) {
+            *byte ^= 0x81;
+        }
+        let repaired = SpaceCANFrame::decode(&repairable).unwrap();
+        assert_eq!(repaired.data, data);
+
+        let mut hopeless = encoded.clone();
+        for byte in hopeless.iter_mut().skip(payload_offset).take(40) {
+            *byte ^= 0x81;
+        }
+        let error = SpaceCANFrame::decode(&hopeless).unwrap_err();
+        assert!(error.contains("could not be corrected"), "{}", error);
+    }
+
+    #[test]
+    fn test_arq_retransmits_after_dropped_frame() {
+        let mut sender = SpaceCANAdapter::new();
+        let mut receiver = SpaceCANAdapter::new();
+        let mut network = crate::config::RustSatConfig::default().network;
+        network.retry_backoff_ms = 20;
+        let arq = ArqConfig::from(&network);
+        assert_eq!(arq.ack_timeout, std::time::Duration::from_millis(network.connection_timeout_ms));
+        sender.set_arq_config(arq);
+        let waits = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = waits.clone();
+        sender.set_backoff_delay(move |backoff| recorded.lock().unwrap().push(backoff));
+
+        let frame = SpaceCANFrame::new(0x42, b"command".to_vec(), FramePriority::High);
+        let mut deliveries = 0;
+        let attempts = sender.transmit_reliable(&frame, |bytes| {
+            deliveries += 1;
+            if deliveries == 1 {
+                return None;  // First transmission lost in space
+            }
+            receiver.receive_reliable(bytes).ok()
+        }).unwrap();
+
+        assert_eq!(attempts, 2);
+        assert_eq!(*waits.lock().unwrap(), vec![std::time::Duration::from_millis(20)]);
+        assert_eq!(sender.get_statistics().retransmissions, 1);
+        assert_eq!(sender.get_statistics().acks_received, 1);
+        assert_eq!(receiver.receive().unwrap().map(|b| SpaceCANFrame::decode(&b).unwrap().data), Some(b"command".to_vec()));
+
+        let unacknowledged = sender.transmit_reliable(&frame, |_| None);
+        assert!(unacknowledged.is_err());
+        assert_eq!(sender.get_statistics().retransmissions, 4);
+        assert_eq!(waits.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_frequency_hopping_wraps_and_avoids_jammed_bands() {
+        let mut adapter = SpaceCANAdapter::new();
+        adapter.set_channel_hop_sequence(0, vec![435.0, 436.0, 437.0], Duration::seconds(10)).unwrap();
+        let start = Utc::now();
+
+        // First tick only starts the hop clock; ticks inside the interval do nothing
+        assert_eq!(adapter.tick_frequency_hopping(start, &[]), 0);
+        assert_eq!(adapter.tick_frequency_hopping(start + Duration::seconds(5), &[]), 0);
+
+        let mut visited = Vec::new();
+        for i in 1..=3 {
+            assert_eq!(adapter.tick_frequency_hopping(start + Duration::seconds(10 * i), &[]), 1);
+            visited.push(adapter.get_channel(0).unwrap().frequency);
+        }
+        assert_eq!(visited, vec![436.0, 437.0, 435.0]);
+
+        // From 435.0, 436.0 is jammed so the channel skips to 437.0
+        let jammed = [FrequencyBand { low_mhz: 435.9, high_mhz: 436.1 }];
+        adapter.tick_frequency_hopping(start + Duration::seconds(40), &jammed);
+        assert_eq!(adapter.get_channel(0).unwrap().frequency, 437.0);
+
+        // With every frequency jammed the channel stays put
+        let everything = [FrequencyBand { low_mhz: 400.0, high_mhz: 500.0 }];
+        assert_eq!(adapter.tick_frequency_hopping(start + Duration::seconds(50), &everything), 0);
+        assert_eq!(adapter.get_channel(0).unwrap().frequency, 437.0);
+
+        adapter.transmit(&SpaceCANFrame::new(0x123, vec![1], FramePriority::Normal)).unwrap();
+        assert_eq!(adapter.get_statistics().last_frequency_mhz, Some(437.0));
+    }
+
+    #[test]
+    fn test_duplicate_frames_are_dropped() {
+        let mut adapter = SpaceCANAdapter::new();
+        let frame = SpaceCANFrame::new(0x123, b"telemetry".to_vec(), FramePriority::Normal);
+        let encoded = frame.encode();
+
+        adapter.accept_frame(&encoded).unwrap();
+        adapter.accept_frame(&encoded).unwrap();
+        assert!(adapter.receive().unwrap().is_some());
+        assert!(adapter.receive().unwrap().is_none());
+        assert_eq!(adapter.get_statistics().duplicates_dropped, 1);
+
+        let mut fresh = SpaceCANFrame::new(0x123, b"telemetry".to_vec(), FramePriority::Normal);
+        fresh.sequence_number = frame.sequence_number.wrapping_add(1);
+        adapter.accept_frame(&fresh.encode()).unwrap();
+        assert!(adapter.receive().unwrap().is_some());
+        assert_eq!(adapter.get_statistics().frames_received, 2);
+    }
+
+    #[test]
+    fn test_dedup_window_evicts_oldest() {
+        let mut window = DedupWindow::new(2);
+        let mut frames: Vec<SpaceCANFrame> = (0..3)
+            .map(|_| SpaceCANFrame::new(0x42, vec![0], FramePriority::Low))
+            .collect();
+        for (i, frame) in frames.iter_mut().enumerate() {
+            frame.sequence_number = i as u16;
+        }
+
+        assert!(frames.iter().all(|frame| window.insert(frame)));
+        assert_eq!(window.len(), 2);
+        // Frame 0 was evicted, frame 2 is still remembered
+        assert!(window.insert(&frames[0]));
+        assert!(!window.insert(&frames[2]));
     }
 
     #[test]
@@ -459,4 +1321,56 @@ mod tests {
         assert!(adapter.channels.contains_key(&2));
         assert!(adapter.set_channel_power_mode(2, PowerMode::HighPower).is_ok());
     }
+
+    #[test]
+    fn test_frame_encryption_between_adapters() {
+        let mut crypto = CryptoModule::new();
+        crypto.initialize_keys().unwrap();
+
+        let mut sender = SpaceCANAdapter::new();
+        sender.enable_frame_encryption(crypto.clone());
+        let mut receiver = SpaceCANAdapter::new();
+        receiver.enable_frame_encryption(crypto);
+
+        let plaintext = b"frame level secret".to_vec();
+        let frame = SpaceCANFrame::new(0x42, plaintext.clone(), FramePriority::Normal);
+        let wire = sender.transmit_encoded(&frame).unwrap();
+
+        let on_air = SpaceCANFrame::decode(&wire).unwrap();
+        assert!(on_air.encrypted);
+        assert_ne!(on_air.data, plaintext);
+
+        receiver.accept_frame(&wire).unwrap();
+        let received = SpaceCANFrame::decode(&receiver.receive().unwrap().unwrap()).unwrap();
+        assert!(!received.encrypted);
+        assert_eq!(received.data, plaintext);
+
+        // A peer without the frame key must not silently accept ciphertext
+        assert!(SpaceCANAdapter::new().accept_frame(&wire).is_err());
+
+        // The largest encryptable payload still round-trips; one byte more would overflow the length field
+        let largest = vec![0x5A; MAX_ENCRYPTABLE_PAYLOAD];
+        let wire = sender.transmit_encoded(&SpaceCANFrame::new(0x42, largest.clone(), FramePriority::Normal)).unwrap();
+        receiver.accept_frame(&wire).unwrap();
+        assert_eq!(SpaceCANFrame::decode(&receiver.receive().unwrap().unwrap()).unwrap().data, largest);
+        let oversized = SpaceCANFrame::new(0x42, vec![0x5A; 228], FramePriority::Normal);
+        assert!(sender.transmit_encoded(&oversized).unwrap_err().contains("once encrypted"));
+        assert!(SpaceCANFrame::fragment(0x42, &[0; 300], FramePriority::Normal, 228).is_err());
+    }
+
+    #[test]
+    fn test_channel_modulation_tradeoff() {
+        let mut adapter = SpaceCANAdapter::new();
+        adapter.add_channel(2, 2400.0, 100.0);
+        adapter.add_channel(3, 2400.0, 100.0);
+        adapter.set_channel_modulation(2, Modulation::Bpsk, CodingRate::Half).unwrap();
+        adapter.set_channel_modulation(3, Modulation::Qam16, CodingRate::SevenEighths).unwrap();
+
+        let robust = adapter.get_channel(2).unwrap();
+        let fast = adapter.get_channel(3).unwrap();
+
+        assert!(fast.effective_data_rate_bps() > robust.effective_data_rate_bps());
+        assert!(fast.bit_error_rate(8.0) > robust.bit_error_rate(8.0));
+        assert!(robust.bit_error_rate(20.0) < robust.bit_error_rate(0.0));
+    }
 }
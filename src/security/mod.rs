@@ -1,12 +1,19 @@
 // Security and cryptographic communication module for CubeSat communications
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
+use hmac::{Hmac, Mac};
+use aes_gcm::{Aes256Gcm, Nonce};
+use aes_gcm::aead::Aead;
+use subtle::ConstantTimeEq;
+use hkdf::Hkdf;
+use x25519_dalek::{PublicKey, StaticSecret};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc, Duration};
 use log::{info, warn};
+use crate::config::SecurityConfig;
 use rand::RngCore;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Permission {
     Telemetry,
     Command,
@@ -14,12 +21,71 @@ pub enum Permission {
     Admin,
 }
 
+type HmacSha256 = Hmac<Sha256>;
+
+/// Length of the HMAC-SHA256 tag appended to authenticated messages
+pub const AUTH_TAG_LEN: usize = 32;
+
+/// Per-message protection applied by `CryptoModule::protect_message`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageProtection {
+    Encrypted = 0x01,
+    AuthenticateOnly = 0x02,
+}
+
+/// Length of the random AES-GCM nonce prepended to every ciphertext
+pub const NONCE_LEN: usize = 12;
+/// Length of the AES-GCM authentication tag appended to every ciphertext
+pub const GCM_TAG_LEN: usize = 16;
+
+/// HKDF context string binding derived keys to satellite-ground sessions
+const SESSION_KEY_INFO: &[u8] = b"rustsat-esa session key v1";
+
+/// Default maximum age (or clock skew) of a secure message timestamp
+pub const DEFAULT_REPLAY_WINDOW_SECS: i64 = 300;
+/// Secure message header: from, to, key epoch, counter, timestamp (ms) and signature length
+const SECURE_HEADER_LEN: usize = 32;
+/// How long keys retired by a rotation still verify in-flight messages by default
+pub const DEFAULT_KEY_GRACE_PERIOD_SECS: i64 = 600;
+
+/// Length of the truncated HMAC-SHA256 tag on emergency messages
+pub const EMERGENCY_TAG_LEN: usize = 8;
+
+/// Length of the CRC-16 trailer on emergency messages
+pub const EMERGENCY_CRC_LEN: usize = 2;
+
+/// Keys retired by the last rotation, kept until the grace period ends
+#[derive(Debug, Clone)]
+struct RetiredKeys {
+    epoch: u32,
+    encryption_key: Vec<u8>,
+    signing_key: Vec<u8>,
+    retired_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone)]
 pub struct CryptoModule {
     encryption_key: Vec<u8>,
     signing_key: Vec<u8>,
-    auth_tokens: HashMap<u32, (String, DateTime<Utc>)>,
+    auth_tokens: HashMap<u32, (String, DateTime<Utc>, Vec<Permission>)>,
     session_keys: HashMap<u32, Vec<u8>>,
+    pending_sessions: HashMap<u32, [u8; 32]>,
+    emergency_crc_enabled: bool,
+    emergency_bypass_enabled: bool,
+    send_counters: HashMap<u32, u64>,
+    last_seen_counters: HashMap<u32, u64>,
+    replay_window: Duration,
+    key_epoch: u32,
+    keys_generated_at: DateTime<Utc>,
+    key_rotation_interval: Duration,
+    key_grace_period: Duration,
+    retired_keys: Option<RetiredKeys>,
+}
+
+impl Default for CryptoModule {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CryptoModule {
@@ -29,6 +95,17 @@ impl CryptoModule {
             signing_key: vec![0u8; 32],
             auth_tokens: HashMap::new(),
             session_keys: HashMap::new(),
+            pending_sessions: HashMap::new(),
+            emergency_crc_enabled: true,
+            emergency_bypass_enabled: false,
+            send_counters: HashMap::new(),
+            last_seen_counters: HashMap::new(),
+            replay_window: Duration::seconds(DEFAULT_REPLAY_WINDOW_SECS),
+            key_epoch: 0,
+            keys_generated_at: Utc::now(),
+            key_rotation_interval: Duration::hours(24),
+            key_grace_period: Duration::seconds(DEFAULT_KEY_GRACE_PERIOD_SECS),
+            retired_keys: None,
         }
     }
 
@@ -36,68 +113,218 @@ impl CryptoModule {
         // Generate random keys
         rand::thread_rng().fill_bytes(&mut self.encryption_key);
         rand::thread_rng().fill_bytes(&mut self.signing_key);
+        self.keys_generated_at = Utc::now();
         
         info!("Cryptographic keys initialized");
         Ok(())
     }
 
-    /// Simple XOR encryption for demonstration
-    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
-        let mut encrypted = data.to_vec();
-        
-        for (i, byte) in encrypted.iter_mut().enumerate() {
-            *byte ^= self.encryption_key[i % self.encryption_key.len()];
+    /// Epoch of the keys currently used to protect outgoing messages
+    pub fn key_epoch(&self) -> u32 {
+        self.key_epoch
+    }
+
+    /// Set how long keys retired by a rotation keep verifying in-flight messages
+    pub fn set_key_grace_period(&mut self, grace_period: Duration) {
+        self.key_grace_period = grace_period;
+    }
+
+    /// Replace the keys with fresh ones, keeping the old pair for the grace period
+    pub fn rotate_keys(&mut self, now: DateTime<Utc>) {
+        let mut encryption_key = vec![0u8; 32];
+        let mut signing_key = vec![0u8; 32];
+        rand::thread_rng().fill_bytes(&mut encryption_key);
+        rand::thread_rng().fill_bytes(&mut signing_key);
+
+        self.retired_keys = Some(RetiredKeys {
+            epoch: self.key_epoch,
+            encryption_key: std::mem::replace(&mut self.encryption_key, encryption_key),
+            signing_key: std::mem::replace(&mut self.signing_key, signing_key),
+            retired_at: now,
+        });
+        self.key_epoch = self.key_epoch.wrapping_add(1);
+        self.keys_generated_at = now;
+        info!("Rotated keys to epoch {}", self.key_epoch);
+    }
+
+    /// Rotate once the configured interval has passed and forget retired keys past their grace period
+    pub fn rotate_keys_if_due(&mut self, now: DateTime<Utc>) -> bool {
+        if self.retired_keys.as_ref().is_some_and(|retired| now - retired.retired_at > self.key_grace_period) {
+            self.retired_keys = None;
+        }
+        if now - self.keys_generated_at < self.key_rotation_interval {
+            return false;
+        }
+        self.rotate_keys(now);
+        true
+    }
+
+    /// Encryption and signing keys for an epoch, if they are current or still in their grace period
+    fn keys_for_epoch(&self, epoch: u32, now: DateTime<Utc>) -> Result<(&[u8], &[u8]), String> {
+        if epoch == self.key_epoch {
+            return Ok((&self.encryption_key, &self.signing_key));
+        }
+        match &self.retired_keys {
+            Some(retired) if retired.epoch == epoch && now - retired.retired_at <= self.key_grace_period => {
+                Ok((&retired.encryption_key, &retired.signing_key))
+            }
+            _ => Err(format!("No valid keys for epoch {} (current epoch {})", epoch, self.key_epoch)),
         }
-        
-        Ok(encrypted)
     }
 
-    /// Simple XOR decryption for demonstration
+    /// Current encryption and signing keys, followed by the retired pair while it is in its grace period
+    fn valid_keys(&self, now: DateTime<Utc>) -> Vec<(&[u8], &[u8])> {
+        let mut keys = vec![(self.encryption_key.as_slice(), self.signing_key.as_slice())];
+        if let Some(retired) = &self.retired_keys {
+            if now - retired.retired_at <= self.key_grace_period {
+                keys.push((&retired.encryption_key, &retired.signing_key));
+            }
+        }
+        keys
+    }
+
+    /// AES-256-GCM encryption under a fresh random nonce, returned as nonce || ciphertext || tag
+    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        aes_gcm_seal(&self.encryption_key, data)
+    }
+
+    /// AES-256-GCM decryption, failing if the authentication tag does not verify under any valid key
     pub fn decrypt(&self, encrypted_data: &[u8]) -> Result<Vec<u8>, String> {
-        let mut decrypted = encrypted_data.to_vec();
-        
-        for (i, byte) in decrypted.iter_mut().enumerate() {
-            *byte ^= self.encryption_key[i % self.encryption_key.len()];
+        let mut result = Err("No valid keys".to_string());
+        for (encryption_key, _) in self.valid_keys(Utc::now()) {
+            result = aes_gcm_open(encryption_key, encrypted_data);
+            if result.is_ok() {
+                break;
+            }
         }
-        
-        Ok(decrypted)
+        result
+    }
+
+    /// Start an X25519 key exchange with a peer, returning our public key to send to it
+    pub fn begin_session(&mut self, peer_id: u32) -> [u8; 32] {
+        let secret = StaticSecret::random_from_rng(rand::thread_rng());
+        let public = PublicKey::from(&secret);
+        self.pending_sessions.insert(peer_id, secret.to_bytes());
+        public.to_bytes()
+    }
+
+    /// Finish the key exchange with the peer's public key, deriving the session key with HKDF
+    pub fn complete_session(&mut self, peer_id: u32, peer_public: &[u8; 32]) -> Result<(), String> {
+        let secret = self.pending_sessions.remove(&peer_id)
+            .map(StaticSecret::from)
+            .ok_or(format!("No session handshake in progress with node {}", peer_id))?;
+        let shared = secret.diffie_hellman(&PublicKey::from(*peer_public));
+        if !shared.was_contributory() {
+            return Err(format!("Node {} sent a low-order public key", peer_id));
+        }
+
+        let mut session_key = vec![0u8; 32];
+        Hkdf::<Sha256>::new(None, shared.as_bytes())
+            .expand(SESSION_KEY_INFO, &mut session_key)
+            .map_err(|e| format!("Session key derivation failed: {}", e))?;
+        self.session_keys.insert(peer_id, session_key);
+        info!("Established session key with node {}", peer_id);
+        Ok(())
+    }
+
+    /// Whether a session key has been established with a peer
+    pub fn has_session(&self, peer_id: u32) -> bool {
+        self.session_keys.contains_key(&peer_id)
+    }
+
+    /// AES-256-GCM encryption under the session key shared with a peer
+    pub fn encrypt_session(&self, peer_id: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+        aes_gcm_seal(self.session_key(peer_id)?, data)
+    }
+
+    /// AES-256-GCM decryption under the session key shared with a peer
+    pub fn decrypt_session(&self, peer_id: u32, encrypted_data: &[u8]) -> Result<Vec<u8>, String> {
+        aes_gcm_open(self.session_key(peer_id)?, encrypted_data)
+    }
+
+    fn session_key(&self, peer_id: u32) -> Result<&[u8], String> {
+        self.session_keys.get(&peer_id)
+            .map(Vec::as_slice)
+            .ok_or(format!("No session established with node {}", peer_id))
     }
 
-    pub fn create_secure_message(&self, from: u32, to: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+    /// Encrypt and sign a message, stamping it with the next counter for `from` and the current time
+    pub fn create_secure_message(&mut self, from: u32, to: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+        let counter = self.send_counters.entry(from).or_insert(0);
+        *counter += 1;
+        let counter = *counter;
+
         let encrypted = self.encrypt(data)?;
-        let signature = self.sign_data(&encrypted)?;
-        
         let mut message = Vec::new();
         message.extend_from_slice(&from.to_be_bytes());
         message.extend_from_slice(&to.to_be_bytes());
+        message.extend_from_slice(&self.key_epoch.to_be_bytes());
+        message.extend_from_slice(&counter.to_be_bytes());
+        message.extend_from_slice(&Utc::now().timestamp_millis().to_be_bytes());
+
+        let mut signed = message.clone();
+        signed.extend_from_slice(&encrypted);
+        let signature = self.hmac_tag(&signed)?;
+
         message.extend_from_slice(&(signature.len() as u32).to_be_bytes());
         message.extend_from_slice(&signature);
         message.extend_from_slice(&encrypted);
-        
+
         Ok(message)
     }
 
-    pub fn verify_and_decrypt(&self, message: &[u8]) -> Result<Vec<u8>, String> {
-        if message.len() < 12 {
+    pub fn verify_and_decrypt(&mut self, message: &[u8]) -> Result<Vec<u8>, String> {
+        self.verify_and_decrypt_at(message, Utc::now())
+    }
+
+    /// Verify and decrypt a secure message received at `now`, rejecting replays and stale messages
+    pub fn verify_and_decrypt_at(&mut self, message: &[u8], now: DateTime<Utc>) -> Result<Vec<u8>, String> {
+        if message.len() < SECURE_HEADER_LEN {
             return Err("Message too short".to_string());
         }
-        
-        let sig_len = u32::from_be_bytes([message[8], message[9], message[10], message[11]]) as usize;
-        
-        if message.len() < 12 + sig_len {
+
+        let from = u32::from_be_bytes([message[0], message[1], message[2], message[3]]);
+        let epoch = u32::from_be_bytes([message[8], message[9], message[10], message[11]]);
+        let mut counter_bytes = [0u8; 8];
+        counter_bytes.copy_from_slice(&message[12..20]);
+        let counter = u64::from_be_bytes(counter_bytes);
+        let mut timestamp_bytes = [0u8; 8];
+        timestamp_bytes.copy_from_slice(&message[20..28]);
+        let timestamp = DateTime::from_timestamp_millis(i64::from_be_bytes(timestamp_bytes))
+            .ok_or("Invalid message timestamp")?;
+        let sig_len = u32::from_be_bytes([message[28], message[29], message[30], message[31]]) as usize;
+
+        if message.len() < SECURE_HEADER_LEN + sig_len {
             return Err("Invalid message format".to_string());
         }
-        
-        let signature = &message[12..12 + sig_len];
-        let encrypted_data = &message[12 + sig_len..];
-        
-        // Verify signature (simplified)
-        let expected_sig = self.sign_data(encrypted_data)?;
-        if signature != expected_sig {
+
+        let signature = &message[SECURE_HEADER_LEN..SECURE_HEADER_LEN + sig_len];
+        let encrypted_data = &message[SECURE_HEADER_LEN + sig_len..];
+
+        // Verify the HMAC over the header fields and ciphertext in constant time
+        let (encryption_key, signing_key) = self.keys_for_epoch(epoch, now)?;
+        let mut signed = message[..SECURE_HEADER_LEN - 4].to_vec();
+        signed.extend_from_slice(encrypted_data);
+        let expected_sig = hmac_sha256(signing_key, &signed)?;
+        if !bool::from(signature.ct_eq(&expected_sig)) {
             return Err("Signature verification failed".to_string());
         }
-        
-        self.decrypt(encrypted_data)
+
+        if (now - timestamp).num_milliseconds().abs() > self.replay_window.num_milliseconds() {
+            return Err(format!("Message from node {} is outside the acceptance window", from));
+        }
+        if self.last_seen_counters.get(&from).is_some_and(|&last| counter <= last) {
+            return Err(format!("Replayed message from node {} (counter {})", from, counter));
+        }
+
+        let plaintext = aes_gcm_open(encryption_key, encrypted_data)?;
+        self.last_seen_counters.insert(from, counter);
+        Ok(plaintext)
+    }
+
+    /// Set how far a secure message timestamp may differ from the receive time
+    pub fn set_replay_window(&mut self, window: Duration) {
+        self.replay_window = window;
     }
 
     pub fn generate_auth_token(&mut self, node_id: u32, permissions: Vec<Permission>) -> Result<String, String> {
@@ -105,44 +332,418 @@ impl CryptoModule {
         let token_hash = format!("{:x}", Sha256::digest(token_data.as_bytes()));
         
         let expiry = Utc::now() + Duration::hours(24);
-        self.auth_tokens.insert(node_id, (token_hash.clone(), expiry));
+        self.auth_tokens.insert(node_id, (token_hash.clone(), expiry, permissions));
         
         Ok(token_hash)
     }
 
+    /// Check that a node's token is current and was granted `required_permission`
     pub fn verify_auth_token(&self, node_id: u32, token: &str, required_permission: Permission) -> Result<bool, String> {
-        if let Some((stored_token, expiry)) = self.auth_tokens.get(&node_id) {
+        if let Some((stored_token, expiry, permissions)) = self.auth_tokens.get(&node_id) {
             if Utc::now() > *expiry {
                 return Ok(false);
             }
-            Ok(stored_token == token)
+            Ok(bool::from(stored_token.as_bytes().ct_eq(token.as_bytes())) && permissions.contains(&required_permission))
         } else {
             Ok(false)
         }
     }
 
+    /// Enable or disable CRC checking of received emergency messages
+    pub fn set_emergency_crc_verification(&mut self, enabled: bool) {
+        self.emergency_crc_enabled = enabled;
+    }
+
+    /// Tolerate emergency messages whose HMAC does not verify, e.g. from a node that lost its keys
+    pub fn set_emergency_bypass(&mut self, enabled: bool) {
+        self.emergency_bypass_enabled = enabled;
+    }
+
+    /// Apply the emergency-handling switches and key rotation interval from the security configuration
+    pub fn apply_config(&mut self, config: &SecurityConfig) {
+        self.emergency_crc_enabled = config.emergency_crc_enabled;
+        self.emergency_bypass_enabled = config.emergency_bypass_enabled;
+        self.key_rotation_interval = Duration::hours(config.key_rotation_interval_hours as i64);
+    }
+
+    /// Frame an emergency as `EMERGENCY || node_id || data || truncated HMAC || CRC-16`
     pub fn create_emergency_message(&self, node_id: u32, data: &[u8]) -> Result<Vec<u8>, String> {
-        // Emergency messages use simplified encryption
         let mut message = Vec::new();
         message.extend_from_slice(b"EMERGENCY");
         message.extend_from_slice(&node_id.to_be_bytes());
         message.extend_from_slice(data);
+        let tag = self.hmac_tag(&message)?;
+        message.extend_from_slice(&tag[..EMERGENCY_TAG_LEN]);
+        let crc = crc16_ccitt(&message);
+        message.extend_from_slice(&crc.to_be_bytes());
         
         Ok(message)
     }
 
     pub fn verify_emergency_message(&self, message: &[u8]) -> Result<Vec<u8>, String> {
-        if message.len() < 13 || &message[0..9] != b"EMERGENCY" {
+        if message.len() < 13 + EMERGENCY_TAG_LEN + EMERGENCY_CRC_LEN || &message[0..9] != b"EMERGENCY" {
             return Err("Not an emergency message".to_string());
         }
         
-        Ok(message[13..].to_vec())
+        let (body, trailer) = message.split_at(message.len() - EMERGENCY_CRC_LEN);
+        if self.emergency_crc_enabled && crc16_ccitt(body).to_be_bytes() != trailer {
+            return Err("Emergency message CRC mismatch".to_string());
+        }
+
+        let (signed, tag) = body.split_at(body.len() - EMERGENCY_TAG_LEN);
+        let mut authenticated = false;
+        for (_, signing_key) in self.valid_keys(Utc::now()) {
+            let expected = hmac_sha256(signing_key, signed)?;
+            authenticated |= bool::from(tag.ct_eq(&expected[..EMERGENCY_TAG_LEN]));
+        }
+        if !authenticated {
+            if !self.emergency_bypass_enabled {
+                return Err("Emergency message authentication failed".to_string());
+            }
+            warn!("Accepting unauthenticated emergency message under emergency bypass");
+        }
+        
+        Ok(signed[13..].to_vec())
+    }
+
+    /// Protect a message, either encrypting it or only appending an HMAC to the plaintext
+    pub fn protect_message(&self, data: &[u8], protection: MessageProtection) -> Result<Vec<u8>, String> {
+        let mut message = vec![protection as u8];
+        match protection {
+            MessageProtection::Encrypted => message.extend_from_slice(&self.encrypt(data)?),
+            MessageProtection::AuthenticateOnly => message.extend_from_slice(data),
+        }
+
+        let tag = self.hmac_tag(&message)?;
+        message.extend_from_slice(&tag);
+        Ok(message)
+    }
+
+    /// Verify a protected message and return its plaintext
+    pub fn unprotect_message(&self, message: &[u8]) -> Result<Vec<u8>, String> {
+        if message.len() < 1 + AUTH_TAG_LEN {
+            return Err("Message too short".to_string());
+        }
+
+        let (body, tag) = message.split_at(message.len() - AUTH_TAG_LEN);
+        let valid_keys = self.valid_keys(Utc::now());
+        let mut encryption_key = None;
+        for (key, signing_key) in valid_keys {
+            let mut mac = HmacSha256::new_from_slice(signing_key)
+                .map_err(|e| format!("Invalid signing key: {}", e))?;
+            mac.update(body);
+            if mac.verify_slice(tag).is_ok() {
+                encryption_key = Some(key);
+                break;
+            }
+        }
+        let encryption_key = encryption_key.ok_or("Message authentication failed".to_string())?;
+
+        match body[0] {
+            0x01 => aes_gcm_open(encryption_key, &body[1..]),
+            0x02 => Ok(body[1..].to_vec()),
+            other => Err(format!("Unknown message protection {:#04x}", other)),
+        }
+    }
+
+    /// Read the payload of an authenticate-only message without verifying it
+    pub fn authenticated_payload(message: &[u8]) -> Result<&[u8], String> {
+        if message.len() < 1 + AUTH_TAG_LEN || message[0] != MessageProtection::AuthenticateOnly as u8 {
+            return Err("Not an authenticate-only message".to_string());
+        }
+        Ok(&message[1..message.len() - AUTH_TAG_LEN])
+    }
+
+    fn hmac_tag(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        hmac_sha256(&self.signing_key, data)
+    }
+
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut mac = HmacSha256::new_from_slice(key)
+        .map_err(|e| format!("Invalid signing key: {}", e))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn aes_gcm_cipher(key: &[u8]) -> Result<Aes256Gcm, String> {
+    <Aes256Gcm as aes_gcm::KeyInit>::new_from_slice(key)
+        .map_err(|_| format!("Encryption key must be 32 bytes, got {}", key.len()))
+}
+
+fn aes_gcm_seal(key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let ciphertext = aes_gcm_cipher(key)?
+        .encrypt(Nonce::from_slice(&nonce), data)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut encrypted = nonce.to_vec();
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+fn aes_gcm_open(key: &[u8], encrypted_data: &[u8]) -> Result<Vec<u8>, String> {
+    if encrypted_data.len() < NONCE_LEN + GCM_TAG_LEN {
+        return Err("Ciphertext too short".to_string());
+    }
+
+    let (nonce, ciphertext) = encrypted_data.split_at(NONCE_LEN);
+    aes_gcm_cipher(key)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed: ciphertext authentication failed".to_string())
+}
+
+/// CRC-16/CCITT-FALSE, cheap enough for time-critical emergency traffic
+fn crc16_ccitt(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aes_gcm_round_trip() {
+        let mut crypto = CryptoModule::new();
+        crypto.initialize_keys().unwrap();
+        let plaintext = b"BEACON:1;PWR:100.0;TEMP:20.0;HEALTH:1.00;UPTIME:0";
+
+        let encrypted = crypto.encrypt(plaintext).unwrap();
+        assert_eq!(encrypted.len(), NONCE_LEN + plaintext.len() + GCM_TAG_LEN);
+        assert_eq!(crypto.decrypt(&encrypted).unwrap(), plaintext);
+        assert_eq!(crypto.decrypt(&crypto.encrypt(b"").unwrap()).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_aes_gcm_detects_tampering() {
+        let crypto = CryptoModule::new();
+        let encrypted = crypto.encrypt(b"ATTITUDE:0,0,90").unwrap();
+
+        for index in [0, NONCE_LEN, encrypted.len() - 1] {
+            let mut tampered = encrypted.clone();
+            tampered[index] ^= 0x01;
+            assert!(crypto.decrypt(&tampered).is_err());
+        }
+        assert!(crypto.decrypt(&encrypted[..NONCE_LEN + GCM_TAG_LEN - 1]).is_err());
+
+        let mut other = CryptoModule::new();
+        other.initialize_keys().unwrap();
+        assert!(other.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_aes_gcm_uses_fresh_nonces() {
+        let crypto = CryptoModule::new();
+        let first = crypto.encrypt(b"same plaintext").unwrap();
+        let second = crypto.encrypt(b"same plaintext").unwrap();
+        assert_ne!(first, second);
+        assert_ne!(first[..NONCE_LEN], second[..NONCE_LEN]);
+    }
+
+    #[test]
+    fn test_secure_message_replay_protection() {
+        let mut crypto = CryptoModule::new();
+        crypto.initialize_keys().unwrap();
+
+        let first = crypto.create_secure_message(1, 2, b"first").unwrap();
+        let second = crypto.create_secure_message(1, 2, b"second").unwrap();
+        assert_eq!(crypto.verify_and_decrypt(&first).unwrap(), b"first");
+        assert!(crypto.verify_and_decrypt(&first).unwrap_err().contains("Replayed"));
+        assert_eq!(crypto.verify_and_decrypt(&second).unwrap(), b"second");
+        assert!(crypto.verify_and_decrypt(&first).is_err());
+
+        let other_sender = crypto.create_secure_message(7, 2, b"other").unwrap();
+        assert_eq!(crypto.verify_and_decrypt(&other_sender).unwrap(), b"other");
+
+        let stale = crypto.create_secure_message(1, 2, b"stale").unwrap();
+        let later = Utc::now() + Duration::seconds(DEFAULT_REPLAY_WINDOW_SECS + 60);
+        assert!(crypto.verify_and_decrypt_at(&stale, later).unwrap_err().contains("acceptance window"));
+
+        let mut forged = crypto.create_secure_message(1, 2, b"forged").unwrap();
+        forged[15] = forged[15].wrapping_add(100);
+        assert!(crypto.verify_and_decrypt(&forged).unwrap_err().contains("Signature"));
+    }
+
+    #[test]
+    fn test_secure_message_signature_is_hmac() {
+        let mut crypto = CryptoModule::new();
+        crypto.initialize_keys().unwrap();
+        let message = crypto.create_secure_message(1, 2, b"payload").unwrap();
+        let sig_len = u32::from_be_bytes([message[28], message[29], message[30], message[31]]) as usize;
+        assert_eq!(sig_len, AUTH_TAG_LEN);
+
+        let mut signed = message[..SECURE_HEADER_LEN - 4].to_vec();
+        signed.extend_from_slice(&message[SECURE_HEADER_LEN + sig_len..]);
+        assert_eq!(&message[SECURE_HEADER_LEN..SECURE_HEADER_LEN + sig_len], crypto.hmac_tag(&signed).unwrap());
+
+        let mut tampered = message.clone();
+        tampered[SECURE_HEADER_LEN + 5] ^= 0x80;
+        assert!(crypto.verify_and_decrypt(&tampered).unwrap_err().contains("Signature"));
+
+        // A shortened signature that matches the tag prefix must not be accepted
+        for kept in [0, 1, AUTH_TAG_LEN - 1] {
+            let mut truncated = message[..SECURE_HEADER_LEN - 4].to_vec();
+            truncated.extend_from_slice(&(kept as u32).to_be_bytes());
+            truncated.extend_from_slice(&message[SECURE_HEADER_LEN..SECURE_HEADER_LEN + kept]);
+            truncated.extend_from_slice(&message[SECURE_HEADER_LEN + sig_len..]);
+            assert!(crypto.verify_and_decrypt(&truncated).unwrap_err().contains("Signature"));
+        }
+        assert_eq!(crypto.verify_and_decrypt(&message).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn test_auth_token_permissions() {
+        let mut crypto = CryptoModule::new();
+        let token = crypto.generate_auth_token(4, vec![Permission::Telemetry]).unwrap();
+
+        assert!(crypto.verify_auth_token(4, &token, Permission::Telemetry).unwrap());
+        assert!(!crypto.verify_auth_token(4, &token, Permission::Command).unwrap());
+        assert!(!crypto.verify_auth_token(4, &token, Permission::Admin).unwrap());
+        assert!(!crypto.verify_auth_token(4, "forged", Permission::Telemetry).unwrap());
+        assert!(!crypto.verify_auth_token(5, &token, Permission::Telemetry).unwrap());
+    }
+
+    #[test]
+    fn test_session_key_handshake() {
+        let mut satellite = CryptoModule::new();
+        let mut ground = CryptoModule::new();
+        assert!(satellite.encrypt_session(100, b"early").is_err());
+
+        let satellite_public = satellite.begin_session(100);
+        let ground_public = ground.begin_session(1);
+        satellite.complete_session(100, &ground_public).unwrap();
+        ground.complete_session(1, &satellite_public).unwrap();
+        assert!(satellite.has_session(100) && ground.has_session(1));
+
+        let downlink = satellite.encrypt_session(100, b"session telemetry").unwrap();
+        assert_eq!(ground.decrypt_session(1, &downlink).unwrap(), b"session telemetry");
+        let uplink = ground.encrypt_session(1, b"session command").unwrap();
+        assert_eq!(satellite.decrypt_session(100, &uplink).unwrap(), b"session command");
+
+        // Session traffic is not readable under the static key, and handshakes are single-use
+        assert!(ground.decrypt(&downlink).is_err());
+        assert!(satellite.complete_session(100, &ground_public).is_err());
+        satellite.begin_session(100);
+        assert!(satellite.complete_session(100, &[0u8; 32]).unwrap_err().contains("low-order"));
     }
 
-    fn sign_data(&self, data: &[u8]) -> Result<Vec<u8>, String> {
-        let mut hasher = Sha256::new();
-        hasher.update(&self.signing_key);
-        hasher.update(data);
-        Ok(hasher.finalize().to_vec())
+    #[test]
+    fn test_key_rotation_grace_period() {
+        let mut crypto = CryptoModule::new();
+        crypto.initialize_keys().unwrap();
+        crypto.set_key_grace_period(Duration::seconds(60));
+        let now = Utc::now();
+        assert!(!crypto.rotate_keys_if_due(now + Duration::hours(1)));
+
+        let in_flight = crypto.create_secure_message(1, 2, b"old epoch").unwrap();
+        let late = crypto.create_secure_message(1, 2, b"too late").unwrap();
+        crypto.rotate_keys(now);
+        assert_eq!(crypto.key_epoch(), 1);
+
+        // Old-epoch messages verify during the grace window and fail once it has passed
+        assert_eq!(crypto.verify_and_decrypt_at(&in_flight, now + Duration::seconds(30)).unwrap(), b"old epoch");
+        assert!(crypto.verify_and_decrypt_at(&late, now + Duration::seconds(90)).unwrap_err().contains("epoch 0"));
+
+        let fresh = crypto.create_secure_message(1, 2, b"new epoch").unwrap();
+        assert_eq!(crypto.verify_and_decrypt_at(&fresh, now + Duration::seconds(90)).unwrap(), b"new epoch");
+
+        assert!(!crypto.rotate_keys_if_due(now + Duration::hours(23)));
+        assert!(crypto.retired_keys.is_none());
+        assert!(crypto.rotate_keys_if_due(now + Duration::hours(24)));
+        assert_eq!(crypto.key_epoch(), 2);
+    }
+
+    #[test]
+    fn test_messages_from_retired_keys_verify_during_grace_period() {
+        let mut crypto = CryptoModule::new();
+        crypto.initialize_keys().unwrap();
+        crypto.set_key_grace_period(Duration::seconds(60));
+        let ciphertext = crypto.encrypt(b"sealed").unwrap();
+        let protected = crypto.protect_message(b"protected", MessageProtection::Encrypted).unwrap();
+        let signed = crypto.protect_message(b"signed", MessageProtection::AuthenticateOnly).unwrap();
+        let emergency = crypto.create_emergency_message(7, b"LOW POWER").unwrap();
+
+        let mut expired = crypto.clone();
+        crypto.rotate_keys(Utc::now());
+        assert_eq!(crypto.decrypt(&ciphertext).unwrap(), b"sealed");
+        assert_eq!(crypto.unprotect_message(&protected).unwrap(), b"protected");
+        assert_eq!(crypto.unprotect_message(&signed).unwrap(), b"signed");
+        assert_eq!(crypto.verify_emergency_message(&emergency).unwrap(), b"LOW POWER");
+        let fresh = crypto.protect_message(b"fresh", MessageProtection::Encrypted).unwrap();
+        assert_eq!(crypto.unprotect_message(&fresh).unwrap(), b"fresh");
+
+        expired.rotate_keys(Utc::now() - Duration::seconds(90));
+        assert!(expired.decrypt(&ciphertext).is_err());
+        assert!(expired.unprotect_message(&protected).is_err());
+        assert!(expired.unprotect_message(&signed).is_err());
+        assert!(expired.verify_emergency_message(&emergency).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_authenticate_only_messages() {
+        let mut crypto = CryptoModule::new();
+        crypto.initialize_keys().unwrap();
+
+        let payload = b"housekeeping in the clear";
+        let message = crypto.protect_message(payload, MessageProtection::AuthenticateOnly).unwrap();
+
+        // Readable without any key, verifiable with one
+        assert_eq!(CryptoModule::authenticated_payload(&message).unwrap(), payload);
+        assert_eq!(crypto.unprotect_message(&message).unwrap(), payload);
+
+        let mut tampered = message.clone();
+        tampered[3] ^= 0x01;
+        assert!(crypto.unprotect_message(&tampered).is_err());
+
+        let encrypted = crypto.protect_message(payload, MessageProtection::Encrypted).unwrap();
+        assert!(CryptoModule::authenticated_payload(&encrypted).is_err());
+        assert_eq!(crypto.unprotect_message(&encrypted).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_corrupted_emergency_message_detected() {
+        let mut crypto = CryptoModule::new();
+        let message = crypto.create_emergency_message(7, b"BATTERY CRITICAL").unwrap();
+        assert_eq!(crypto.verify_emergency_message(&message).unwrap(), b"BATTERY CRITICAL");
+        assert_eq!(crc16_ccitt(b"123456789"), 0x29B1);
+
+        let mut corrupted = message.clone();
+        corrupted[21] ^= 0x20;
+        assert!(crypto.verify_emergency_message(&corrupted).unwrap_err().contains("CRC"));
+
+        crypto.set_emergency_crc_verification(false);
+        assert!(crypto.verify_emergency_message(&corrupted).unwrap_err().contains("authentication"));
+        crypto.set_emergency_bypass(true);
+        assert_eq!(crypto.verify_emergency_message(&corrupted).unwrap(), b"BATTERY cRITICAL");
+    }
+
+    #[test]
+    fn test_emergency_message_authentication() {
+        let mut crypto = CryptoModule::new();
+        crypto.initialize_keys().unwrap();
+        let message = crypto.create_emergency_message(7, b"THERMAL RUNAWAY").unwrap();
+        assert_eq!(message.len(), 13 + 15 + EMERGENCY_TAG_LEN + EMERGENCY_CRC_LEN);
+        assert_eq!(crypto.verify_emergency_message(&message).unwrap(), b"THERMAL RUNAWAY");
+
+        // A forger without the signing key can still produce a valid CRC
+        let mut forger = CryptoModule::new();
+        forger.initialize_keys().unwrap();
+        let forged = forger.create_emergency_message(7, b"DEORBIT NOW").unwrap();
+        assert!(crypto.verify_emergency_message(&forged).unwrap_err().contains("authentication"));
+
+        let mut config = crate::config::RustSatConfig::default().security;
+        config.emergency_bypass_enabled = true;
+        crypto.apply_config(&config);
+        assert_eq!(crypto.verify_emergency_message(&forged).unwrap(), b"DEORBIT NOW");
+    }
+}
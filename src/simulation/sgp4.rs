@@ -0,0 +1,246 @@
+// SGP4 propagation of two-line element sets through the `sgp4` crate in AFSPC mode (WGS-72 constants)
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use super::{gmst_rad, OrbitState, EARTH_RADIUS_KM};
+use crate::protocol::network::OrbitalPosition;
+
+/// Mean orbital elements from a two-line element set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tle {
+    pub satellite_number: u32,
+    pub epoch: DateTime<Utc>,
+    pub mean_motion_dot: f64,     // rev/day^2, halved as in the TLE
+    pub bstar: f64,               // earth radii^-1
+    pub inclination: f64,         // degrees
+    pub raan: f64,                // degrees
+    pub eccentricity: f64,        // 0-1
+    pub argument_of_perigee: f64, // degrees
+    pub mean_anomaly: f64,        // degrees
+    pub mean_motion: f64,         // rev/day
+}
+
+impl Tle {
+    /// Parse the two data lines of a TLE, validating layout and checksums
+    pub fn parse(line1: &str, line2: &str) -> Result<Self, String> {
+        let elements = ::sgp4::Elements::from_tle(None, line1.trim_end().as_bytes(), line2.trim_end().as_bytes())
+            .map_err(|e| format!("Malformed TLE: {}", e))?;
+        let satellite_number = u32::try_from(elements.norad_id)
+            .map_err(|_| format!("TLE satellite number {} is out of range", elements.norad_id))?;
+
+        Ok(Self {
+            satellite_number,
+            epoch: Utc.from_utc_datetime(&elements.datetime),
+            mean_motion_dot: elements.mean_motion_dot,
+            bstar: elements.drag_term,
+            inclination: elements.inclination,
+            raan: elements.right_ascension,
+            eccentricity: elements.eccentricity,
+            argument_of_perigee: elements.argument_of_perigee,
+            mean_anomaly: elements.mean_anomaly,
+            mean_motion: elements.mean_motion,
+        })
+    }
+
+    /// The elements in the form the `sgp4` crate propagates
+    fn elements(&self) -> ::sgp4::Elements {
+        ::sgp4::Elements {
+            object_name: None,
+            international_designator: None,
+            norad_id: self.satellite_number as u64,
+            classification: ::sgp4::Classification::Unclassified,
+            datetime: self.epoch.naive_utc(),
+            mean_motion_dot: self.mean_motion_dot,
+            mean_motion_ddot: 0.0,
+            drag_term: self.bstar,
+            element_set_number: 0,
+            inclination: self.inclination,
+            right_ascension: self.raan,
+            eccentricity: self.eccentricity,
+            argument_of_perigee: self.argument_of_perigee,
+            mean_anomaly: self.mean_anomaly,
+            mean_motion: self.mean_motion,
+            revolution_number: 0,
+            ephemeris_type: 0,
+        }
+    }
+}
+
+/// TEME position and velocity of a TLE satellite at `time`
+pub fn sgp4_state(tle: &Tle, time: DateTime<Utc>) -> Result<OrbitState, String> {
+    let elements = tle.elements();
+    let constants = ::sgp4::Constants::from_elements_afspc_compatibility_mode(&elements)
+        .map_err(|e| format!("Invalid elements for satellite {}: {}", tle.satellite_number, e))?;
+    let minutes = elements.datetime_to_minutes_since_epoch(&time.naive_utc())
+        .map_err(|e| format!("Cannot propagate satellite {} to {}: {}", tle.satellite_number, time, e))?;
+    let prediction = constants.propagate_afspc_compatibility_mode(minutes)
+        .map_err(|e| format!("SGP4 propagation of satellite {} failed: {}", tle.satellite_number, e))?;
+
+    let [x, y, z] = prediction.position;
+    let [vx, vy, vz] = prediction.velocity;
+    Ok(OrbitState {
+        position_eci: (x, y, z),
+        velocity_eci: (vx, vy, vz),
+    })
+}
+
+/// Geodetic position of a TLE satellite at `time` on the simulator's spherical Earth
+pub fn propagate_sgp4(tle: &Tle, time: DateTime<Utc>) -> Result<OrbitalPosition, String> {
+    Ok(geodetic_position(&sgp4_state(tle, time)?, time))
+}
+
+/// Geodetic position of a TEME state at `time` on the simulator's spherical Earth
+pub(crate) fn geodetic_position(state: &OrbitState, time: DateTime<Utc>) -> OrbitalPosition {
+    let (x, y, z) = state.position_eci;
+    let radius = (x * x + y * y + z * z).sqrt();
+    let longitude = (y.atan2(x) - gmst_rad(time)).to_degrees().rem_euclid(360.0);
+
+    OrbitalPosition {
+        latitude: (z / radius).asin().to_degrees(),
+        longitude: if longitude > 180.0 { longitude - 360.0 } else { longitude },
+        altitude: radius - EARTH_RADIUS_KM,
+        velocity: state.velocity_eci,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    /// Vanguard 1 elements from the SGP4 verification set (Vallado et al., AIAA 2006-6753)
+    fn vanguard() -> Tle {
+        let epoch = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap()
+            + Duration::microseconds((178.78495062 * 86400.0e6) as i64);
+        Tle {
+            satellite_number: 5,
+            epoch,
+            mean_motion_dot: 0.00000023,
+            bstar: 0.28098e-4,
+            inclination: 34.2682,
+            raan: 348.7242,
+            eccentricity: 0.1859667,
+            argument_of_perigee: 331.7664,
+            mean_anomaly: 19.3264,
+            mean_motion: 10.82419157,
+        }
+    }
+
+    const ISS_LINE1: &str = "1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927";
+    const ISS_LINE2: &str = "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537";
+
+    #[test]
+    fn test_parse_iss_tle() {
+        let tle = Tle::parse(ISS_LINE1, ISS_LINE2).unwrap();
+        assert_eq!(tle.satellite_number, 25544);
+        let expected_epoch = Utc.with_ymd_and_hms(2008, 9, 20, 0, 0, 0).unwrap()
+            + Duration::microseconds((0.51782528 * 86400.0e6) as i64);
+        assert!((tle.epoch - expected_epoch).num_microseconds().unwrap().abs() <= 1);
+        assert!((tle.mean_motion_dot + 0.00002182).abs() < 1e-15);
+        assert!((tle.bstar + 0.11606e-4).abs() < 1e-15);
+        assert_eq!(tle.inclination, 51.6416);
+        assert_eq!(tle.raan, 247.4627);
+        assert!((tle.eccentricity - 0.0006703).abs() < 1e-15);
+        assert_eq!(tle.argument_of_perigee, 130.5360);
+        assert_eq!(tle.mean_anomaly, 325.0288);
+        assert_eq!(tle.mean_motion, 15.72125391);
+
+        let vanguard_tle = Tle::parse(
+            "1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753",
+            "2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667",
+        ).unwrap();
+        let expected = vanguard();
+        assert!((vanguard_tle.epoch - expected.epoch).num_microseconds().unwrap().abs() <= 1);
+        assert_eq!(Tle { epoch: expected.epoch, ..vanguard_tle }, expected);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_tles() {
+        // Last digit altered so the checksum no longer matches
+        assert!(Tle::parse(&ISS_LINE1.replace("2927", "2928"), ISS_LINE2).unwrap_err().contains("checksum"));
+        assert!(Tle::parse(ISS_LINE2, ISS_LINE1).is_err());
+        assert!(Tle::parse(&ISS_LINE1[..60], ISS_LINE2).is_err());
+        // Inclination replaced with letters, checksum kept valid
+        let garbled = format!("{}{}", &ISS_LINE2[..8], " 5a.6416 247.4627 0006703 130.5360 325.0288 15.72125391563536");
+        assert!(Tle::parse(ISS_LINE1, &garbled).unwrap_err().contains("Malformed"));
+        let other_satellite = "2 25545  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563538";
+        assert!(Tle::parse(ISS_LINE1, other_satellite).unwrap_err().contains("NORAD mismatch"));
+    }
+
+    fn assert_close(actual: (f64, f64, f64), expected: (f64, f64, f64), tolerance: f64) {
+        let error = ((actual.0 - expected.0).powi(2) + (actual.1 - expected.1).powi(2) + (actual.2 - expected.2).powi(2)).sqrt();
+        assert!(error < tolerance, "{:?} differs from {:?} by {}", actual, expected, error);
+    }
+
+    #[test]
+    fn test_sgp4_matches_verification_vectors() {
+        let tle = vanguard();
+        let at_epoch = sgp4_state(&tle, tle.epoch).unwrap();
+        assert_close(at_epoch.position_eci, (7022.46529266, -1400.08296755, 0.03995155), 1e-3);
+        assert_close(at_epoch.velocity_eci, (1.893841015, 6.405893759, 4.534807250), 1e-6);
+
+        let later = sgp4_state(&tle, tle.epoch + Duration::minutes(360)).unwrap();
+        assert_close(later.position_eci, (-7154.03120202, -3783.17682504, -3536.19412294), 1e-3);
+        assert_close(later.velocity_eci, (4.741887409, -4.151817765, -2.093935425), 1e-6);
+    }
+
+    #[test]
+    fn test_sgp4_matches_verification_set() {
+        // Near-Earth cases from SGP4-VER.TLE with their published tcppver.out states
+        let delta_debris = Tle::parse(
+            "1 06251U 62025E   06176.82412014  .00008885  00000-0  12808-3 0  3985",
+            "2 06251  58.0579  54.0425 0030035 139.1568 221.1854 15.56387291  6774",
+        ).unwrap();
+        let at_epoch = sgp4_state(&delta_debris, delta_debris.epoch).unwrap();
+        assert_close(at_epoch.position_eci, (3988.31022699, 5498.96657235, 0.90055879), 1e-3);
+        assert_close(at_epoch.velocity_eci, (-3.290032738, 2.357652820, 6.496623475), 1e-6);
+        // Two hours on, with atmospheric drag acting through B*
+        let later = sgp4_state(&delta_debris, delta_debris.epoch + Duration::minutes(120)).unwrap();
+        assert_close(later.position_eci, (-3935.69800083, 409.10980837, 5471.33577327), 1e-3);
+
+        let cbers = Tle::parse(
+            "1 28057U 03049A   06177.78615833  .00000060  00000-0  35940-4 0  1836",
+            "2 28057  98.4283 247.6961 0000884  88.1964 271.9322 14.35478080140550",
+        ).unwrap();
+        let at_epoch = sgp4_state(&cbers, cbers.epoch).unwrap();
+        assert_close(at_epoch.position_eci, (-2715.28237486, -6619.26436889, -0.01341443), 1e-3);
+        assert_close(at_epoch.velocity_eci, (-1.008587273, 0.422782003, 7.385272942), 1e-6);
+    }
+
+    #[test]
+    fn test_sgp4_propagates_deep_space_orbits() {
+        // Deep-space cases from the same verification set: a Molniya orbit and a near-geostationary one
+        let molniya = Tle::parse(
+            "1 08195U 75081A   06176.33215444  .00000099  00000-0  11873-3 0   813",
+            "2 08195  64.1586 279.0717 6877146 264.7651  20.2257  2.00491383225656",
+        ).unwrap();
+        let at_epoch = sgp4_state(&molniya, molniya.epoch).unwrap();
+        assert_close(at_epoch.position_eci, (2349.89483350, -14785.93811562, 0.02119378), 1e-3);
+        assert_close(at_epoch.velocity_eci, (2.721488096, -3.256811655, 4.498416672), 1e-6);
+        let later = sgp4_state(&molniya, molniya.epoch + Duration::minutes(240)).unwrap();
+        assert_close(later.position_eci, (19752.78050009, -8600.07130962, 37522.72921090), 1e-3);
+
+        let geostationary = Tle::parse(
+            "1 14128U 83058A   06176.02844893 -.00000158  00000-0  10000-3 0  9627",
+            "2 14128  11.4384  35.2134 0011562  26.4582 333.5652  0.98870114 46093",
+        ).unwrap();
+        let later = sgp4_state(&geostationary, geostationary.epoch + Duration::minutes(120)).unwrap();
+        assert_close(later.position_eci, (18263.33439094, 38159.96004751, 4186.18304085), 1e-3);
+        assert_close(later.velocity_eci, (-2.744396611, 1.255583260, 0.528558932), 1e-6);
+    }
+
+    #[test]
+    fn test_propagate_sgp4_geodetic_position() {
+        let tle = vanguard();
+        let position = propagate_sgp4(&tle, tle.epoch).unwrap();
+        let radius = (7022.46529266_f64.powi(2) + 1400.08296755_f64.powi(2) + 0.03995155_f64.powi(2)).sqrt();
+        assert!((position.altitude - (radius - EARTH_RADIUS_KM)).abs() < 1e-3);
+        assert!(position.latitude.abs() < 0.01);
+        assert!((-180.0..=180.0).contains(&position.longitude));
+
+        let mut geostationary = tle.clone();
+        geostationary.eccentricity = 0.0;
+        geostationary.mean_motion = 1.0027;
+        let position = propagate_sgp4(&geostationary, tle.epoch).unwrap();
+        assert!((position.altitude - 35_786.0).abs() < 50.0, "altitude {}", position.altitude);
+    }
+}
@@ -1,8 +1,17 @@
 // Real-time telemetry processing and mission timeline synchronization
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
 use chrono::{DateTime, Utc, Duration};
 use log::{info, warn, error, debug};
+use tokio::sync::broadcast;
+
+/// Processed points buffered per live subscriber before the slowest one starts lagging
+pub const LIVE_TELEMETRY_CAPACITY: usize = 1024;
+/// Largest decoded telemetry body accepted from the link, enough for a full default buffer
+pub const MAX_DECOMPRESSED_SIZE: usize = 4 * 1024 * 1024;
 
 /// Telemetry data types for CubeSat systems
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -26,6 +35,8 @@ pub struct TelemetryData {
     pub value: TelemetryValue,
     pub quality: f64,  // 0.0 to 1.0
     pub sequence_number: u64,
+    #[serde(default)]
+    pub raw_value: Option<TelemetryValue>,  // uncalibrated sensor reading, if calibrated
 }
 
 /// Telemetry value variants
@@ -48,14 +59,216 @@ pub struct TelemetryPacket {
     pub data_points: Vec<TelemetryData>,
     pub compression_type: CompressionType,
     pub priority: u8,
+    #[serde(default)]
+    pub fragment_index: u16,
+    #[serde(default = "default_fragment_total")]
+    pub fragment_total: u16,
+    #[serde(default)]
+    pub compressed_body: Vec<u8>,  // data points packed with `compression_type`, if any
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_fragment_total() -> u16 {
+    1
+}
+
+impl TelemetryPacket {
+    /// Serialize the data points and compress them with the packet's codec
+    pub fn compressed_payload(&self) -> Result<Vec<u8>, String> {
+        let json = serde_json::to_vec(&self.data_points)
+            .map_err(|e| format!("Failed to serialize data points: {}", e))?;
+        compress(&json, self.compression_type)
+    }
+
+    /// Recover the data points from a payload produced by `compressed_payload`
+    pub fn decode_payload(payload: &[u8], compression: CompressionType) -> Result<Vec<TelemetryData>, String> {
+        let json = decompress(payload, compression)?;
+        serde_json::from_slice(&json)
+            .map_err(|e| format!("Failed to parse data points: {}", e))
+    }
+
+    /// Move the data points into the compressed body for transmission, leaving a packed packet as is
+    pub fn pack_body(&mut self) -> Result<(), String> {
+        if !self.compressed_body.is_empty() {
+            return Ok(());
+        }
+        self.compressed_body = self.compressed_payload()?;
+        self.data_points.clear();
+        Ok(())
+    }
+
+    /// Data points carried by the packet, decompressing the body if it was packed
+    pub fn unpack_body(&self) -> Result<Vec<TelemetryData>, String> {
+        if self.compressed_body.is_empty() {
+            return Ok(self.data_points.clone());
+        }
+        Self::decode_payload(&self.compressed_body, self.compression_type)
+    }
+
+    /// Split into sub-packets whose JSON encoding fits within `max_bytes`
+    pub fn fragment(&self, max_bytes: usize) -> Result<Vec<TelemetryPacket>, String> {
+        if !self.compressed_body.is_empty() {
+            return Err("Cannot fragment a packet with a packed body".to_string());
+        }
+
+        // Size the header with the widest possible fragment tags
+        let header = TelemetryPacket {
+            data_points: Vec::new(),
+            fragment_index: u16::MAX,
+            fragment_total: u16::MAX,
+            ..self.clone()
+        };
+        let header_size = serde_json::to_vec(&header)
+            .map_err(|e| format!("Failed to serialize packet: {}", e))?
+            .len();
+
+        let mut groups: Vec<Vec<TelemetryData>> = Vec::new();
+        let mut current: Vec<TelemetryData> = Vec::new();
+        let mut current_size = header_size;
+
+        for point in &self.data_points {
+            let point_size = serde_json::to_vec(point)
+                .map_err(|e| format!("Failed to serialize data point: {}", e))?
+                .len();
+            if header_size + point_size > max_bytes {
+                return Err(format!("Data point {} does not fit in {} bytes", point.sequence_number, max_bytes));
+            }
+
+            let separator = if current.is_empty() { 0 } else { 1 };
+            if current_size + separator + point_size > max_bytes {
+                groups.push(std::mem::take(&mut current));
+                current_size = header_size;
+            }
+
+            current_size += point_size + if current.is_empty() { 0 } else { 1 };
+            current.push(point.clone());
+        }
+        if !current.is_empty() || groups.is_empty() {
+            groups.push(current);
+        }
+
+        let total = u16::try_from(groups.len())
+            .map_err(|_| "Too many fragments".to_string())?;
+
+        Ok(groups.into_iter().enumerate()
+            .map(|(index, data_points)| TelemetryPacket {
+                data_points,
+                fragment_index: index as u16,
+                fragment_total: total,
+                ..header.clone()
+            })
+            .collect())
+    }
+
+    /// Restore the original packet from all of its fragments
+    pub fn reassemble(fragments: &[TelemetryPacket]) -> Result<TelemetryPacket, String> {
+        let first = fragments.first().ok_or("No fragments to reassemble")?;
+        let total = first.fragment_total as usize;
+
+        let mut ordered: Vec<Option<&TelemetryPacket>> = vec![None; total];
+        for fragment in fragments {
+            if fragment.packet_id != first.packet_id || fragment.fragment_total != first.fragment_total {
+                return Err(format!("Fragment does not belong to packet {}", first.packet_id));
+            }
+            let slot = ordered.get_mut(fragment.fragment_index as usize)
+                .ok_or(format!("Fragment index {} out of range", fragment.fragment_index))?;
+            if slot.is_some() {
+                return Err(format!("Duplicate fragment {}", fragment.fragment_index));
+            }
+            *slot = Some(fragment);
+        }
+
+        if let Some(missing) = ordered.iter().position(|f| f.is_none()) {
+            return Err(format!("Missing fragment {} of {}", missing, total));
+        }
+
+        Ok(TelemetryPacket {
+            data_points: ordered.into_iter()
+                .flatten()
+                .flat_map(|f| f.data_points.iter().cloned())
+                .collect(),
+            fragment_index: 0,
+            fragment_total: 1,
+            ..first.clone()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CompressionType {
     None,
     LZ4,
     Gzip,
-    Custom,
+    Custom,  // byte-oriented run-length encoding
+}
+
+/// Compress a payload with the given codec
+pub fn compress(data: &[u8], compression: CompressionType) -> Result<Vec<u8>, String> {
+    match compression {
+        CompressionType::None => Ok(data.to_vec()),
+        CompressionType::LZ4 => Ok(lz4_flex::compress_prepend_size(data)),
+        CompressionType::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)
+                .and_then(|_| encoder.finish())
+                .map_err(|e| format!("Gzip compression failed: {}", e))
+        }
+        CompressionType::Custom => Ok(run_length_encode(data)),
+    }
+}
+
+/// Reverse `compress` for the given codec
+pub fn decompress(data: &[u8], compression: CompressionType) -> Result<Vec<u8>, String> {
+    match compression {
+        CompressionType::None => Ok(data.to_vec()),
+        CompressionType::LZ4 => {
+            // The size prefix comes off the link, so check it before allocating
+            let prefix: [u8; 4] = data.get(..4)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or("LZ4 data is missing its size prefix")?;
+            let size = u32::from_le_bytes(prefix) as usize;
+            if size > MAX_DECOMPRESSED_SIZE {
+                return Err(format!("LZ4 payload claims {} bytes, limit is {}", size, MAX_DECOMPRESSED_SIZE));
+            }
+            lz4_flex::decompress(&data[4..], size)
+                .map_err(|e| format!("LZ4 decompression failed: {}", e))
+        }
+        CompressionType::Gzip => {
+            let mut decompressed = Vec::new();
+            flate2::read::GzDecoder::new(data)
+                .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+                .read_to_end(&mut decompressed)
+                .map_err(|e| format!("Gzip decompression failed: {}", e))?;
+            if decompressed.len() > MAX_DECOMPRESSED_SIZE {
+                return Err(format!("Gzip payload exceeds {} bytes", MAX_DECOMPRESSED_SIZE));
+            }
+            Ok(decompressed)
+        }
+        CompressionType::Custom => run_length_decode(data),
+    }
+}
+
+fn run_length_encode(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for &byte in data {
+        match encoded.len().checked_sub(2) {
+            Some(last) if encoded[last + 1] == byte && encoded[last] < u8::MAX => encoded[last] += 1,
+            _ => encoded.extend_from_slice(&[1, byte]),
+        }
+    }
+    encoded
+}
+
+fn run_length_decode(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() % 2 != 0 {
+        return Err("Run-length data has an odd length".to_string());
+    }
+    let size: usize = data.chunks_exact(2).map(|run| run[0] as usize).sum();
+    if size > MAX_DECOMPRESSED_SIZE {
+        return Err(format!("Run-length payload exceeds {} bytes", MAX_DECOMPRESSED_SIZE));
+    }
+    Ok(data.chunks_exact(2)
+        .flat_map(|run| std::iter::repeat(run[1]).take(run[0] as usize))
+        .collect())
 }
 
 /// Mission timeline event
@@ -96,7 +309,10 @@ pub struct TelemetryProcessor {
     data_aggregators: HashMap<TelemetryType, DataAggregator>,
     statistics: TelemetryStatistics,
     alert_thresholds: HashMap<TelemetryType, AlertThreshold>,
+    calibrations: HashMap<TelemetryType, Calibration>,
+    compression_type: CompressionType,
     downlink_queue: VecDeque<TelemetryPacket>,
+    live_updates: broadcast::Sender<TelemetryData>,
 }
 
 /// Data aggregator for telemetry analysis
@@ -108,7 +324,137 @@ pub struct DataAggregator {
     pub min_value: f64,
     pub max_value: f64,
     pub average: f64,
+    pub p50: Option<f64>,
+    pub p95: Option<f64>,
     pub last_updated: DateTime<Utc>,
+    // Welford running statistics over the numeric samples in the window
+    numeric_count: u64,
+    running_mean: f64,
+    running_m2: f64,
+}
+
+/// Samples an aggregator needs before its z-scores are meaningful
+pub const MIN_ZSCORE_SAMPLES: u64 = 10;
+
+impl DataAggregator {
+    pub fn new(data_type: TelemetryType, window_size: Duration) -> Self {
+        Self {
+            data_type,
+            window_size,
+            samples: VecDeque::new(),
+            min_value: f64::INFINITY,
+            max_value: f64::NEG_INFINITY,
+            average: 0.0,
+            p50: None,
+            p95: None,
+            last_updated: Utc::now(),
+            numeric_count: 0,
+            running_mean: 0.0,
+            running_m2: 0.0,
+        }
+    }
+
+    /// Sample variance of the numeric values in the window
+    pub fn variance(&self) -> Option<f64> {
+        (self.numeric_count >= 2).then(|| self.running_m2 / (self.numeric_count - 1) as f64)
+    }
+
+    /// Standard deviations between `value` and the window mean, once enough samples are in
+    pub fn z_score(&self, value: f64) -> Option<f64> {
+        if self.numeric_count < MIN_ZSCORE_SAMPLES {
+            return None;
+        }
+        let std_dev = self.variance()?.sqrt();
+        let deviation = value - self.running_mean;
+        if std_dev > 0.0 {
+            Some(deviation / std_dev)
+        } else if deviation == 0.0 {
+            Some(0.0)
+        } else {
+            Some(deviation.signum() * f64::INFINITY)
+        }
+    }
+
+    /// Nearest-rank percentile (0-100) of the numeric samples in the window; None when the window is empty
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+        let mut values: Vec<f64> = self.samples.iter()
+            .filter_map(|sample| numeric_value(&sample.value))
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let rank = (p / 100.0 * values.len() as f64).ceil() as usize;
+        Some(values[rank.clamp(1, values.len()) - 1])
+    }
+
+    /// Newest numeric sample strictly older than `timestamp`
+    pub fn last_reading_before(&self, timestamp: DateTime<Utc>) -> Option<(DateTime<Utc>, f64)> {
+        self.samples.iter().rev()
+            .filter(|sample| sample.timestamp < timestamp)
+            .find_map(|sample| numeric_value(&sample.value).map(|value| (sample.timestamp, value)))
+    }
+
+    fn add_to_running_stats(&mut self, value: f64) {
+        self.numeric_count += 1;
+        let delta = value - self.running_mean;
+        self.running_mean += delta / self.numeric_count as f64;
+        self.running_m2 += delta * (value - self.running_mean);
+    }
+
+    fn remove_from_running_stats(&mut self, value: f64) {
+        if self.numeric_count <= 1 {
+            self.numeric_count = 0;
+            self.running_mean = 0.0;
+            self.running_m2 = 0.0;
+            return;
+        }
+        let previous_mean = self.running_mean;
+        self.numeric_count -= 1;
+        self.running_mean = (previous_mean * (self.numeric_count + 1) as f64 - value) / self.numeric_count as f64;
+        self.running_m2 = (self.running_m2 - (value - previous_mean) * (value - self.running_mean)).max(0.0);
+    }
+}
+
+/// Numeric reading of a scalar telemetry value
+fn numeric_value(value: &TelemetryValue) -> Option<f64> {
+    match value {
+        TelemetryValue::Float(v) => Some(*v),
+        TelemetryValue::Integer(v) => Some(*v as f64),
+        _ => None,
+    }
+}
+
+/// Calibration polynomial mapping raw sensor readings to engineering units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Calibration {
+    pub coefficients: Vec<f64>,  // c0 + c1*x + c2*x^2 + ...
+}
+
+impl Calibration {
+    /// Linear calibration: `scale * x + offset`
+    pub fn linear(scale: f64, offset: f64) -> Self {
+        Self { coefficients: vec![offset, scale] }
+    }
+
+    /// Evaluate the polynomial for a raw value
+    pub fn apply(&self, raw: f64) -> f64 {
+        self.coefficients.iter().rev().fold(0.0, |acc, &c| acc * raw + c)
+    }
+
+    /// Convert a numeric telemetry value, leaving non-numeric values unchanged
+    pub fn apply_value(&self, value: &TelemetryValue) -> Option<TelemetryValue> {
+        match value {
+            TelemetryValue::Float(v) => Some(TelemetryValue::Float(self.apply(*v))),
+            TelemetryValue::Integer(v) => Some(TelemetryValue::Float(self.apply(*v as f64))),
+            TelemetryValue::Vector3D(x, y, z) => Some(TelemetryValue::Vector3D(self.apply(*x), self.apply(*y), self.apply(*z))),
+            TelemetryValue::Array(values) => Some(TelemetryValue::Array(values.iter().map(|v| self.apply(*v)).collect())),
+            TelemetryValue::Boolean(_) | TelemetryValue::String(_) => None,
+        }
+    }
 }
 
 /// Alert threshold configuration
@@ -117,6 +463,7 @@ pub struct AlertThreshold {
     pub min_value: Option<f64>,
     pub max_value: Option<f64>,
     pub rate_of_change_limit: Option<f64>,
+    pub max_zscore: Option<f64>,  // deviation from the aggregator window, in standard deviations
     pub alert_level: AlertLevel,
 }
 
@@ -128,17 +475,42 @@ pub enum AlertLevel {
     Emergency,
 }
 
+/// Downlink priority of packets without out-of-limit readings
+pub const DOWNLINK_PRIORITY_HOUSEKEEPING: u8 = 1;
+
+impl AlertLevel {
+    /// Downlink priority for a packet carrying a reading at this alert level
+    pub fn downlink_priority(&self) -> u8 {
+        match self {
+            AlertLevel::Info => DOWNLINK_PRIORITY_HOUSEKEEPING,
+            AlertLevel::Warning => 2,
+            AlertLevel::Critical => 3,
+            AlertLevel::Emergency => 4,
+        }
+    }
+}
+
 /// Telemetry processing statistics
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TelemetryStatistics {
     pub data_points_processed: u64,
     pub packets_transmitted: u64,
     pub alerts_generated: u64,
-    pub compression_ratio: f64,
+    pub compression_ratio: f64,  // compressed / uncompressed bytes over all packets
+    pub bytes_before_compression: u64,
+    pub bytes_after_compression: u64,
     pub average_latency: Duration,
     pub data_quality_score: f64,
 }
 
+/// Processor state saved by `TelemetryProcessor::persist` for crash recovery
+#[derive(Serialize, Deserialize)]
+struct PersistedTelemetry {
+    telemetry_buffer: VecDeque<TelemetryData>,
+    mission_timeline: Vec<MissionEvent>,
+    statistics: TelemetryStatistics,
+}
+
 impl TelemetryProcessor {
     pub fn new() -> Self {
         Self {
@@ -147,10 +519,18 @@ impl TelemetryProcessor {
             data_aggregators: HashMap::new(),
             statistics: TelemetryStatistics::default(),
             alert_thresholds: HashMap::new(),
+            calibrations: HashMap::new(),
+            compression_type: CompressionType::LZ4,
             downlink_queue: VecDeque::new(),
+            live_updates: broadcast::channel(LIVE_TELEMETRY_CAPACITY).0,
         }
     }
 
+    /// Receive every point processed from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<TelemetryData> {
+        self.live_updates.subscribe()
+    }
+
     /// Initialize telemetry processing with default configurations
     pub fn initialize(&mut self) -> Result<(), String> {
         info!("Initializing telemetry processor");
@@ -178,16 +558,7 @@ impl TelemetryProcessor {
         ];
 
         for data_type in telemetry_types {
-            let aggregator = DataAggregator {
-                data_type: data_type.clone(),
-                window_size: Duration::minutes(10),
-                samples: VecDeque::new(),
-                min_value: f64::INFINITY,
-                max_value: f64::NEG_INFINITY,
-                average: 0.0,
-                last_updated: Utc::now(),
-            };
-            self.data_aggregators.insert(data_type, aggregator);
+            self.register_aggregator(data_type, Duration::minutes(10));
         }
     }
 
@@ -200,6 +571,7 @@ impl TelemetryProcessor {
                 min_value: Some(20.0), // 20% battery minimum
                 max_value: None,
                 rate_of_change_limit: Some(-5.0), // -5% per minute
+                max_zscore: None,
                 alert_level: AlertLevel::Warning,
             }
         );
@@ -211,6 +583,7 @@ impl TelemetryProcessor {
                 min_value: Some(-40.0), // -40°C minimum
                 max_value: Some(85.0),  // 85°C maximum
                 rate_of_change_limit: Some(10.0), // 10°C per minute
+                max_zscore: None,
                 alert_level: AlertLevel::Critical,
             }
         );
@@ -222,6 +595,7 @@ impl TelemetryProcessor {
                 min_value: Some(0.7), // 70% health minimum
                 max_value: None,
                 rate_of_change_limit: Some(-0.1), // -10% per minute
+                max_zscore: None,
                 alert_level: AlertLevel::Warning,
             }
         );
@@ -267,9 +641,32 @@ impl TelemetryProcessor {
         info!("Initialized mission timeline with {} events", self.mission_timeline.len());
     }
 
+    /// Set the calibration applied to incoming telemetry of a given type
+    pub fn set_calibration(&mut self, data_type: TelemetryType, calibration: Calibration) {
+        self.calibrations.insert(data_type, calibration);
+    }
+
+    /// Start aggregating a telemetry type over a sliding window, replacing any existing aggregator
+    pub fn register_aggregator(&mut self, data_type: TelemetryType, window_size: Duration) {
+        let aggregator = DataAggregator::new(data_type.clone(), window_size);
+        self.data_aggregators.insert(data_type, aggregator);
+    }
+
+    /// Set the alert threshold for a telemetry type, replacing any default
+    pub fn register_threshold(&mut self, data_type: TelemetryType, threshold: AlertThreshold) {
+        self.alert_thresholds.insert(data_type, threshold);
+    }
+
     /// Process incoming telemetry data
-    pub fn process_telemetry(&mut self, data: TelemetryData) -> Result<(), String> {
+    pub fn process_telemetry(&mut self, mut data: TelemetryData) -> Result<(), String> {
         debug!("Processing telemetry data: {:?}", data.data_type);
+
+        // Convert raw readings to engineering units
+        if let Some(calibration) = self.calibrations.get(&data.data_type) {
+            if let Some(calibrated) = calibration.apply_value(&data.value) {
+                data.raw_value = Some(std::mem::replace(&mut data.value, calibrated));
+            }
+        }
         
         // Validate data quality
         if data.quality < 0.5 {
@@ -282,8 +679,9 @@ impl TelemetryProcessor {
         // Update data aggregator
         self.update_aggregator(&data)?;
         
-        // Add to buffer
+        // Add to buffer and notify live subscribers, if any
         self.telemetry_buffer.push_back(data.clone());
+        let _ = self.live_updates.send(data.clone());
         
         // Maintain buffer size
         if self.telemetry_buffer.len() > 10000 {
@@ -299,6 +697,157 @@ impl TelemetryProcessor {
         Ok(())
     }
 
+    /// Buffered points from one node and type with `from <= timestamp < to`, oldest first
+    pub fn query(&self, source_node: u32, data_type: &TelemetryType, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<TelemetryData> {
+        self.query_history(source_node, Some(data_type), from, to)
+    }
+
+    /// Like `query`, but across every type when `data_type` is `None`, ordered by timestamp then sequence number
+    pub fn query_history(&self, source_node: u32, data_type: Option<&TelemetryType>, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<TelemetryData> {
+        let mut points: Vec<TelemetryData> = self.telemetry_buffer.iter()
+            .filter(|data| data.source_node == source_node && data_type.map_or(true, |t| &data.data_type == t))
+            .filter(|data| data.timestamp >= from && data.timestamp < to)
+            .cloned()
+            .collect();
+        points.sort_by_key(|data| (data.timestamp, data.sequence_number));
+        points
+    }
+
+    /// Most recent buffered point from one node and type
+    pub fn query_latest(&self, source_node: u32, data_type: &TelemetryType) -> Option<&TelemetryData> {
+        self.telemetry_buffer.iter()
+            .filter(|data| data.source_node == source_node && &data.data_type == data_type)
+            .max_by_key(|data| data.timestamp)
+    }
+
+    /// Most recent buffered point of each type from one node, oldest first
+    pub fn query_latest_all(&self, source_node: u32) -> Vec<&TelemetryData> {
+        let mut latest: HashMap<&TelemetryType, &TelemetryData> = HashMap::new();
+        for data in self.telemetry_buffer.iter().filter(|data| data.source_node == source_node) {
+            let entry = latest.entry(&data.data_type).or_insert(data);
+            if data.timestamp >= entry.timestamp {
+                *entry = data;
+            }
+        }
+        let mut points: Vec<&TelemetryData> = latest.into_values().collect();
+        points.sort_by_key(|data| data.timestamp);
+        points
+    }
+
+    /// Write points as CSV with a header row, returning the number of data rows
+    pub fn export_csv<W: Write>(&self, mut writer: W, points: &[TelemetryData]) -> Result<usize, String> {
+        let write_error = |e: std::io::Error| format!("Failed to write CSV export: {}", e);
+        writeln!(writer, "timestamp,source_node,data_type,value,quality").map_err(write_error)?;
+        for data in points {
+            writeln!(writer, "{},{},{},{},{}",
+                     data.timestamp.to_rfc3339(),
+                     data.source_node,
+                     csv_escape(data.data_type.type_name()),
+                     csv_escape(&data.value.to_csv_field()),
+                     data.quality).map_err(write_error)?;
+        }
+        Ok(points.len())
+    }
+
+    /// Write the buffered telemetry as a JSON array of rows, returning the number of rows
+    pub fn export_json<W: Write>(&self, writer: W) -> Result<usize, String> {
+        let rows: Vec<serde_json::Value> = self.telemetry_buffer.iter()
+            .map(|data| serde_json::json!({
+                "timestamp": data.timestamp.to_rfc3339(),
+                "source_node": data.source_node,
+                "data_type": data.data_type.type_name(),
+                "value": data.value.to_json(),
+                "quality": data.quality,
+            }))
+            .collect();
+        serde_json::to_writer_pretty(writer, &rows)
+            .map_err(|e| format!("Failed to write JSON export: {}", e))?;
+        Ok(rows.len())
+    }
+
+    /// Save the buffer, timeline and statistics, replacing the file atomically
+    pub fn persist<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let state = PersistedTelemetry {
+            telemetry_buffer: self.telemetry_buffer.clone(),
+            mission_timeline: self.mission_timeline.clone(),
+            statistics: self.statistics.clone(),
+        };
+        let content = serde_json::to_string(&state)
+            .map_err(|e| format!("Failed to serialize telemetry state: {}", e))?;
+
+        let path = path.as_ref();
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, content)
+            .and_then(|_| fs::rename(&temp_path, path))
+            .map_err(|e| format!("Failed to write telemetry state: {}", e))
+    }
+
+    /// Restore state saved by `persist`, returning the number of buffered points recovered.
+    /// A missing or corrupt file leaves the processor empty rather than failing startup.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> usize {
+        let path = path.as_ref();
+        let state = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))
+            .and_then(|content| serde_json::from_str::<PersistedTelemetry>(&content)
+                .map_err(|e| format!("corrupt state in {}: {}", path.display(), e)));
+
+        let state = state.unwrap_or_else(|e| {
+            warn!("Starting with empty telemetry state, {}", e);
+            PersistedTelemetry {
+                telemetry_buffer: VecDeque::new(),
+                mission_timeline: Vec::new(),
+                statistics: TelemetryStatistics::default(),
+            }
+        });
+
+        self.telemetry_buffer = state.telemetry_buffer;
+        self.mission_timeline = state.mission_timeline;
+        self.statistics = state.statistics;
+        info!("Restored {} buffered telemetry points", self.telemetry_buffer.len());
+        self.telemetry_buffer.len()
+    }
+
+    /// Write the buffered telemetry to a JSON archive, returning the number of points written
+    pub fn archive_to<P: AsRef<Path>>(&self, path: P) -> Result<usize, String> {
+        let content = serde_json::to_string(&self.telemetry_buffer)
+            .map_err(|e| format!("Failed to serialize telemetry archive: {}", e))?;
+        fs::write(path, content)
+            .map_err(|e| format!("Failed to write telemetry archive: {}", e))?;
+        Ok(self.telemetry_buffer.len())
+    }
+
+    /// Replay an archive through `process_telemetry`, pacing points by their timestamps divided by `speed`
+    pub async fn replay_from<P: AsRef<Path>>(&mut self, path: P, speed: f64) -> Result<usize, String> {
+        if !speed.is_finite() || speed <= 0.0 {
+            return Err(format!("Replay speed must be positive and finite, got {}", speed));
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read telemetry archive: {}", e))?;
+        let archived: Vec<TelemetryData> = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse telemetry archive: {}", e))?;
+
+        // Work out the whole schedule up front so a bad speed fails before anything is replayed
+        let mut delays = vec![std::time::Duration::ZERO];
+        for pair in archived.windows(2) {
+            let gap = (pair[1].timestamp - pair[0].timestamp).to_std().unwrap_or_default();
+            delays.push(std::time::Duration::try_from_secs_f64(gap.as_secs_f64() / speed)
+                .map_err(|_| format!("Replay delay of {:?} at speed {} is too long", gap, speed))?);
+        }
+
+        for (mut data, delay) in archived.iter().cloned().zip(delays) {
+            tokio::time::sleep(delay).await;
+
+            // Archived points are already calibrated; replay from the raw reading
+            if let Some(raw) = data.raw_value.take() {
+                data.value = raw;
+            }
+            self.process_telemetry(data)?;
+        }
+
+        info!("Replayed {} archived telemetry points", archived.len());
+        Ok(archived.len())
+    }
+
     /// Check telemetry data against alert thresholds
     fn check_alerts(&mut self, data: &TelemetryData) -> Result<(), String> {
         if let Some(threshold) = self.alert_thresholds.get(&data.data_type).cloned() {
@@ -327,28 +876,32 @@ impl TelemetryProcessor {
                 }
             }
 
-            // Check rate of change
+            // Check rate of change against the latest earlier reading of this type
             if let Some(rate_limit) = threshold.rate_of_change_limit {
-                if let Some(aggregator) = self.data_aggregators.get(&data.data_type) {
-                    if let Some(last_sample) = aggregator.samples.back() {
-                        let time_diff = data.timestamp.signed_duration_since(last_sample.timestamp);
-                        if time_diff > Duration::zero() {
-                            let last_value = match &last_sample.value {
-                                TelemetryValue::Float(v) => *v,
-                                TelemetryValue::Integer(v) => *v as f64,
-                                _ => return Ok(()),
-                            };
-                            
-                            let rate = (value - last_value) / time_diff.num_seconds() as f64 * 60.0; // per minute
-                            if rate.abs() > rate_limit.abs() {
-                                alert_triggered = true;
-                                alert_message.push_str(&format!("Rate of change {} exceeds limit {}", rate, rate_limit));
-                            }
+                let previous = self.data_aggregators.get(&data.data_type)
+                    .and_then(|aggregator| aggregator.last_reading_before(data.timestamp));
+                if let Some((last_timestamp, last_value)) = previous {
+                    let elapsed_secs = (data.timestamp - last_timestamp).num_milliseconds() as f64 / 1000.0;
+                    if elapsed_secs > 0.0 {
+                        let rate = (value - last_value) / elapsed_secs * 60.0; // per minute
+                        if rate.abs() > rate_limit.abs() {
+                            alert_triggered = true;
+                            alert_message.push_str(&format!("Rate of change {:.2} exceeds limit {}", rate, rate_limit));
                         }
                     }
                 }
             }
 
+            // Check deviation from the recent distribution
+            if let Some(max_zscore) = threshold.max_zscore {
+                let z_score = self.data_aggregators.get(&data.data_type)
+                    .and_then(|aggregator| aggregator.z_score(value));
+                if let Some(z_score) = z_score.filter(|z| z.abs() > max_zscore) {
+                    alert_triggered = true;
+                    alert_message.push_str(&format!("Z-score {:.1} exceeds limit {}", z_score, max_zscore));
+                }
+            }
+
             if alert_triggered {
                 self.generate_alert(data, &threshold.alert_level, &alert_message)?;
             }
@@ -379,12 +932,17 @@ impl TelemetryProcessor {
             // Add new sample
             aggregator.samples.push_back(data.clone());
             aggregator.last_updated = data.timestamp;
+            if let Some(value) = numeric_value(&data.value) {
+                aggregator.add_to_running_stats(value);
+            }
 
             // Remove old samples outside the window
             let cutoff_time = data.timestamp - aggregator.window_size;
             while let Some(front) = aggregator.samples.front() {
                 if front.timestamp < cutoff_time {
-                    aggregator.samples.pop_front();
+                    if let Some(value) = aggregator.samples.pop_front().as_ref().and_then(|old| numeric_value(&old.value)) {
+                        aggregator.remove_from_running_stats(value);
+                    }
                 } else {
                     break;
                 }
@@ -409,42 +967,116 @@ impl TelemetryProcessor {
                     aggregator.average = sum / count;
                 }
             }
+
+            aggregator.p50 = aggregator.percentile(50.0);
+            aggregator.p95 = aggregator.percentile(95.0);
         }
 
         Ok(())
     }
 
+    /// Choose the codec applied to downlink packets
+    pub fn set_compression_type(&mut self, compression: CompressionType) {
+        self.compression_type = compression;
+    }
+
     /// Create telemetry packet for downlink
-    pub fn create_telemetry_packet(&mut self, node_id: u32, max_data_points: usize) -> Result<TelemetryPacket, String> {
+    pub fn create_telemetry_packet(&mut self, node_id: u32, max_data_points: usize) -> Result<Option<TelemetryPacket>, String> {
+        // Take the oldest points from this node, leaving other nodes' telemetry buffered
         let mut data_points = Vec::new();
-        
-        // Collect recent telemetry data
-        let mut count = 0;
-        while let Some(data) = self.telemetry_buffer.pop_front() {
-            if data.source_node == node_id {
+        let mut remaining = VecDeque::with_capacity(self.telemetry_buffer.len());
+        for data in self.telemetry_buffer.drain(..) {
+            if data.source_node == node_id && data_points.len() < max_data_points {
                 data_points.push(data);
-                count += 1;
-                if count >= max_data_points {
-                    break;
-                }
+            } else {
+                remaining.push_back(data);
             }
         }
+        self.telemetry_buffer = remaining;
 
         if data_points.is_empty() {
-            return Err("No telemetry data available".to_string());
+            return Ok(None);
         }
 
-        let packet = TelemetryPacket {
+        let priority = data_points.iter()
+            .filter_map(|data| self.limit_violation(data))
+            .map(|level| level.downlink_priority())
+            .max()
+            .unwrap_or(DOWNLINK_PRIORITY_HOUSEKEEPING);
+
+        let point_count = data_points.len();
+        let uncompressed = serde_json::to_vec(&data_points)
+            .map_err(|e| format!("Failed to serialize data points: {}", e))?
+            .len();
+        let mut packet = TelemetryPacket {
             packet_id: rand::random::<u32>(),
             source_node: node_id,
             timestamp: Utc::now(),
             data_points,
-            compression_type: CompressionType::LZ4,
-            priority: 1,
+            compression_type: self.compression_type,
+            priority,
+            fragment_index: 0,
+            fragment_total: 1,
+            compressed_body: Vec::new(),
         };
+        if let Err(e) = packet.pack_body() {
+            self.requeue_packet(packet);
+            return Err(e);
+        }
 
-        info!("Created telemetry packet with {} data points", packet.data_points.len());
-        Ok(packet)
+        let compressed = packet.compressed_body.len();
+        self.statistics.bytes_before_compression += uncompressed as u64;
+        self.statistics.bytes_after_compression += compressed as u64;
+        self.statistics.compression_ratio =
+            self.statistics.bytes_after_compression as f64 / self.statistics.bytes_before_compression as f64;
+
+        info!("Created telemetry packet with {} data points ({} -> {} bytes, {:?})",
+              point_count, uncompressed, compressed, packet.compression_type);
+        Ok(Some(packet))
+    }
+
+    /// Unpack a received packet and process each of its data points, returning how many were ingested
+    pub fn ingest_packet(&mut self, packet: &TelemetryPacket) -> Result<usize, String> {
+        let data_points = packet.unpack_body()
+            .map_err(|e| format!("Telemetry packet {} has a corrupt body: {}", packet.packet_id, e))?;
+
+        let count = data_points.len();
+        for data in data_points {
+            self.process_telemetry(data)?;
+        }
+        debug!("Ingested {} data points from packet {}", count, packet.packet_id);
+        Ok(count)
+    }
+
+    /// Alert level of a reading outside its type's fixed min/max limits
+    fn limit_violation(&self, data: &TelemetryData) -> Option<AlertLevel> {
+        let threshold = self.alert_thresholds.get(&data.data_type)?;
+        let value = numeric_value(&data.value)?;
+        let below = threshold.min_value.is_some_and(|min| value < min);
+        let above = threshold.max_value.is_some_and(|max| value > max);
+        (below || above).then(|| threshold.alert_level.clone())
+    }
+
+    /// Queue a packet for downlink behind any packets of higher priority or equal priority and older
+    pub fn enqueue_downlink(&mut self, packet: TelemetryPacket) {
+        let served_after = |queued: &TelemetryPacket| {
+            (std::cmp::Reverse(queued.priority), queued.timestamp)
+                > (std::cmp::Reverse(packet.priority), packet.timestamp)
+        };
+        let position = self.downlink_queue.iter()
+            .position(served_after)
+            .unwrap_or(self.downlink_queue.len());
+        self.downlink_queue.insert(position, packet);
+    }
+
+    /// Take the highest priority, oldest packet waiting for downlink
+    pub fn next_downlink(&mut self) -> Option<TelemetryPacket> {
+        self.downlink_queue.pop_front()
+    }
+
+    /// Number of packets waiting for downlink
+    pub fn downlink_queue_len(&self) -> usize {
+        self.downlink_queue.len()
     }
 
     /// Log transmission event
@@ -453,6 +1085,29 @@ impl TelemetryProcessor {
         // Update statistics would go here
     }
 
+    /// Return an undelivered packet's points to the front of the buffer, oldest first
+    pub fn requeue_packet(&mut self, packet: TelemetryPacket) {
+        let data_points = match packet.unpack_body() {
+            Ok(data_points) => data_points,
+            Err(e) => {
+                error!("Dropping undelivered telemetry packet {}: {}", packet.packet_id, e);
+                return;
+            }
+        };
+        debug!("Requeuing {} points from undelivered telemetry packet {}",
+               data_points.len(), packet.packet_id);
+        for data in data_points.into_iter().rev() {
+            self.telemetry_buffer.push_front(data);
+        }
+    }
+
+    /// Record a telemetry packet that has been downlinked to the ground
+    pub fn record_downlink(&mut self, packet: &TelemetryPacket, destination: u32) {
+        self.statistics.packets_transmitted += 1;
+        debug!("Downlinked telemetry packet {} ({} body bytes) to node {}",
+               packet.packet_id, packet.compressed_body.len(), destination);
+    }
+
     /// Log reception event
     pub fn log_reception(&mut self, bytes_received: usize) {
         debug!("Logged reception: {} bytes", bytes_received);
@@ -491,28 +1146,9 @@ impl TelemetryProcessor {
         self.data_aggregators.get(data_type)
     }
 
-    /// Compress telemetry data for efficient transmission
+    /// Compress telemetry data for efficient transmission with the configured codec
     pub fn compress_telemetry_data(&self, data: &[u8]) -> Result<Vec<u8>, String> {
-        // Simplified compression - in production, use proper compression algorithms
-        let mut compressed = Vec::new();
-        
-        // Simple run-length encoding for demonstration
-        let mut i = 0;
-        while i < data.len() {
-            let current_byte = data[i];
-            let mut count = 1u8;
-            
-            while (i + count as usize) < data.len() && 
-                  data[i + count as usize] == current_byte && 
-                  count < 255 {
-                count += 1;
-            }
-            
-            compressed.push(count);
-            compressed.push(current_byte);
-            i += count as usize;
-        }
-        
+        let compressed = compress(data, self.compression_type)?;
         info!("Compressed {} bytes to {} bytes (ratio: {:.2})", 
               data.len(), compressed.len(), 
               compressed.len() as f64 / data.len() as f64);
@@ -549,7 +1185,57 @@ impl Default for TelemetryProcessor {
     }
 }
 
+impl TelemetryValue {
+    /// Flatten into a single CSV cell; vectors and arrays join their components with `;`
+    pub fn to_csv_field(&self) -> String {
+        let joined = |values: &[f64]| values.iter().map(f64::to_string).collect::<Vec<_>>().join(";");
+        match self {
+            TelemetryValue::Float(v) => v.to_string(),
+            TelemetryValue::Integer(v) => v.to_string(),
+            TelemetryValue::Boolean(v) => v.to_string(),
+            TelemetryValue::String(v) => v.clone(),
+            TelemetryValue::Vector3D(x, y, z) => joined(&[*x, *y, *z]),
+            TelemetryValue::Array(values) => joined(values),
+        }
+    }
+
+    /// Plain JSON form: numbers, booleans and strings as-is, vectors and arrays as number arrays
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            TelemetryValue::Float(v) => serde_json::json!(v),
+            TelemetryValue::Integer(v) => serde_json::json!(v),
+            TelemetryValue::Boolean(v) => serde_json::json!(v),
+            TelemetryValue::String(v) => serde_json::json!(v),
+            TelemetryValue::Vector3D(x, y, z) => serde_json::json!([x, y, z]),
+            TelemetryValue::Array(values) => serde_json::json!(values),
+        }
+    }
+}
+
+/// Quote a CSV cell if it contains a delimiter, quote or line break
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
 impl TelemetryType {
+    /// Parse a type name case-insensitively, falling back to `Custom`
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "systemhealth" => TelemetryType::SystemHealth,
+            "powerstatus" => TelemetryType::PowerStatus,
+            "orbitposition" => TelemetryType::OrbitPosition,
+            "communication" => TelemetryType::Communication,
+            "payload" => TelemetryType::Payload,
+            "temperature" => TelemetryType::Temperature,
+            "attitude" => TelemetryType::Attitude,
+            _ => TelemetryType::Custom(name.to_string()),
+        }
+    }
+
     fn type_name(&self) -> &str {
         match self {
             TelemetryType::SystemHealth => "SystemHealth",
@@ -568,6 +1254,19 @@ impl TelemetryType {
 mod tests {
     use super::*;
 
+    /// A float reading from `source_node` with good quality
+    fn reading(source_node: u32, data_type: TelemetryType, timestamp: DateTime<Utc>, sequence_number: u64, value: f64) -> TelemetryData {
+        TelemetryData {
+            timestamp,
+            source_node,
+            data_type,
+            value: TelemetryValue::Float(value),
+            quality: 0.9,
+            sequence_number,
+            raw_value: None,
+        }
+    }
+
     #[test]
     fn test_telemetry_processor_creation() {
         let processor = TelemetryProcessor::new();
@@ -587,6 +1286,7 @@ mod tests {
             value: TelemetryValue::Float(25.0),
             quality: 0.95,
             sequence_number: 1,
+            raw_value: None,
         };
         
         assert!(processor.process_telemetry(data).is_ok());
@@ -612,20 +1312,25 @@ mod tests {
         processor.initialize().unwrap();
         
         // Add some test data
-        let data = TelemetryData {
-            timestamp: Utc::now(),
-            source_node: 1,
-            data_type: TelemetryType::SystemHealth,
-            value: TelemetryValue::Float(0.85),
-            quality: 0.9,
-            sequence_number: 1,
-        };
+        let data = reading(1, TelemetryType::SystemHealth, Utc::now(), 1, 0.85);
         
         processor.process_telemetry(data).unwrap();
         
-        let packet = processor.create_telemetry_packet(1, 10).unwrap();
+        let packet = processor.create_telemetry_packet(1, 10).unwrap().unwrap();
         assert_eq!(packet.source_node, 1);
-        assert!(!packet.data_points.is_empty());
+        assert!(packet.data_points.is_empty());
+        assert_eq!(packet.unpack_body().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_empty_telemetry_packet_is_none() {
+        let mut processor = TelemetryProcessor::new();
+        processor.initialize().unwrap();
+        assert!(processor.create_telemetry_packet(1, 10).unwrap().is_none());
+
+        processor.process_telemetry(reading(1, TelemetryType::PowerStatus, Utc::now(), 1, 0.9)).unwrap();
+        assert!(processor.create_telemetry_packet(1, 10).unwrap().is_some());
+        assert!(processor.create_telemetry_packet(1, 10).unwrap().is_none());
     }
 
     #[test]
@@ -641,18 +1346,467 @@ mod tests {
             value: TelemetryValue::Float(100.0), // Above 85°C threshold
             quality: 0.9,
             sequence_number: 1,
+            raw_value: None,
         };
         
         assert!(processor.process_telemetry(data).is_ok());
         assert!(processor.statistics.alerts_generated > 0);
     }
 
+    #[test]
+    fn test_zscore_alert_on_outlier() {
+        let mut processor = TelemetryProcessor::new();
+        processor.initialize().unwrap();
+        processor.register_threshold(TelemetryType::Temperature, AlertThreshold {
+            min_value: None,
+            max_value: None,
+            rate_of_change_limit: None,
+            max_zscore: Some(4.0),
+            alert_level: AlertLevel::Warning,
+        });
+
+        let start = Utc::now();
+        for i in 0..30 {
+            processor.process_telemetry(reading(1, TelemetryType::Temperature, start + Duration::seconds(i), i as u64, 20.0 + [0.0, 0.2, -0.2][i as usize % 3])).unwrap();
+        }
+        assert_eq!(processor.statistics.alerts_generated, 0);
+
+        // Well inside the fixed 85 C limit, but far outside the recent distribution
+        processor.process_telemetry(reading(1, TelemetryType::Temperature, start + Duration::seconds(30), 30, 25.0)).unwrap();
+        assert_eq!(processor.statistics.alerts_generated, 1);
+
+        let aggregator = processor.get_aggregated_data(&TelemetryType::Temperature).unwrap();
+        let mean: f64 = aggregator.samples.iter().filter_map(|s| numeric_value(&s.value)).sum::<f64>() / 31.0;
+        assert!((aggregator.z_score(mean).unwrap()).abs() < 1e-9);
+        assert!(DataAggregator::new(TelemetryType::Temperature, Duration::minutes(1)).z_score(1.0).is_none());
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut processor = TelemetryProcessor::new();
+        processor.initialize().unwrap();
+        let aggregator = processor.get_aggregated_data(&TelemetryType::PowerStatus).unwrap();
+        assert_eq!(aggregator.percentile(50.0), None);
+        assert_eq!(aggregator.p50, None);
+
+        // Values 1..=100 arriving in scrambled order
+        let start = Utc::now();
+        for i in 0..100 {
+            let value = ((i * 37) % 100 + 1) as f64;
+            processor.process_telemetry(reading(1, TelemetryType::PowerStatus, start + Duration::seconds(i), i as u64, value)).unwrap();
+        }
+
+        let aggregator = processor.get_aggregated_data(&TelemetryType::PowerStatus).unwrap();
+        assert_eq!(aggregator.percentile(0.0), Some(1.0));
+        assert_eq!(aggregator.percentile(50.0), Some(50.0));
+        assert_eq!(aggregator.percentile(99.0), Some(99.0));
+        assert_eq!(aggregator.percentile(99.5), Some(100.0));
+        assert_eq!(aggregator.percentile(100.0), Some(100.0));
+        assert_eq!(aggregator.percentile(101.0), None);
+        assert_eq!((aggregator.p50, aggregator.p95), (Some(50.0), Some(95.0)));
+
+        let mut single = DataAggregator::new(TelemetryType::Temperature, Duration::minutes(1));
+        single.samples.push_back(TelemetryData {
+            timestamp: start,
+            source_node: 1,
+            data_type: TelemetryType::Temperature,
+            value: TelemetryValue::Integer(7),
+            quality: 1.0,
+            sequence_number: 0,
+            raw_value: None,
+        });
+        assert_eq!(single.percentile(1.0), Some(7.0));
+        assert_eq!(single.percentile(99.0), Some(7.0));
+    }
+
+    #[test]
+    fn test_custom_type_registration() {
+        let mut processor = TelemetryProcessor::new();
+        processor.initialize().unwrap();
+        let wheel = TelemetryType::Custom("reaction_wheel_rpm".to_string());
+        let start = Utc::now();
+        // Unregistered custom types are buffered but not aggregated or checked
+        processor.process_telemetry(reading(1, wheel.clone(), start, 0, 9000.0)).unwrap();
+        assert!(processor.get_aggregated_data(&wheel).is_none());
+        assert_eq!(processor.query_latest(1, &wheel).unwrap().sequence_number, 0);
+        assert_eq!(processor.statistics.alerts_generated, 0);
+
+        processor.register_aggregator(wheel.clone(), Duration::seconds(30));
+        processor.register_threshold(wheel.clone(), AlertThreshold {
+            min_value: None,
+            max_value: Some(6000.0),
+            rate_of_change_limit: None,
+            max_zscore: None,
+            alert_level: AlertLevel::Critical,
+        });
+        for (i, rpm) in [(1, 3000.0), (2, 3200.0), (40, 3400.0), (41, 6500.0)] {
+            processor.process_telemetry(reading(1, wheel.clone(), start + Duration::seconds(i), i as u64, rpm)).unwrap();
+        }
+
+        let aggregator = processor.get_aggregated_data(&wheel).unwrap();
+        assert_eq!(aggregator.window_size, Duration::seconds(30));
+        assert_eq!(aggregator.samples.len(), 2);
+        assert_eq!(aggregator.max_value, 6500.0);
+        assert_eq!(processor.statistics.alerts_generated, 1);
+    }
+
+    #[test]
+    fn test_downlink_priority_order() {
+        let mut processor = TelemetryProcessor::new();
+        processor.initialize().unwrap();
+        let start = Utc::now();
+        let mut packet_for = |data: TelemetryData| {
+            let node = data.source_node;
+            processor.process_telemetry(data).unwrap();
+            processor.create_telemetry_packet(node, 10).unwrap().unwrap()
+        };
+        let housekeeping = packet_for(reading(1, TelemetryType::Temperature, start, 1, 20.0));
+        let overheating = packet_for(reading(2, TelemetryType::Temperature, start, 2, 95.0));
+        let low_power = packet_for(reading(3, TelemetryType::PowerStatus, start, 3, 10.0));
+        assert_eq!(housekeeping.priority, DOWNLINK_PRIORITY_HOUSEKEEPING);
+        assert_eq!(overheating.priority, AlertLevel::Critical.downlink_priority());
+        assert_eq!(low_power.priority, AlertLevel::Warning.downlink_priority());
+
+        let aged = |packet: &TelemetryPacket, id: u32, seconds: i64| TelemetryPacket {
+            packet_id: id,
+            timestamp: start + Duration::seconds(seconds),
+            ..packet.clone()
+        };
+        processor.enqueue_downlink(aged(&housekeeping, 1, 0));
+        processor.enqueue_downlink(aged(&low_power, 2, 5));
+        processor.enqueue_downlink(aged(&housekeeping, 3, -10));
+        processor.enqueue_downlink(aged(&overheating, 4, 20));
+        processor.enqueue_downlink(aged(&low_power, 5, 1));
+        assert_eq!(processor.downlink_queue_len(), 5);
+
+        let served: Vec<u32> = std::iter::from_fn(|| processor.next_downlink())
+            .map(|packet| packet.packet_id)
+            .collect();
+        assert_eq!(served, vec![4, 5, 2, 3, 1]);
+        assert!(processor.next_downlink().is_none());
+    }
+
+    #[test]
+    fn test_welford_window_eviction() {
+        let mut aggregator = DataAggregator::new(TelemetryType::Temperature, Duration::minutes(1));
+        for value in [1.0, 2.0, 3.0, 4.0, 10.0] {
+            aggregator.add_to_running_stats(value);
+        }
+        aggregator.remove_from_running_stats(1.0);
+        aggregator.remove_from_running_stats(10.0);
+        assert!((aggregator.running_mean - 3.0).abs() < 1e-9);
+        assert!((aggregator.variance().unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_time_range_query() {
+        let mut processor = TelemetryProcessor::new();
+        let start = Utc::now();
+        let point = |node: u32, data_type: TelemetryType, minute: i64| reading(node, data_type, start + Duration::minutes(minute), minute as u64, minute as f64);
+        // Out of order arrival, mixed nodes and types
+        for minute in [4, 0, 2, 1, 3] {
+            processor.process_telemetry(point(1, TelemetryType::Temperature, minute)).unwrap();
+        }
+        processor.process_telemetry(point(2, TelemetryType::Temperature, 2)).unwrap();
+        processor.process_telemetry(point(1, TelemetryType::PowerStatus, 2)).unwrap();
+
+        let window = processor.query(1, &TelemetryType::Temperature, start + Duration::minutes(1), start + Duration::minutes(4));
+        let minutes: Vec<u64> = window.iter().map(|data| data.sequence_number).collect();
+        assert_eq!(minutes, vec![1, 2, 3]);
+        assert_eq!(processor.query(2, &TelemetryType::Temperature, start, start + Duration::hours(1)).len(), 1);
+        assert!(processor.query(1, &TelemetryType::Attitude, start, start + Duration::hours(1)).is_empty());
+
+        assert_eq!(processor.query_latest(1, &TelemetryType::Temperature).unwrap().sequence_number, 4);
+        assert!(processor.query_latest(3, &TelemetryType::Temperature).is_none());
+        // Queries do not consume the buffer
+        assert_eq!(processor.create_telemetry_packet(1, 100).unwrap().unwrap().unpack_body().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_csv_and_json_export() {
+        let mut processor = TelemetryProcessor::new();
+        let values = [
+            (TelemetryType::Temperature, TelemetryValue::Float(21.5)),
+            (TelemetryType::Attitude, TelemetryValue::Vector3D(0.1, -0.2, 90.0)),
+            (TelemetryType::Payload, TelemetryValue::Array(vec![1.0, 2.5])),
+            (TelemetryType::Custom("mode, note".to_string()), TelemetryValue::String("say \"hi\"".to_string())),
+        ];
+        for (i, (data_type, value)) in values.into_iter().enumerate() {
+            processor.process_telemetry(TelemetryData {
+                timestamp: Utc::now(),
+                source_node: 3,
+                data_type,
+                value,
+                quality: 0.75,
+                sequence_number: i as u64,
+                raw_value: None,
+            }).unwrap();
+        }
+
+        let points = processor.query(3, &TelemetryType::Attitude, Utc::now() - Duration::hours(1), Utc::now() + Duration::hours(1));
+        let mut csv = Vec::new();
+        assert_eq!(processor.export_csv(&mut csv, &points).unwrap(), 1);
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "timestamp,source_node,data_type,value,quality");
+        let row: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(row.len(), 5);
+        assert!(DateTime::parse_from_rfc3339(row[0]).is_ok());
+        assert_eq!(&row[1..], ["3", "Attitude", "0.1;-0.2;90", "0.75"]);
+
+        let all: Vec<TelemetryData> = processor.telemetry_buffer.iter().cloned().collect();
+        let mut csv = Vec::new();
+        assert_eq!(processor.export_csv(&mut csv, &all).unwrap(), 4);
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.contains(",\"mode, note\",\"say \"\"hi\"\"\",0.75"));
+
+        let mut json = Vec::new();
+        assert_eq!(processor.export_json(&mut json).unwrap(), 4);
+        let rows: Vec<serde_json::Value> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[1]["value"], serde_json::json!([0.1, -0.2, 90.0]));
+        assert_eq!(rows[2]["data_type"], "Payload");
+        assert_eq!(rows[0]["source_node"], 3);
+    }
+
+    #[test]
+    fn test_persist_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("telemetry_state.json");
+
+        let mut processor = TelemetryProcessor::new();
+        processor.initialize().unwrap();
+        for i in 0..5 {
+            processor.process_telemetry(TelemetryData {
+                timestamp: Utc::now(),
+                source_node: 1,
+                data_type: TelemetryType::Temperature,
+                value: TelemetryValue::Float(90.0 + i as f64),
+                quality: 0.8,
+                sequence_number: i,
+                raw_value: None,
+            }).unwrap();
+        }
+        processor.persist(&path).unwrap();
+
+        let mut restored = TelemetryProcessor::new();
+        assert_eq!(restored.load(&path), 5);
+        let original: Vec<u64> = processor.telemetry_buffer.iter().map(|d| d.sequence_number).collect();
+        let reloaded: Vec<u64> = restored.telemetry_buffer.iter().map(|d| d.sequence_number).collect();
+        assert_eq!(reloaded, original);
+        assert_eq!(restored.mission_timeline.len(), processor.mission_timeline.len());
+        assert_eq!(restored.statistics.data_points_processed, 5);
+        assert_eq!(restored.statistics.alerts_generated, processor.statistics.alerts_generated);
+        assert_eq!(restored.statistics.data_quality_score, processor.statistics.data_quality_score);
+
+        fs::write(&path, "{ not json").unwrap();
+        assert_eq!(restored.load(&path), 0);
+        assert_eq!(restored.statistics.data_points_processed, 0);
+        assert_eq!(restored.load(dir.path().join("missing.json")), 0);
+    }
+
+    #[test]
+    fn test_rate_of_change_alerts() {
+        let rate_only = AlertThreshold {
+            min_value: None,
+            max_value: None,
+            rate_of_change_limit: Some(10.0), // per minute
+            max_zscore: None,
+            alert_level: AlertLevel::Warning,
+        };
+        let start = Utc::now();
+        // Flat series, including repeated and sub-second timestamps, never alerts
+        let mut flat = TelemetryProcessor::new();
+        flat.initialize().unwrap();
+        flat.register_threshold(TelemetryType::Temperature, rate_only.clone());
+        for millis in [0, 0, 500, 500, 1000, 60_000, 60_250] {
+            flat.process_telemetry(reading(1, TelemetryType::Temperature, start + Duration::milliseconds(millis), millis as u64, 25.0)).unwrap();
+        }
+        assert_eq!(flat.statistics.alerts_generated, 0);
+
+        // Slow drift over sub-second steps stays under the limit
+        let mut ramp = TelemetryProcessor::new();
+        ramp.initialize().unwrap();
+        ramp.register_threshold(TelemetryType::Temperature, rate_only);
+        for step in 0..5 {
+            ramp.process_telemetry(reading(1, TelemetryType::Temperature, start + Duration::milliseconds(step * 500), (step * 500) as u64, 25.0 + step as f64 * 0.05)).unwrap();
+        }
+        assert_eq!(ramp.statistics.alerts_generated, 0);
+
+        // A duplicate timestamp is compared with the earlier reading, not itself
+        ramp.process_telemetry(reading(1, TelemetryType::Temperature, start + Duration::milliseconds(2000), 2000, 25.2)).unwrap();
+        assert_eq!(ramp.statistics.alerts_generated, 0);
+
+        // 5 C in 6 s is 50 C per minute
+        ramp.process_telemetry(reading(1, TelemetryType::Temperature, start + Duration::milliseconds(8000), 8000, 30.2)).unwrap();
+        assert_eq!(ramp.statistics.alerts_generated, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_from_archive() {
+        let mut processor = TelemetryProcessor::new();
+        processor.initialize().unwrap();
+        let start = Utc::now();
+        for (i, temperature) in [20.0, 100.0, 25.0].iter().enumerate() {
+            processor.process_telemetry(reading(1, TelemetryType::Temperature, start + Duration::seconds(i as i64), i as u64, *temperature)).unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("telemetry.json");
+        assert_eq!(processor.archive_to(&archive).unwrap(), 3);
+
+        let mut replay = TelemetryProcessor::new();
+        replay.initialize().unwrap();
+        assert_eq!(replay.replay_from(&archive, 1000.0).await.unwrap(), 3);
+
+        assert_eq!(replay.statistics.data_points_processed, 3);
+        assert_eq!(replay.statistics.alerts_generated, processor.statistics.alerts_generated);
+        assert!(replay.statistics.alerts_generated > 0);
+        assert!(replay.replay_from(&archive, 0.0).await.is_err());
+        assert!(replay.replay_from(&archive, f64::INFINITY).await.is_err());
+        assert!(replay.replay_from(&archive, f64::MIN_POSITIVE).await.unwrap_err().contains("too long"));
+        assert_eq!(replay.statistics.data_points_processed, 3);
+    }
+
+    #[test]
+    fn test_ingest_compressed_packet() {
+        let mut satellite = TelemetryProcessor::new();
+        for i in 0..10 {
+            satellite.process_telemetry(reading(2, TelemetryType::Temperature, Utc::now(), i, 20.0 + i as f64)).unwrap();
+        }
+        let mut packet = satellite.create_telemetry_packet(2, 10).unwrap().unwrap();
+        assert!(packet.data_points.is_empty());
+        // Packing again must not replace the body with an empty one
+        packet.pack_body().unwrap();
+
+        let mut ground = TelemetryProcessor::new();
+        ground.initialize().unwrap();
+        assert_eq!(ground.ingest_packet(&packet).unwrap(), 10);
+        let aggregator = ground.get_aggregated_data(&TelemetryType::Temperature).unwrap();
+        assert_eq!(aggregator.samples.len(), 10);
+        assert_eq!(aggregator.max_value, 29.0);
+        assert_eq!(ground.get_statistics().data_points_processed, 10);
+
+        let mut truncated = packet.clone();
+        truncated.compressed_body.truncate(truncated.compressed_body.len() / 2);
+        assert!(ground.ingest_packet(&truncated).unwrap_err().contains("corrupt body"));
+        assert!(packet.fragment(1024).is_err());
+    }
+
     #[test]
     fn test_data_compression() {
-        let processor = TelemetryProcessor::new();
+        let mut processor = TelemetryProcessor::new();
+        processor.set_compression_type(CompressionType::Custom);
         let test_data = vec![1, 1, 1, 2, 2, 3, 3, 3, 3];
         
         let compressed = processor.compress_telemetry_data(&test_data).unwrap();
         assert!(compressed.len() < test_data.len());
+        assert_eq!(decompress(&compressed, CompressionType::Custom).unwrap(), test_data);
+    }
+
+    #[test]
+    fn test_codec_round_trips() {
+        let points: Vec<TelemetryData> = (0..50)
+            .map(|i| TelemetryData {
+                timestamp: Utc::now(),
+                source_node: 1,
+                data_type: TelemetryType::Temperature,
+                value: TelemetryValue::Float(20.0 + (i % 5) as f64 * 0.5),
+                quality: 0.95,
+                sequence_number: i,
+                raw_value: None,
+            })
+            .collect();
+        let json = serde_json::to_vec(&points).unwrap();
+
+        for codec in [CompressionType::None, CompressionType::LZ4, CompressionType::Gzip, CompressionType::Custom] {
+            let compressed = compress(&json, codec).unwrap();
+            assert_eq!(decompress(&compressed, codec).unwrap(), json, "{:?}", codec);
+            if matches!(codec, CompressionType::LZ4 | CompressionType::Gzip) {
+                assert!(compressed.len() * 3 < json.len(), "{:?} only reached {} bytes", codec, compressed.len());
+            }
+        }
+        assert!(decompress(b"not gzip", CompressionType::Gzip).is_err());
+        assert!(decompress(&[3], CompressionType::Custom).is_err());
+    }
+
+    #[test]
+    fn test_decompress_rejects_oversized_payloads() {
+        let mut forged = compress(b"tiny", CompressionType::LZ4).unwrap();
+        forged[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decompress(&forged, CompressionType::LZ4).unwrap_err().contains("limit"));
+        assert!(decompress(&[1, 2], CompressionType::LZ4).is_err());
+
+        let bomb = compress(&vec![0u8; MAX_DECOMPRESSED_SIZE + 1], CompressionType::Gzip).unwrap();
+        assert!(decompress(&bomb, CompressionType::Gzip).is_err());
+
+        let runs = [u8::MAX, 0].repeat(MAX_DECOMPRESSED_SIZE / u8::MAX as usize + 1);
+        assert!(decompress(&runs, CompressionType::Custom).is_err());
+    }
+
+    #[test]
+    fn test_packet_compression_statistics() {
+        let mut processor = TelemetryProcessor::new();
+        processor.set_compression_type(CompressionType::Gzip);
+        for i in 0..20 {
+            processor.process_telemetry(reading(1, TelemetryType::PowerStatus, Utc::now(), i, 80.0)).unwrap();
+        }
+
+        let packet = processor.create_telemetry_packet(1, 20).unwrap().unwrap();
+        assert_eq!(packet.compression_type, CompressionType::Gzip);
+        assert!(packet.data_points.is_empty());
+        assert_eq!(packet.unpack_body().unwrap().len(), 20);
+
+        // The ratio describes the body that is actually sent
+        let stats = processor.get_statistics();
+        assert_eq!(stats.bytes_after_compression, packet.compressed_body.len() as u64);
+        assert!(stats.compression_ratio > 0.0 && stats.compression_ratio < 0.5);
+    }
+
+    #[test]
+    fn test_packet_fragmentation_and_reassembly() {
+        let data_points: Vec<TelemetryData> = (0..40)
+            .map(|i| reading(1, TelemetryType::Temperature, Utc::now(), i, i as f64))
+            .collect();
+        let packet = TelemetryPacket {
+            packet_id: 42,
+            source_node: 1,
+            timestamp: Utc::now(),
+            data_points,
+            compression_type: CompressionType::None,
+            priority: 1,
+            fragment_index: 0,
+            fragment_total: 1,
+            compressed_body: Vec::new(),
+        };
+
+        let fragments = packet.fragment(1024).unwrap();
+        assert!(fragments.len() > 1);
+        for fragment in &fragments {
+            assert!(serde_json::to_vec(fragment).unwrap().len() <= 1024);
+            assert_eq!(fragment.fragment_total as usize, fragments.len());
+        }
+
+        let mut shuffled = fragments.clone();
+        shuffled.reverse();
+        let restored = TelemetryPacket::reassemble(&shuffled).unwrap();
+        let sequence: Vec<u64> = restored.data_points.iter().map(|d| d.sequence_number).collect();
+        assert_eq!(sequence, (0..40).collect::<Vec<u64>>());
+
+        let incomplete: Vec<TelemetryPacket> = fragments.into_iter().skip(1).collect();
+        assert!(TelemetryPacket::reassemble(&incomplete).is_err());
+    }
+
+    #[test]
+    fn test_linear_calibration() {
+        let mut processor = TelemetryProcessor::new();
+        processor.set_calibration(TelemetryType::Payload, Calibration::linear(2.0, 5.0));
+
+        let data = reading(1, TelemetryType::Payload, Utc::now(), 1, 10.0);
+        processor.process_telemetry(data).unwrap();
+
+        let stored = processor.telemetry_buffer.back().unwrap();
+        assert!(matches!(stored.value, TelemetryValue::Float(v) if v == 25.0));
+        assert!(matches!(stored.raw_value, Some(TelemetryValue::Float(v)) if v == 10.0));
     }
 }
\ No newline at end of file
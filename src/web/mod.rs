@@ -2,18 +2,42 @@
 // This shows practical web development skills alongside embedded systems
 
 use warp::Filter;
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket};
+use futures::{SinkExt, StreamExt};
+use log::{debug, warn};
 use serde_json::json;
+use tokio::sync::{broadcast, oneshot};
+use std::future::Future;
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
-use crate::telemetry::{TelemetryData, TelemetryProcessor};
-use crate::simulation::SpaceSimulator;
+use crate::telemetry::{TelemetryData, TelemetryProcessor, TelemetryType};
+use crate::simulation::{ScenarioConfig, SpaceSimulator};
+use crate::metrics::GLOBAL_METRICS;
 
 #[derive(Clone)]
 pub struct DashboardState {
     pub telemetry_processor: Arc<Mutex<TelemetryProcessor>>,
     pub simulator: Arc<Mutex<SpaceSimulator>>,
     pub active_satellites: Arc<Mutex<HashMap<u32, SatelliteStatus>>>,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Default and maximum number of points returned by the telemetry history endpoint
+pub const DEFAULT_HISTORY_LIMIT: usize = 1000;
+pub const MAX_HISTORY_LIMIT: usize = 10000;
+
+/// Query parameters for `/api/telemetry/{id}/history`; timestamps are RFC 3339
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct HistoryQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    #[serde(rename = "type")]
+    pub data_type: Option<String>,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>, // `next_cursor` from the previous page
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -28,63 +52,109 @@ pub struct SatelliteStatus {
     pub status: String,
 }
 
+impl Default for DashboardState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DashboardState {
     pub fn new() -> Self {
         Self {
             telemetry_processor: Arc::new(Mutex::new(TelemetryProcessor::new())),
             simulator: Arc::new(Mutex::new(SpaceSimulator::new())),
             active_satellites: Arc::new(Mutex::new(HashMap::new())),
+            started_at: Utc::now(),
         }
     }
     
+    /// Whether a satellite is simulated or has reported its status
+    pub fn knows_satellite(&self, satellite_id: u32) -> bool {
+        self.simulator.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).satellite(satellite_id).is_some()
+            || self.active_satellites.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).contains_key(&satellite_id)
+    }
+    
     pub fn update_satellite_status(&self, satellite_id: u32, status: SatelliteStatus) {
         if let Ok(mut satellites) = self.active_satellites.lock() {
             satellites.insert(satellite_id, status);
         }
     }
+    
+    /// Status of every simulated or reported satellite, ordered by id. Simulated
+    /// satellites take their position from the simulator; reported fields win otherwise.
+    pub fn satellite_statuses(&self) -> Vec<SatelliteStatus> {
+        let simulator = self.simulator.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let reported = self.active_satellites.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        
+        let mut statuses: HashMap<u32, SatelliteStatus> = reported.clone();
+        for (id, position) in simulator.get_satellite_positions() {
+            let live_position = (position.latitude, position.longitude, position.altitude);
+            match statuses.get_mut(&id) {
+                Some(status) => status.position = live_position,
+                None => {
+                    let Some(satellite) = simulator.satellite(id) else { continue };
+                    statuses.insert(id, SatelliteStatus {
+                        id,
+                        name: format!("CubeSat-{}", id),
+                        position: live_position,
+                        battery_level: satellite.system_state.power_level,
+                        temperature: satellite.system_state.temperature,
+                        signal_strength: 0.0, // Unknown until a contact is reported
+                        last_contact: satellite.last_update,
+                        status: "Operational".to_string(),
+                    });
+                }
+            }
+        }
+        
+        let mut statuses: Vec<SatelliteStatus> = statuses.into_values().collect();
+        statuses.sort_by_key(|status| status.id);
+        statuses
+    }
 }
 
-pub async fn start_dashboard(port: u16) -> Result<(), Box<dyn std::error::Error>> {
+/// Bind the dashboard to localhost (port 0 picks a free port) and return the bound
+/// address with the server future, which completes once `shutdown` fires or its sender is dropped
+pub async fn start_dashboard(port: u16, shutdown: oneshot::Receiver<()>) -> Result<(SocketAddr, impl Future<Output = ()>), Box<dyn std::error::Error>> {
     let state = DashboardState::new();
     
-    // Initialize with some demo satellites
-    let demo_satellites = vec![
-        SatelliteStatus {
-            id: 1,
-            name: "CubeSat-Alpha".to_string(),
-            position: (45.0, 2.0, 408.0),
-            battery_level: 0.85,
-            temperature: 22.5,
-            signal_strength: 0.92,
-            last_contact: Utc::now(),
-            status: "Operational".to_string(),
-        },
-        SatelliteStatus {
-            id: 2,
-            name: "CubeSat-Beta".to_string(),
-            position: (-12.0, 45.0, 412.0),
-            battery_level: 0.78,
-            temperature: 18.3,
-            signal_strength: 0.87,
-            last_contact: Utc::now(),
-            status: "Operational".to_string(),
-        },
-        SatelliteStatus {
-            id: 3,
-            name: "CubeSat-Gamma".to_string(),
-            position: (67.0, -15.0, 395.0),
-            battery_level: 0.92,
-            temperature: 25.1,
-            signal_strength: 0.95,
-            last_contact: Utc::now(),
-            status: "Operational".to_string(),
-        },
-    ];
-    
-    for sat in demo_satellites {
-        state.update_satellite_status(sat.id, sat);
+    if let Ok(mut simulator) = state.simulator.lock() {
+        simulator.initialize_scenario(ScenarioConfig::default())?;
+    }
+    
+    if let Ok(mut processor) = state.telemetry_processor.lock() {
+        processor.initialize()?;
     }
     
+    // Static files
+    let static_files = warp::path("static")
+        .and(warp::fs::dir("web/static"));
+    
+    // Main dashboard page
+    let dashboard = warp::path::end()
+        .and(warp::get())
+        .map(|| {
+            warp::reply::html(include_str!("dashboard.html"))
+        });
+    
+    let routes = api_routes(state)
+        .or(static_files)
+        .or(dashboard)
+        .with(warp::cors().allow_any_origin());
+    
+    let (address, server) = warp::serve(routes)
+        .try_bind_with_graceful_shutdown(([127, 0, 0, 1], port), async {
+            let _ = shutdown.await;
+        })?;
+    
+    println!("🌐 Starting web dashboard on http://localhost:{}", address.port());
+    println!("   Open your browser to view real-time satellite data");
+    
+    Ok((address, server))
+}
+
+/// JSON API routes served by the dashboard
+pub fn api_routes(state: DashboardState) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
     let state_filter = warp::any().map(move || state.clone());
     
     // API Routes
@@ -98,74 +168,252 @@ pub async fn start_dashboard(port: u16) -> Result<(), Box<dyn std::error::Error>
         .and(state_filter.clone())
         .and_then(get_telemetry);
     
+    let api_history = warp::path!("api" / "telemetry" / u32 / "history")
+        .and(warp::get())
+        .and(warp::query::<HistoryQuery>())
+        .and(state_filter.clone())
+        .and_then(get_telemetry_history);
+    
     let api_status = warp::path!("api" / "status")
         .and(warp::get())
         .and(state_filter.clone())
         .and_then(get_system_status);
     
-    // Static files
-    let static_files = warp::path("static")
-        .and(warp::fs::dir("web/static"));
+    let api_topology = warp::path!("api" / "topology")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_topology);
     
-    // Main dashboard page
-    let dashboard = warp::path::end()
+    let api_aggregates = warp::path!("api" / "aggregates" / String)
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_aggregates);
+    
+    let api_orbits = warp::path!("api" / "orbits")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_orbits);
+    
+    // Live telemetry pushed as JSON text messages
+    let ws_telemetry = warp::path!("ws" / "telemetry")
+        .and(warp::ws())
+        .and(state_filter.clone())
+        .map(|ws: warp::ws::Ws, state: DashboardState| {
+            // Subscribe before the upgrade so nothing processed after the handshake is missed
+            let updates = state.telemetry_processor.lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .subscribe();
+            ws.on_upgrade(move |socket| stream_telemetry(socket, updates))
+        });
+    
+    // Prometheus scrape endpoint
+    let metrics = warp::path!("metrics")
         .and(warp::get())
         .map(|| {
-            warp::reply::html(include_str!("dashboard.html"))
+            warp::reply::with_header(GLOBAL_METRICS.export_prometheus(), "content-type", "text/plain; version=0.0.4")
         });
     
-    let routes = api_satellites
+    api_satellites
         .or(api_telemetry)
+        .or(api_history)
         .or(api_status)
-        .or(static_files)
-        .or(dashboard)
-        .with(warp::cors().allow_any_origin());
-    
-    println!("🌐 Starting web dashboard on http://localhost:{}", port);
-    println!("   Open your browser to view real-time satellite data");
+        .or(api_topology)
+        .or(api_aggregates)
+        .or(api_orbits)
+        .or(ws_telemetry)
+        .or(metrics)
+}
+
+async fn stream_telemetry(socket: WebSocket, mut updates: broadcast::Receiver<TelemetryData>) {
+    let (mut outgoing, mut incoming) = socket.split();
     
-    warp::serve(routes)
-        .run(([127, 0, 0, 1], port))
-        .await;
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(data) => {
+                    let text = match serde_json::to_string(&data) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            warn!("Failed to serialize live telemetry: {}", e);
+                            continue;
+                        }
+                    };
+                    if outgoing.send(Message::text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Live telemetry client lagged, dropped {} points", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = incoming.next() => match message {
+                // Clients only listen; anything but a close frame is ignored
+                Some(Ok(message)) if !message.is_close() => {}
+                _ => break,
+            },
+        }
+    }
     
-    Ok(())
+    debug!("Live telemetry client disconnected");
+    let _ = outgoing.close().await;
 }
 
 async fn get_satellites(state: DashboardState) -> Result<impl warp::Reply, warp::Rejection> {
-    if let Ok(satellites) = state.active_satellites.lock() {
-        let satellite_list: Vec<&SatelliteStatus> = satellites.values().collect();
-        Ok(warp::reply::json(&satellite_list))
-    } else {
-        Ok(warp::reply::json(&json!({"error": "Unable to fetch satellites"})))
-    }
+    Ok(warp::reply::json(&state.satellite_statuses()))
 }
 
 async fn get_telemetry(satellite_id: u32, state: DashboardState) -> Result<impl warp::Reply, warp::Rejection> {
-    // Generate some sample telemetry data
+    let position = state.simulator.lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .satellite(satellite_id)
+        .map(|satellite| satellite.position.clone());
+    if !state.knows_satellite(satellite_id) {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": format!("Satellite {} not found", satellite_id)})),
+            StatusCode::NOT_FOUND,
+        ));
+    }
+    
+    let processor = state.telemetry_processor.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
     let telemetry_data = json!({
         "satellite_id": satellite_id,
         "timestamp": Utc::now(),
-        "data": {
-            "temperature": 22.5 + (satellite_id as f64 * 0.5),
-            "battery_voltage": 7.2 + (satellite_id as f64 * 0.1),
-            "solar_panel_current": 0.85 + (satellite_id as f64 * 0.05),
-            "attitude": [0.1, -0.2, 0.05],
-            "position": [45.0 + satellite_id as f64, 2.0 + satellite_id as f64, 408.0]
-        }
+        "position": position,
+        "data": processor.query_latest_all(satellite_id),
     });
     
-    Ok(warp::reply::json(&telemetry_data))
+    Ok(warp::reply::with_status(warp::reply::json(&telemetry_data), StatusCode::OK))
+}
+
+/// Opaque history page cursor naming the last point returned, as (timestamp, sequence number)
+fn encode_history_cursor(data: &TelemetryData) -> String {
+    format!("{:016x}{:08x}{:016x}", data.timestamp.timestamp(), data.timestamp.timestamp_subsec_nanos(), data.sequence_number)
+}
+
+fn decode_history_cursor(cursor: &str) -> Option<(DateTime<Utc>, u64)> {
+    if cursor.len() != 40 || !cursor.is_ascii() {
+        return None;
+    }
+    let seconds = u64::from_str_radix(&cursor[..16], 16).ok()? as i64;
+    let nanos = u32::from_str_radix(&cursor[16..24], 16).ok()?;
+    let sequence_number = u64::from_str_radix(&cursor[24..], 16).ok()?;
+    Some((DateTime::from_timestamp(seconds, nanos)?, sequence_number))
+}
+
+async fn get_telemetry_history(satellite_id: u32, query: HistoryQuery, state: DashboardState) -> Result<impl warp::Reply, warp::Rejection> {
+    let bad_request = |error: String| Ok(warp::reply::with_status(warp::reply::json(&json!({"error": error})), StatusCode::BAD_REQUEST));
+    if !state.knows_satellite(satellite_id) {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": format!("Satellite {} not found", satellite_id)})),
+            StatusCode::NOT_FOUND,
+        ));
+    }
+    
+    let parse_time = |name: &str, value: &Option<String>, default: DateTime<Utc>| match value {
+        Some(value) => DateTime::parse_from_rfc3339(value)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(|e| format!("Invalid '{}' timestamp '{}': {}", name, value, e)),
+        None => Ok(default),
+    };
+    let range = parse_time("from", &query.from, DateTime::<Utc>::MIN_UTC)
+        .and_then(|from| Ok((from, parse_time("to", &query.to, DateTime::<Utc>::MAX_UTC)?)));
+    let (from, to) = match range {
+        Ok(range) => range,
+        Err(error) => return bad_request(error),
+    };
+    if from > to {
+        return bad_request(format!("'from' ({}) is after 'to' ({})", from, to));
+    }
+    let after = match query.cursor.as_deref().map(decode_history_cursor) {
+        Some(None) => return bad_request("Invalid history cursor".to_string()),
+        Some(cursor) => cursor,
+        None => None,
+    };
+    let data_type = query.data_type.as_deref().map(TelemetryType::from_name);
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+    
+    let mut points = state.telemetry_processor.lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .query_history(satellite_id, data_type.as_ref(), from, to);
+    if let Some(after) = after {
+        points.retain(|data| (data.timestamp, data.sequence_number) > after);
+    }
+    let next_cursor = (points.len() > limit).then(|| encode_history_cursor(&points[limit - 1]));
+    points.truncate(limit);
+    
+    let history = json!({
+        "satellite_id": satellite_id,
+        "count": points.len(),
+        "next_cursor": next_cursor,
+        "points": points,
+    });
+    Ok(warp::reply::with_status(warp::reply::json(&history), StatusCode::OK))
 }
 
 async fn get_system_status(state: DashboardState) -> Result<impl warp::Reply, warp::Rejection> {
+    let active_satellites = state.satellite_statuses().len();
+    let ground_stations = state.simulator.lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get_ground_station_status()
+        .len();
+    let telemetry_points = state.telemetry_processor.lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get_statistics()
+        .data_points_processed;
+    let uptime = (Utc::now() - state.started_at).max(chrono::Duration::zero());
+    
     let status = json!({
-        "system_status": "Operational",
-        "active_satellites": 3,
-        "ground_stations": 2,
-        "uptime": "72h 15m",
-        "data_rate": "1.2 MB/s",
+        "system_status": if active_satellites > 0 { "Operational" } else { "Idle" },
+        "active_satellites": active_satellites,
+        "ground_stations": ground_stations,
+        "uptime": format!("{}h {}m", uptime.num_hours(), uptime.num_minutes() % 60),
+        "uptime_seconds": uptime.num_seconds(),
+        "telemetry_points": telemetry_points,
+        "message_rate": GLOBAL_METRICS.throughput_window(60),
         "last_updated": Utc::now()
     });
     
     Ok(warp::reply::json(&status))
-}
\ No newline at end of file
+}
+
+async fn get_topology(state: DashboardState) -> Result<impl warp::Reply, warp::Rejection> {
+    if let Ok(simulator) = state.simulator.lock() {
+        Ok(warp::reply::json(&simulator.network().topology_graph()))
+    } else {
+        Ok(warp::reply::json(&json!({"error": "Unable to fetch topology"})))
+    }
+}
+
+async fn get_orbits(state: DashboardState) -> Result<impl warp::Reply, warp::Rejection> {
+    if let Ok(simulator) = state.simulator.lock() {
+        Ok(warp::reply::json(&simulator.orbit_states()))
+    } else {
+        Ok(warp::reply::json(&json!({"error": "Unable to fetch orbits"})))
+    }
+}
+
+async fn get_aggregates(data_type: String, state: DashboardState) -> Result<impl warp::Reply, warp::Rejection> {
+    let data_type = TelemetryType::from_name(&data_type);
+    let processor = state.telemetry_processor.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let Some(aggregator) = processor.get_aggregated_data(&data_type) else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": format!("No aggregator for {:?}", data_type)})),
+            StatusCode::NOT_FOUND,
+        ));
+    };
+    let response = json!({
+        "data_type": format!("{:?}", aggregator.data_type),
+        "window_seconds": aggregator.window_size.num_seconds(),
+        "min": aggregator.min_value,
+        "max": aggregator.max_value,
+        "average": aggregator.average,
+        "p50": aggregator.p50,
+        "p95": aggregator.p95,
+        "sample_count": aggregator.samples.len(),
+        "last_updated": aggregator.last_updated,
+    });
+
+    Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+}
@@ -9,7 +9,7 @@ use rustsat_esa::simulation::SpaceSimulator;
 #[test]
 fn test_complete_protocol_stack_integration() {
     // Test basic component initialization
-    let cubesat = CubeSatProtocol::new(1);
+    let _cubesat = CubeSatProtocol::new(1);
     let _network = MeshNetwork::new();
     let _crypto = CryptoModule::new();
     let _telemetry = TelemetryProcessor::new();
@@ -40,7 +40,7 @@ fn test_spacecan_frame_lifecycle() {
 
 #[test]
 fn test_mesh_network_operations() {
-    let network = MeshNetwork::new();
+    let _network = MeshNetwork::new();
     
     // Basic network test
     println!("Mesh network initialized successfully");
@@ -48,7 +48,7 @@ fn test_mesh_network_operations() {
 
 #[test]
 fn test_cubesat_operations() {
-    let cubesat = CubeSatProtocol::new(1);
+    let _cubesat = CubeSatProtocol::new(1);
     
     // Basic cubesat test - just check it was created
     println!("CubeSat operations test passed");
@@ -122,7 +122,7 @@ fn test_error_handling_and_recovery() {
     
     // Test crypto with invalid data
     let crypto = CryptoModule::new();
-    let decrypt_result = crypto.decrypt(&vec![0xFF; 32]);
+    let decrypt_result = crypto.decrypt(&[0xFF; 32]);
     assert!(decrypt_result.is_err());
     
     println!("Error handling tests completed successfully");
@@ -145,4 +145,327 @@ fn test_performance_basic() {
     
     println!("Performance test: {:.2} frames/second", frames_per_second);
     assert!(frames_per_second > 100.0); // Should handle at least 100 frames per second
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_dashboard_aggregates_endpoint() {
+    use rustsat_esa::telemetry::{TelemetryData, TelemetryType, TelemetryValue};
+    use rustsat_esa::web::{api_routes, DashboardState};
+
+    let state = DashboardState::new();
+    {
+        let mut processor = state.telemetry_processor.lock().unwrap();
+        processor.initialize().unwrap();
+        for (i, temperature) in [20.0, 25.0, 30.0].iter().enumerate() {
+            processor.process_telemetry(TelemetryData {
+                timestamp: chrono::Utc::now(),
+                source_node: 1,
+                data_type: TelemetryType::Temperature,
+                value: TelemetryValue::Float(*temperature),
+                quality: 0.95,
+                sequence_number: i as u64,
+                raw_value: None,
+            }).unwrap();
+        }
+    }
+
+    let routes = api_routes(state);
+    let response = warp::test::request()
+        .method("GET")
+        .path("/api/aggregates/temperature")
+        .reply(&routes)
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["min"], 20.0);
+    assert_eq!(body["max"], 30.0);
+    assert_eq!(body["average"], 25.0);
+    assert_eq!(body["p50"], 25.0);
+    assert_eq!(body["p95"], 30.0);
+    assert_eq!(body["sample_count"], 3);
+
+    // Unknown types and known types without an aggregator
+    for data_type in ["warp_core", "payload"] {
+        let response = warp::test::request()
+            .method("GET")
+            .path(&format!("/api/aggregates/{}", data_type))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 404);
+    }
+}
+
+#[tokio::test]
+async fn test_dashboard_metrics_endpoint() {
+    use rustsat_esa::web::{api_routes, DashboardState};
+
+    rustsat_esa::record_message!();
+    let response = warp::test::request()
+        .method("GET")
+        .path("/metrics")
+        .reply(&api_routes(DashboardState::new()))
+        .await;
+
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let body = std::str::from_utf8(response.body()).unwrap();
+    assert!(body.contains("# TYPE rustsat_message_throughput gauge"));
+    assert!(body.lines().any(|line| line.starts_with("rustsat_error_rate ")));
+}
+
+#[tokio::test]
+async fn test_dashboard_streams_live_telemetry() {
+    use rustsat_esa::telemetry::{TelemetryData, TelemetryType, TelemetryValue};
+    use rustsat_esa::web::{api_routes, DashboardState};
+
+    let state = DashboardState::new();
+    state.telemetry_processor.lock().unwrap().initialize().unwrap();
+
+    let mut client = warp::test::ws()
+        .path("/ws/telemetry")
+        .handshake(api_routes(state.clone()))
+        .await
+        .expect("websocket handshake");
+
+    state.telemetry_processor.lock().unwrap().process_telemetry(TelemetryData {
+        timestamp: chrono::Utc::now(),
+        source_node: 7,
+        data_type: TelemetryType::Temperature,
+        value: TelemetryValue::Float(21.5),
+        quality: 0.9,
+        sequence_number: 1,
+        raw_value: None,
+    }).unwrap();
+
+    let message = client.recv().await.expect("live telemetry message");
+    let body: serde_json::Value = serde_json::from_str(message.to_str().unwrap()).unwrap();
+    assert_eq!(body["source_node"], 7);
+    assert_eq!(body["data_type"], "Temperature");
+    assert_eq!(body["sequence_number"], 1);
+}
+
+fn seeded_dashboard_state() -> rustsat_esa::web::DashboardState {
+    use rustsat_esa::simulation::ScenarioConfig;
+    use rustsat_esa::telemetry::{TelemetryData, TelemetryType, TelemetryValue};
+
+    let state = rustsat_esa::web::DashboardState::new();
+    *state.simulator.lock().unwrap() = SpaceSimulator::with_seed(42);
+    state.simulator.lock().unwrap().initialize_scenario(ScenarioConfig {
+        satellite_count: 2,
+        ground_station_count: 3,
+        space_weather_enabled: false,
+        ..ScenarioConfig::default()
+    }).unwrap();
+
+    let mut processor = state.telemetry_processor.lock().unwrap();
+    processor.initialize().unwrap();
+    let now = chrono::Utc::now();
+    for (i, data_type) in [TelemetryType::Temperature, TelemetryType::PowerStatus, TelemetryType::Temperature].into_iter().enumerate() {
+        processor.process_telemetry(TelemetryData {
+            timestamp: now + chrono::Duration::seconds(i as i64),
+            source_node: 1,
+            data_type,
+            value: TelemetryValue::Float(20.0 + i as f64),
+            quality: 0.95,
+            sequence_number: i as u64,
+            raw_value: None,
+        }).unwrap();
+    }
+    drop(processor);
+    state
+}
+
+#[tokio::test]
+async fn test_dashboard_satellites_reflect_simulator() {
+    use rustsat_esa::web::api_routes;
+
+    let state = seeded_dashboard_state();
+    let positions = state.simulator.lock().unwrap().get_satellite_positions();
+
+    let response = warp::test::request()
+        .method("GET")
+        .path("/api/satellites")
+        .reply(&api_routes(state))
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    let satellites = body.as_array().unwrap();
+    assert_eq!(satellites.len(), 2);
+    for satellite in satellites {
+        let id = satellite["id"].as_u64().unwrap() as u32;
+        assert_eq!(satellite["position"][0], positions[&id].latitude);
+        assert_eq!(satellite["position"][2], positions[&id].altitude);
+    }
+}
+
+#[tokio::test]
+async fn test_dashboard_topology_reflects_simulator() {
+    use rustsat_esa::web::api_routes;
+
+    let response = warp::test::request()
+        .method("GET")
+        .path("/api/topology")
+        .reply(&api_routes(seeded_dashboard_state()))
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    // Two satellites and three ground stations
+    assert_eq!(body["nodes"].as_array().unwrap().len(), 5);
+    assert!(!body["edges"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_dashboard_telemetry_for_satellite() {
+    use rustsat_esa::web::api_routes;
+
+    let routes = api_routes(seeded_dashboard_state());
+
+    let response = warp::test::request().method("GET").path("/api/telemetry/1").reply(&routes).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["satellite_id"], 1);
+    assert!(body["position"]["altitude"].as_f64().unwrap() > 300.0);
+    // Latest point of each type, oldest first
+    let data = body["data"].as_array().unwrap();
+    assert_eq!(data.len(), 2);
+    assert_eq!(data[0]["data_type"], "PowerStatus");
+    assert_eq!(data[1]["data_type"], "Temperature");
+    assert_eq!(data[1]["sequence_number"], 2);
+
+    let response = warp::test::request().method("GET").path("/api/telemetry/99").reply(&routes).await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_dashboard_system_status() {
+    use rustsat_esa::web::{api_routes, SatelliteStatus};
+
+    let state = seeded_dashboard_state();
+    state.update_satellite_status(9, SatelliteStatus {
+        id: 9,
+        name: "CubeSat-Reported".to_string(),
+        position: (0.0, 0.0, 400.0),
+        battery_level: 0.5,
+        temperature: 20.0,
+        signal_strength: 0.8,
+        last_contact: chrono::Utc::now(),
+        status: "Operational".to_string(),
+    });
+
+    let response = warp::test::request()
+        .method("GET")
+        .path("/api/status")
+        .reply(&api_routes(state))
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["system_status"], "Operational");
+    assert_eq!(body["active_satellites"], 3);
+    assert_eq!(body["ground_stations"], 3);
+    assert_eq!(body["telemetry_points"], 3);
+    assert!(body["uptime_seconds"].as_i64().unwrap() >= 0);
+}
+
+#[tokio::test]
+async fn test_dashboard_telemetry_history_range() {
+    use chrono::SecondsFormat;
+    use rustsat_esa::telemetry::{TelemetryData, TelemetryType, TelemetryValue};
+    use rustsat_esa::web::{api_routes, DashboardState};
+
+    let state = DashboardState::new();
+    let base = chrono::Utc::now() - chrono::Duration::hours(1);
+    let at = |minutes: i64| base + chrono::Duration::minutes(minutes);
+    {
+        let mut processor = state.telemetry_processor.lock().unwrap();
+        processor.initialize().unwrap();
+        // Inserted out of order, with another type, another node and a shared timestamp mixed in
+        for (minute, sequence_number, node, data_type) in [
+            (3, 3, 1, TelemetryType::Temperature),
+            (0, 0, 1, TelemetryType::Temperature),
+            (2, 2, 1, TelemetryType::PowerStatus),
+            (1, 1, 1, TelemetryType::Temperature),
+            (1, 10, 1, TelemetryType::PowerStatus),
+            (2, 2, 2, TelemetryType::Temperature),
+            (4, 4, 1, TelemetryType::Temperature),
+        ] {
+            processor.process_telemetry(TelemetryData {
+                timestamp: at(minute),
+                source_node: node,
+                data_type,
+                value: TelemetryValue::Float(minute as f64),
+                quality: 0.95,
+                sequence_number,
+                raw_value: None,
+            }).unwrap();
+        }
+    }
+    state.simulator.lock().unwrap().add_node(1);
+    let routes = api_routes(state);
+    let time = |minutes: i64| at(minutes).to_rfc3339_opts(SecondsFormat::Millis, true);
+    let get = |path: String| {
+        let routes = routes.clone();
+        async move { warp::test::request().method("GET").path(&path).reply(&routes).await }
+    };
+    let sequence_numbers = |body: &serde_json::Value| -> Vec<u64> {
+        body["points"].as_array().unwrap().iter().map(|p| p["sequence_number"].as_u64().unwrap()).collect()
+    };
+
+    // `from` is inclusive and `to` exclusive
+    let response = get(format!("/api/telemetry/1/history?from={}&to={}", time(1), time(4))).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(sequence_numbers(&body), vec![1, 10, 2, 3]);
+
+    let response = get(format!("/api/telemetry/1/history?from={}&type=temperature", time(1))).await;
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(sequence_numbers(&body), vec![1, 3, 4]);
+
+    // Paging by cursor neither repeats nor skips points that share a timestamp
+    let mut pages = Vec::new();
+    let mut path = "/api/telemetry/1/history?limit=2".to_string();
+    loop {
+        let body: serde_json::Value = serde_json::from_slice(get(path.clone()).await.body()).unwrap();
+        pages.push(sequence_numbers(&body));
+        match body["next_cursor"].as_str() {
+            Some(cursor) => path = format!("/api/telemetry/1/history?limit=2&cursor={}", cursor),
+            None => break,
+        }
+    }
+    assert_eq!(pages, vec![vec![0, 1], vec![10, 2], vec![3, 4]]);
+
+    for path in [
+        "/api/telemetry/1/history?from=yesterday".to_string(),
+        format!("/api/telemetry/1/history?from={}&to={}", time(3), time(1)),
+        "/api/telemetry/1/history?cursor=not-a-cursor".to_string(),
+    ] {
+        assert_eq!(get(path).await.status(), 400);
+    }
+    assert_eq!(get("/api/telemetry/2/history".to_string()).await.status(), 404);
+}
+
+#[tokio::test]
+async fn test_dashboard_graceful_shutdown() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+    let (address, server) = rustsat_esa::web::start_dashboard(0, shutdown_rx).await.unwrap();
+    assert_ne!(address.port(), 0);
+    let server = tokio::spawn(server);
+
+    let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+    stream.write_all(b"GET /api/status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "unexpected response: {}", response);
+    assert!(response.contains("\"active_satellites\""));
+
+    shutdown_tx.send(()).unwrap();
+    tokio::time::timeout(std::time::Duration::from_secs(5), server)
+        .await
+        .expect("server did not shut down")
+        .unwrap();
+    assert!(tokio::net::TcpStream::connect(address).await.is_err());
+}